@@ -1,11 +1,55 @@
+use crate::cache::audit::AuditLog;
+use crate::cache::index::{IndexEntry, ProjectIndex};
+use crate::cache::retention;
+use crate::cache::sizes;
+use crate::cache::snapshot::{STATS_FILE, Snapshot};
+use crate::cache::tags::TagStore;
+use crate::cleaner::risk;
+use crate::cleaner::scope::CleanScope;
+use crate::cleaner::targer_cleaner::{CleanMode, CleanOptions, CleanupResult, TargetCleaner};
+use crate::cleaner::verify;
+use crate::cli::BuildMark;
 use crate::config::Config;
-use crate::scanner::rust_project_scaner::RustProjectScanner;
-use crate::ui::{CleanerTUI, UI};
+use crate::engine::{Engine, EngineCommand, EngineEvent};
+use crate::notify;
+use crate::progress::{NoProgress, ProgressSink, Stage};
+use crate::query::Query;
+use crate::remote::RemoteHost;
+use crate::report::stats::{self, Stats};
+use crate::report::{Report, ReportFormat, server, terminal, usage};
+use crate::scanner::cross::{self, CrossTool};
+use crate::scanner::heuristics;
+use crate::scanner::rust_project::RustProject;
+use crate::scanner::rust_project_scaner::ScanDiagnostic;
+use crate::scanner::target_finder::{self, Availability, TargetFinder, low_free_space};
+use crate::scanner::users;
+use crate::scanner::watcher::ProjectWatcher;
+use crate::scanner::wsl;
+use crate::scanner::{build_time, compilers};
+use crate::size::format_bytes;
+use crate::summary::RunSummary;
+use crate::tasks::Cancelled;
+use crate::ui::tty::{self, TtyMode};
+use crate::ui::{CleanerTUI, CliUI, FuzzyPicker, UI, cli, metric_note, size_label};
+use crate::vfs;
 use std::error::Error;
+use std::io::{IsTerminal, Read, Write, stdout};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::watch;
 
 pub struct App {
     config: Config,
-    scanner: RustProjectScanner,
+    /// Cleaner.toml (or YAML/JSON variant) the configuration was loaded from, if it exists
+    config_path: PathBuf,
+    /// Scans and cleans on behalf of every command
+    engine: Engine,
+    /// Expression choosing the projects to clean instead of the stale ones
+    selection: Option<Query>,
+    /// Why the last scan left projects out, when it hit a scan limit
+    scan_limited: Option<String>,
+    /// Projects the last scan could not read
+    scan_diagnostics: Vec<ScanDiagnostic>,
 }
 
 impl App {
@@ -16,86 +60,1222 @@ impl App {
         // let config_path = dirs::home_dir()
         //     .unwrap_or_else(|| PathBuf::from("."))
         //     .join("Cleaner.toml");
-        let config_path = dir.join("Cleaner.toml");
+        let config_path = Config::find_config_file(&dir);
 
-        println!("current {:?}", config_path);
         if let Err(e) = config.load_cleaner_config(&config_path) {
-            eprintln!("Warning: Failed to load Cleaner.toml: {}", e);
+            eprintln!("Warning: Failed to load {}: {}", config_path.display(), e);
+        }
+        // Cleaning with the wrong paths and thresholds is worse than not starting
+        if let Some(profile) = &config.profile
+            && !config.profiles.contains(profile)
+        {
+            return Err(match config.profiles.as_slice() {
+                [] => format!(
+                    "Unknown profile '{}': {} defines no profiles",
+                    profile,
+                    config_path.display()
+                ),
+                profiles => format!(
+                    "Unknown profile '{}' (defined: {})",
+                    profile,
+                    profiles.join(", ")
+                ),
+            }
+            .into());
         }
 
-        println!("Config pass {:?}", config);
+        if config.protect_own_projects {
+            config.protected_projects = RustProject::own_projects();
+        }
+        if config.scan_wsl {
+            match wsl::home_roots() {
+                Ok(roots) => {
+                    for root in roots {
+                        if !config.search_paths.contains(&root) {
+                            config.search_paths.push(root);
+                        }
+                    }
+                }
+                Err(e) => eprintln!("Warning: {}", e),
+            }
+        }
 
-        let scanner = RustProjectScanner::new_with_ignores(
-            &config.search_paths,
-            &config.exclude_patterns,
-            &config.ignore_paths,
-        )?;
+        // Sizes seen in a simulation are not the disk's
+        sizes::set_enabled(config.size_cache && !vfs::current().is_virtual());
+        target_finder::set_exact_sizes(config.exact_sizes);
+        let network_fs = config.network_fs.unwrap_or_else(|| {
+            let network: Vec<&PathBuf> = config
+                .search_paths
+                .iter()
+                .filter(|path| target_finder::is_network_filesystem(path))
+                .collect();
+            if let Some(path) = network.first() {
+                eprintln!(
+                    "{} is on a network filesystem; scanning reads less metadata (scan.network_fs)",
+                    path.display()
+                );
+            }
+            !network.is_empty()
+        });
+        target_finder::set_network_fs(network_fs);
 
-        Ok(App { config, scanner })
+        let engine = Engine::new(config.clone())?;
+
+        Ok(App {
+            config,
+            config_path,
+            engine,
+            selection: None,
+            scan_limited: None,
+            scan_diagnostics: Vec::new(),
+        })
     }
 
-    pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
-        use std::io::{Write, stdout};
-        use std::thread;
-        use std::time::Duration;
+    /// Ages out the tool's own records in the cache directory (`[retention]`)
+    ///
+    /// Permanently removes old trash, so it is only called while the instance lock is held.
+    pub fn prune_own_records(&self) {
+        // What a simulation removes is not on disk
+        if vfs::current().is_virtual() {
+            return;
+        }
+        let config = &self.config;
+        match retention::prune(&config.retention) {
+            Ok(pruned) if pruned.trash > 0 => eprintln!(
+                "Removed {} targets ({}) trashed more than {} days ago (retention.trash)",
+                pruned.trash,
+                format_bytes(pruned.trash_bytes),
+                config.retention.trash
+            ),
+            Ok(_) => {}
+            Err(e) => eprintln!("Warning: Could not prune old records: {}", e),
+        }
+    }
 
-        // Print header once
-        println!("Scanning for Rust projects...");
+    /// Scans and cleans the given remote host over SSH instead of the local machine
+    pub fn with_remote(mut self, remote: RemoteHost) -> Self {
+        self.engine = self.engine.with_remote(remote);
+        self
+    }
 
-        // (1) setup animation thread
-        let (tx, rx) = std::sync::mpsc::channel();
-
-        let loading_indicator = thread::spawn(move || {
-            // let crab_frames = [
-            //     "🦀 ▽   ",
-            //     "~ ▽   ",
-            //     "  ▽   ",
-            //     "  ▽ ~ ",
-            //     "  ▽   ",
-            //     "▽ ~   ",
-            //     "  ▽   ",
-            // ];
-            let crab_frames = [
-                "   ╲╱   🦀  ▽     / ╲  ",
-                "   ╲╱   🦀  ~     / ╲  ",
-                "   ╲╱   🦀       / ╲  ",
-                "   ╲╱   🦀      / ╲  ",
-                "   ╲╱   🦀     / ╲  ",
-                "   ╲╱   🦀    / ╲  ",
-                "   ╲╱   🦀   / ╲  ",
-                "   ╲╱   ▽ ~      / ╲  ",
-                "   ╲╱     ▽     / ╲  ",
-            ];
-            let mut i = 0;
-            while rx.try_recv().is_err() {
-                print!("\rScanning... {}", crab_frames[i]);
-                stdout().flush().unwrap();
+    /// Uses the project list maintained by `clear-target watch` instead of scanning
+    pub fn with_cached_projects(mut self) -> Self {
+        self.engine = self.engine.with_cached_projects();
+        self
+    }
+
+    /// Selects the projects matching an expression instead of the stale ones
+    pub fn with_selection(mut self, selection: Query) -> Self {
+        self.selection = Some(selection);
+        self
+    }
+
+    /// Scans once, then keeps the cached project list current until interrupted
+    pub fn run_watch(&mut self) -> Result<(), Box<dyn Error>> {
+        // Usually runs as a service, where a progress bar would only clutter the log
+        let projects = self.engine.scanner().find_projects(&mut NoProgress)?;
+        for note in self.engine.scanner().take_notes() {
+            println!("{}", note);
+        }
+        if let Err(e) = sizes::save() {
+            eprintln!("Warning: Failed to save the size cache: {}", e);
+        }
+        println!("Tracking {} projects; press Ctrl+C to stop", projects.len());
+        if let Some(address) = self.config.report_address {
+            server::spawn(address, self.config.stale_threshold)?;
+            println!("Serving target usage per user on http://{}/", address);
+        }
+
+        ProjectWatcher::new(
+            &self.config.search_paths,
+            &self.config.exclude_patterns,
+            projects,
+        )
+        .with_free_space_trigger(self.config.auto_clean_when_free_below)
+        .run(&mut |message| println!("{}", message))
+    }
+
+    /// Runs the interactive terminal UI
+    ///
+    /// Without a terminal on stdout, the projects are listed as plain text instead and
+    /// the stale ones are offered for cleaning on the terminal, if one is attached.
+    pub fn run(&mut self) -> Result<RunSummary, Box<dyn Error>> {
+        let mode = tty::detect();
+        if mode != TtyMode::Interactive {
+            return self.run_plain(mode);
+        }
+        let mut projects = self.scan_projects()?;
+        Self::apply_stored_data(&mut projects);
+        if std::io::stdin().is_terminal()
+            && let Err(e) = self
+                .offer_exclude_suggestions()
+                .and_then(|_| self.offer_denied_excludes())
+        {
+            if e.is::<Cancelled>() {
+                return Err(e);
+            }
+            eprintln!("Warning: Failed to update Cleaner.toml: {}", e);
+        }
+
+        // (4) start ratatui
+        let mut tui = CleanerTUI::new(projects, self.engine.clone())?;
+        if let Some(selection) = &self.selection {
+            tui.preselect(selection);
+        }
+        if let Some(reason) = &self.scan_limited {
+            tui.show_scan_limit(reason);
+        }
+        tui.add_scan_diagnostics(std::mem::take(&mut self.scan_diagnostics));
+        tui.run()?;
+
+        Ok(tui.take_summary())
+    }
+
+    /// Lists the projects as plain text and cleans the stale ones once confirmed on the
+    /// terminal; with no terminal to ask, nothing is cleaned
+    fn run_plain(&mut self, mode: TtyMode) -> Result<RunSummary, Box<dyn Error>> {
+        let mut ui = CliUI::new(self.config.dry_run);
+        let projects = self.scan_headless(&mut ui)?;
+        let color = cli::use_color(stdout().is_terminal());
+        for line in projects
+            .iter()
+            .filter_map(|project| Self::listed_line(project, color))
+        {
+            println!("{}", line);
+        }
+
+        if mode == TtyMode::Batch {
+            eprintln!(
+                "No terminal attached; nothing cleaned (run `clear-target clean` to clean the stale targets)"
+            );
+            return Ok(RunSummary {
+                dry_run: self.config.dry_run,
+                ..RunSummary::default()
+            });
+        }
+        let selected: Vec<bool> = projects
+            .iter()
+            .map(|project| self.select_for_cleanup(project, None, false))
+            .collect();
+        let (count, size) = projects
+            .iter()
+            .zip(&selected)
+            .filter(|(_, selected)| **selected)
+            .filter_map(|(project, _)| project.target_info.as_ref())
+            .fold((0, 0), |(count, size), target_info| {
+                (count + 1, size + target_info.size_bytes)
+            });
+        let chosen = if self.selection.is_some() {
+            "matching"
+        } else {
+            "stale"
+        };
+        if count == 0 {
+            eprintln!("No {} targets to clean", chosen);
+            return Ok(RunSummary {
+                dry_run: self.config.dry_run,
+                ..RunSummary::default()
+            });
+        }
+        let dry_run = if self.config.dry_run {
+            " as a dry run"
+        } else {
+            ""
+        };
+        let question = format!(
+            "Clean {} {} targets ({}){}? [y/N] ",
+            count,
+            chosen,
+            format_bytes(size),
+            dry_run
+        );
+        let answer = self.engine.tasks().prompt(move || tty::ask(&question))?;
+        if !answer.eq_ignore_ascii_case("y") {
+            return Ok(RunSummary::cancelled(self.config.dry_run));
+        }
+
+        let command = EngineCommand::CleanRequested {
+            projects,
+            selected,
+            options: CleanOptions::from_config(&self.config),
+        };
+        self.engine
+            .handle(command, &mut |event| ui.on_event(event))?;
+        ui.run()?;
+        let result = ui
+            .take_cleanup_result()
+            .ok_or("Cleanup finished without a result")?;
+        Ok(RunSummary::from_cleanup(result, self.config.dry_run))
+    }
+
+    /// Offers to ignore directories that slowed the scan down without holding any projects
+    ///
+    /// Accepted directories are added to `[ignore] paths` in Cleaner.toml for future scans.
+    fn offer_exclude_suggestions(&mut self) -> Result<(), Box<dyn Error>> {
+        let suggestions = self.engine.scanner().exclude_suggestions();
+        if suggestions.is_empty() {
+            return Ok(());
+        }
+
+        let mut accepted = Vec::new();
+        for suggestion in suggestions {
+            print!(
+                "Scanning {} took {:.1}s ({} entries) and found no projects. Ignore it from now on? [y/N] ",
+                suggestion.path.display(),
+                suggestion.elapsed.as_secs_f64(),
+                suggestion.entries
+            );
+            std::io::stdout().flush()?;
+            if self.read_answer()?.eq_ignore_ascii_case("y") {
+                accepted.push(suggestion.path);
+            }
+        }
+        if accepted.is_empty() {
+            return Ok(());
+        }
+
+        Config::add_ignore_paths(&self.config_path, &accepted)?;
+        println!(
+            "Added {} paths to [ignore] in {}",
+            accepted.len(),
+            self.config_path.display()
+        );
+        Ok(())
+    }
+
+    /// Lets the user list the directories the scan was not permitted to read, or ignore them
+    fn offer_denied_excludes(&mut self) -> Result<(), Box<dyn Error>> {
+        let denied = self.engine.scanner().denied_paths();
+        if denied.is_empty() {
+            return Ok(());
+        }
+
+        loop {
+            print!("[l]ist the skipped directories, [i]gnore them from now on, or press Enter: ");
+            std::io::stdout().flush()?;
+            match self.read_answer()?.as_str() {
+                "l" | "L" => {
+                    for path in &denied {
+                        println!("  {}", path.display());
+                    }
+                }
+                "i" | "I" => {
+                    Config::add_ignore_paths(&self.config_path, &denied)?;
+                    println!(
+                        "Added {} paths to [ignore] in {}",
+                        denied.len(),
+                        self.config_path.display()
+                    );
+                    return Ok(());
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Reads a line answering a question from stdin, trimmed; Ctrl+C cancels the run
+    fn read_answer(&self) -> Result<String, Box<dyn Error>> {
+        self.engine.tasks().prompt(|| {
+            let mut answer = String::new();
+            std::io::stdin().read_line(&mut answer)?;
+            Ok(answer.trim().to_string())
+        })
+    }
+
+    /// Warns about directories the scan was not permitted to read
+    fn report_denied(&self) {
+        let denied = self.engine.scanner().denied_paths().len();
+        if denied > 0 {
+            eprintln!("{} directories skipped: permission denied", denied);
+        }
+    }
+
+    /// Runs the graphical UI
+    #[cfg(feature = "gui")]
+    pub fn run_gui(&mut self) -> Result<RunSummary, Box<dyn Error>> {
+        let mut projects = self.scan_projects()?;
+        Self::apply_stored_data(&mut projects);
+        projects.retain(|project| self.config.show_empty || project.target_info.is_some());
+
+        let mut gui = crate::ui::CleanerGUI::new(projects, self.engine.clone())?;
+        gui.run()?;
+
+        Ok(gui.take_summary())
+    }
+
+    /// Prints the projects found by a scan, as JSON or a plain table
+    ///
+    /// With `stream`, each project is printed as soon as it is sized instead.
+    pub fn run_list(&mut self, json: bool, stream: bool) -> Result<(), Box<dyn Error>> {
+        if stream {
+            return self.list_streaming(json);
+        }
+        let mut projects = self.scan_headless(&mut CliUI::new(self.config.dry_run))?;
+        Self::apply_stored_data(&mut projects);
+
+        if let Some(selection) = &self.selection {
+            projects.retain(|project| selection.matches(project));
+        }
+
+        if json {
+            println!("{}", serde_json::to_string(&projects)?);
+            return Ok(());
+        }
+
+        let color = cli::use_color(stdout().is_terminal());
+        let print_project = |project: &RustProject| {
+            if let Some(line) = Self::listed_line(project, color) {
+                println!("{}", line);
+            }
+        };
+        if self.config.all_users {
+            let owned: Vec<(String, &RustProject)> = projects
+                .iter()
+                .map(|project| (users::owner_name(&project.path), project))
+                .collect();
+            for total in usage::by_user(&projects) {
+                println!(
+                    "{}: {} in {} targets, {} stale",
+                    total.user,
+                    format_bytes(total.size_bytes),
+                    total.targets,
+                    format_bytes(total.stale_bytes)
+                );
+                owned
+                    .iter()
+                    .filter(|(user, _)| *user == total.user)
+                    .for_each(|(_, project)| print_project(project));
+            }
+        } else {
+            projects.iter().for_each(print_project);
+        }
+
+        if projects.iter().any(Self::built_with_cross) {
+            Self::note_cross_volumes();
+        }
+        Ok(())
+    }
+
+    /// Prints each project as soon as the scan has sized it, in the order found
+    ///
+    /// Projects are dropped once printed, so no list is held however many there are;
+    /// as JSON, each is an object on a line of its own.
+    fn list_streaming(&mut self, json: bool) -> Result<(), Box<dyn Error>> {
+        let mut ui = CliUI::new(self.config.dry_run);
+        let color = cli::use_color(stdout().is_terminal());
+        let mut built_with_cross = false;
+        self.engine
+            .handle(EngineCommand::ScanRequested, &mut |event| match event {
+                EngineEvent::ProjectFound(project) => {
+                    if project.target_info.is_none()
+                        || self
+                            .selection
+                            .as_ref()
+                            .is_some_and(|selection| !selection.matches(&project))
+                    {
+                        return;
+                    }
+                    built_with_cross |= Self::built_with_cross(&project);
+                    let line = if json {
+                        serde_json::to_string(&project).ok()
+                    } else {
+                        Self::listed_line(&project, color)
+                    };
+                    if let Some(line) = line {
+                        ui.print(&line);
+                    }
+                }
+                event => ui.on_event(event),
+            })?;
+        ui.run()?;
+        self.scan_limited = ui.scan_limited().map(str::to_string);
+        self.report_denied();
+
+        if built_with_cross {
+            Self::note_cross_volumes();
+        }
+        Ok(())
+    }
+
+    /// Line of `list` for a project with a target directory: size, path and the size
+    /// of build outputs kept next to the target
+    fn listed_line(project: &RustProject, color: bool) -> Option<String> {
+        let target_info = project.target_info.as_ref()?;
+        // Pad by the plain width so escape codes do not count towards it
+        let width = size_label(target_info).chars().count();
+        let approximate = if target_info.breakdown.is_estimated() {
+            "~"
+        } else {
+            ""
+        };
+        let companions = [
+            (target_info.web_size, "web outputs"),
+            (target_info.embedded_size, "embedded toolchain"),
+            (target_info.profiling_size, "profiling data"),
+        ];
+        let extra: String = companions
+            .iter()
+            .filter(|(size, _)| *size > 0)
+            .map(|(size, label)| format!("  (+{} in {})", format_bytes(*size), label))
+            .collect();
+        Some(format!(
+            "{}{}{}  {}{}",
+            " ".repeat(10usize.saturating_sub(width)),
+            approximate,
+            cli::paint_size(target_info.size_bytes, color),
+            project.display_path(),
+            extra
+        ))
+    }
+
+    /// Whether part of a project's target was built with cross
+    fn built_with_cross(project: &RustProject) -> bool {
+        project
+            .target_info
+            .iter()
+            .flat_map(|target_info| &target_info.cross_builds)
+            .any(|build| build.tool == CrossTool::Cross)
+    }
+
+    /// Points out the Docker volumes cross keeps builds in, if there are any
+    fn note_cross_volumes() {
+        let volumes = cross::volumes();
+        if !volumes.is_empty() {
+            eprintln!(
+                "cross also keeps builds in Docker volumes: {} (remove with `cross-util volumes remove`)",
+                volumes.join(", ")
+            );
+        }
+    }
+
+    /// Prints a report of the scanned targets and remembers its totals for the next one
+    ///
+    /// With a `format`, the report is written to `output` (or `target-report.<ext>`) instead.
+    pub fn run_report(
+        &mut self,
+        format: Option<ReportFormat>,
+        output: Option<&Path>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut projects = self.scan_headless(&mut CliUI::new(self.config.dry_run))?;
+        Self::apply_stored_data(&mut projects);
+
+        let previous = Snapshot::load().unwrap_or_else(|e| {
+            eprintln!("Warning: Failed to load the previous report: {}", e);
+            None
+        });
+        let report = Report::new(&projects, previous).with_compilers(&projects);
+        match format {
+            Some(format) => {
+                let default_output = PathBuf::from(format!("target-report.{}", format.extension()));
+                let output = output.unwrap_or(&default_output);
+                std::fs::write(output, format.render(&report))?;
+                println!("Wrote report to {}", output.display());
+            }
+            None => print!(
+                "{}",
+                terminal::render(&report, cli::use_color(stdout().is_terminal()))
+            ),
+        }
+
+        if let Err(e) = report.current.save() {
+            eprintln!("Warning: Failed to save report totals: {}", e);
+        }
+        Ok(())
+    }
+
+    /// Prints totals over every project found, with the change since the last run
+    pub fn run_stats(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut ui = CliUI::new(self.config.dry_run);
+        let projects = self.scan_headless_all(&mut ui)?;
+
+        let previous = Snapshot::load_from(STATS_FILE).unwrap_or_else(|e| {
+            eprintln!("Warning: Failed to load the previous stats: {}", e);
+            None
+        });
+        let mut stats = Stats::new(&projects, &self.config.search_paths, previous);
+        if self.config.all_users {
+            stats = stats.with_users(&projects);
+        }
+        print!(
+            "{}",
+            stats::render(&stats, cli::use_color(stdout().is_terminal()))
+        );
+
+        if let Err(e) = stats.report.current.save_to(STATS_FILE) {
+            eprintln!("Warning: Failed to save stats totals: {}", e);
+        }
+        Ok(())
+    }
+
+    /// Lists the indexed projects that were moved or deleted, or all of them, and
+    /// with `purge` forgets the former everywhere in the cache directory
+    pub fn run_index(purge: bool, all: bool) -> Result<(), Box<dyn Error>> {
+        let mut index = ProjectIndex::load()?;
+        let print = |path: &Path, entry: &IndexEntry| {
+            println!(
+                "{}  first seen {}, last seen {}  {}",
+                entry.name,
+                entry.first_seen.format("%Y-%m-%d"),
+                entry.last_seen.format("%Y-%m-%d"),
+                path.display()
+            );
+        };
+        if all {
+            for (path, entry) in index.entries() {
+                print(path, entry);
+            }
+            return Ok(());
+        }
+
+        let gone: Vec<PathBuf> = index
+            .gone()
+            .into_iter()
+            .map(|(path, entry)| {
+                print(path, entry);
+                path.to_path_buf()
+            })
+            .collect();
+        if gone.is_empty() {
+            println!("Every indexed project was found by the last scans");
+            return Ok(());
+        }
+        if !purge {
+            println!(
+                "{} projects were moved or deleted; run `clear-target index --purge` to forget them",
+                gone.len()
+            );
+            return Ok(());
+        }
+
+        let display_paths: Vec<String> = index
+            .remove(&gone)
+            .into_iter()
+            .map(|entry| entry.display_path)
+            .collect();
+        let mut tags = TagStore::load()?;
+        for display_path in &display_paths {
+            tags.forget(display_path);
+        }
+        tags.save()?;
+        AuditLog::forget(&display_paths)?;
+        index.save()?;
+        println!("Forgot {} moved or deleted projects", gone.len());
+        Ok(())
+    }
+
+    /// Records the outcome of the last build of the project containing `path`
+    pub fn run_mark(build: BuildMark, path: Option<&Path>) -> Result<(), Box<dyn Error>> {
+        let start = match path {
+            Some(path) => std::fs::canonicalize(path)?,
+            None => std::env::current_dir()?,
+        };
+        let root = RustProject::find_root(&start)
+            .ok_or_else(|| format!("No Cargo.toml found in {} or its parents", start.display()))?;
+        let project = RustProject::from_path(&root)?;
+
+        let mut store = TagStore::load()?;
+        store.set_health(&project, build.health());
+        store.save()?;
+        match build.health() {
+            Some(health) => println!("Marked {} as {}", project.name, health.label()),
+            None => println!("Cleared the build mark of {}", project.name),
+        }
+        Ok(())
+    }
+
+    /// Prints the active skip patterns, with the reason for each built-in one
+    pub fn print_skip_patterns(&self) {
+        if self.config.skip_patterns.is_empty() {
+            println!("No directories are skipped by pattern");
+        }
+        let width = self
+            .config
+            .skip_patterns
+            .iter()
+            .map(String::len)
+            .max()
+            .unwrap_or(0);
+        for pattern in &self.config.skip_patterns {
+            let reason = heuristics::reason(pattern).unwrap_or("configured in [scan] skip");
+            println!("{:width$}  {}", pattern, reason);
+        }
+    }
+
+    /// Opens the terminal UI directly in the trash restore view
+    pub fn run_restore(&mut self) -> Result<RunSummary, Box<dyn Error>> {
+        tty::require_interactive("restore")?;
+        let mut tui = CleanerTUI::new(Vec::new(), self.engine.clone())?;
+        tui.open_restore_view()?;
+        tui.run()?;
+
+        Ok(RunSummary::cancelled(self.config.dry_run))
+    }
+
+    /// Cleans every stale target directory (or those matching the selection) without starting the UI
+    ///
+    /// When `paths_from` is given, the project list is read from that file
+    /// (or stdin for `-`) and the scanner is bypassed entirely. The summary is sent to
+    /// the webhook and mail address from `[notify]`, if any.
+    pub fn run_clean(
+        &mut self,
+        paths_from: Option<&Path>,
+        only: Option<CleanScope>,
+        stream: bool,
+    ) -> Result<RunSummary, Box<dyn Error>> {
+        let summary = self.clean_headless(paths_from, only, false, stream)?;
+        notify::send(&self.config, &summary);
+        Ok(summary)
+    }
 
-                i = (i + 1) % crab_frames.len();
-                thread::sleep(Duration::from_millis(120));
+    /// Cleans every project listed in a plan saved from the UI, stale or not
+    pub fn run_apply(&mut self, plan: &Path) -> Result<RunSummary, Box<dyn Error>> {
+        let summary = self.clean_headless(Some(plan), None, true, false)?;
+        notify::send(&self.config, &summary);
+        Ok(summary)
+    }
+
+    /// Cleans the scanned or listed projects; `every_listed` chooses all listed ones
+    /// instead of only the stale ones, `stream` cleans them while the scan goes on
+    fn clean_headless(
+        &mut self,
+        paths_from: Option<&Path>,
+        only: Option<CleanScope>,
+        every_listed: bool,
+        stream: bool,
+    ) -> Result<RunSummary, Box<dyn Error>> {
+        // Without an explicit choice of projects, only clean when space is actually short
+        if let Some(threshold) = self.config.auto_clean_when_free_below
+            && paths_from.is_none()
+            && self.selection.is_none()
+            && low_free_space(&self.config.search_paths, threshold).is_empty()
+        {
+            println!(
+                "At least {} free on every search path (settings.auto_clean_when_free_below); nothing to do",
+                format_bytes(threshold)
+            );
+            return Ok(RunSummary {
+                dry_run: self.config.dry_run,
+                ..RunSummary::default()
+            });
+        }
+
+        if stream {
+            return self.clean_streaming(only);
+        }
+
+        let mut ui = CliUI::new(self.config.dry_run);
+        let mut projects = match paths_from {
+            Some(source) => Self::load_projects_from(source)?,
+            None => self.scan_headless(&mut ui)?,
+        };
+        Self::apply_stored_data(&mut projects);
+        if paths_from.is_some() {
+            // Listed projects bypass the engine, which fills this in while scanning
+            for project in &mut projects {
+                project.update_status(&self.config)?;
             }
+        }
 
-            // After stop → clear animation
-            print!("\rScanning complete!     \n");
-            stdout().flush().unwrap();
+        let selected: Vec<bool> = projects
+            .iter()
+            .map(|project| self.select_for_cleanup(project, only, every_listed))
+            .collect();
+
+        if self.config.verify_sizes && only.is_none() {
+            for change in verify::verify_sizes(&mut projects, &selected) {
+                eprintln!(
+                    "Warning: {} changed since the scan: {} now, was {}",
+                    change.project,
+                    format_bytes(change.current),
+                    format_bytes(change.scanned)
+                );
+            }
+        }
+
+        let mut options = CleanOptions::from_config(&self.config).with_scope(only);
+        if paths_from.is_some() {
+            // Listed projects were chosen explicitly, wherever they are
+            options
+                .roots
+                .extend(projects.iter().map(|project| project.path.clone()));
+        }
+        let command = EngineCommand::CleanRequested {
+            projects,
+            selected,
+            options,
+        };
+        self.engine
+            .handle(command, &mut |event| ui.on_event(event))?;
+        ui.run()?;
+        let result = ui
+            .take_cleanup_result()
+            .ok_or("Cleanup finished without a result")?;
+        Ok(RunSummary::from_cleanup(result, self.config.dry_run))
+    }
+
+    /// Cleans each chosen target as soon as the scan has sized it
+    ///
+    /// Projects are dropped once handled, so memory stays flat however many the search
+    /// paths hold. The scan still writes them to the project cache as it goes; sizes are
+    /// not checked again before cleaning since they were just taken.
+    fn clean_streaming(&mut self, only: Option<CleanScope>) -> Result<RunSummary, Box<dyn Error>> {
+        let mut ui = CliUI::new(self.config.dry_run);
+        let options = CleanOptions::from_config(&self.config).with_scope(only);
+        let mut result = CleanupResult::default();
+        let mut failure = None;
+        self.engine
+            .handle(EngineCommand::ScanRequested, &mut |event| match event {
+                EngineEvent::ProjectFound(project) => {
+                    if failure.is_some() || !self.select_for_cleanup(&project, only, false) {
+                        return;
+                    }
+                    let command = EngineCommand::CleanRequested {
+                        projects: vec![*project],
+                        selected: vec![true],
+                        options: options.clone(),
+                    };
+                    // The sizing bar stays up; each cleanup is a single target
+                    let cleaned = self.engine.handle(command, &mut |event| match event {
+                        EngineEvent::Progress { .. } => {}
+                        EngineEvent::CleanFinished(cleanup) => result.merge(cleanup),
+                        event => ui.on_event(event),
+                    });
+                    failure = cleaned.err();
+                }
+                event => ui.on_event(event),
+            })?;
+        if let Some(e) = failure {
+            return Err(e);
+        }
+        self.scan_limited = ui.scan_limited().map(str::to_string);
+        self.report_denied();
+
+        ui.on_event(EngineEvent::CleanFinished(result));
+        ui.run()?;
+        let result = ui
+            .take_cleanup_result()
+            .ok_or("Cleanup finished without a result")?;
+        Ok(RunSummary::from_cleanup(result, self.config.dry_run))
+    }
+
+    /// Whether a headless cleanup cleans a project's target, warning about what it chose
+    ///
+    /// `every_listed` chooses every available target instead of only the stale ones.
+    fn select_for_cleanup(
+        &self,
+        project: &RustProject,
+        only: Option<CleanScope>,
+        every_listed: bool,
+    ) -> bool {
+        let Some(target_info) = &project.target_info else {
+            return false;
+        };
+        if let Some(reason) = target_info.availability.reason() {
+            eprintln!("Skipping {}: target is {}", project.display_path(), reason);
+            return false;
+        }
+        let chosen = match &self.selection {
+            Some(selection) => selection.matches(project),
+            None => every_listed || target_info.is_stale,
+        };
+        if !chosen {
+            return false;
+        }
+
+        if project.orphan {
+            eprintln!(
+                "Warning: {} is an orphaned target; no Cargo.toml is left to rebuild it from",
+                target_info.path.display()
+            );
+        }
+        if only.is_none()
+            && project.host.is_none()
+            && let Some(rebuild) = risk::long_rebuild(&target_info.path, self.config.rebuild_budget)
+        {
+            eprintln!(
+                "Warning: rebuilding {} likely takes about {} (settings.rebuild_budget)",
+                project.display_path(),
+                build_time::format_estimate(rebuild)
+            );
+        }
+        true
+    }
+
+    /// Opens the fuzzy finder over the cleanable targets and cleans the projects picked
+    pub fn run_pick(&mut self) -> Result<RunSummary, Box<dyn Error>> {
+        tty::require_interactive("pick")?;
+        let mut projects = self.scan_projects()?;
+        Self::apply_stored_data(&mut projects);
+        projects.retain(|project| {
+            project
+                .target_info
+                .as_ref()
+                .is_some_and(|t| t.availability == Availability::Available)
         });
+        if projects.is_empty() {
+            println!("No cleanable target directories found.");
+            return Ok(RunSummary::cancelled(self.config.dry_run));
+        }
+
+        let mode = match CleanMode::from_config(&self.config) {
+            CleanMode::DryRun => "dry run",
+            CleanMode::Delete => "live",
+            CleanMode::Trash => "live, to trash",
+        };
+        let Some(picks) = FuzzyPicker::new(&projects, mode)?.run()? else {
+            return Ok(RunSummary::cancelled(self.config.dry_run));
+        };
+
+        let mut selected = vec![false; projects.len()];
+        for index in picks {
+            selected[index] = true;
+        }
+        let mut ui = CliUI::new(self.config.dry_run);
+        let command = EngineCommand::CleanRequested {
+            projects,
+            selected,
+            options: CleanOptions::from_config(&self.config),
+        };
+        self.engine
+            .handle(command, &mut |event| ui.on_event(event))?;
+        ui.run()?;
+        let result = ui
+            .take_cleanup_result()
+            .ok_or("Cleanup finished without a result")?;
+        Ok(RunSummary::from_cleanup(result, self.config.dry_run))
+    }
+
+    /// Analyzes the project containing the current directory and offers to clean it
+    pub fn run_here(&mut self) -> Result<RunSummary, Box<dyn Error>> {
+        let cwd = std::env::current_dir()?;
+        let root = RustProject::find_root(&cwd)
+            .ok_or_else(|| format!("No Cargo.toml found in {} or its parents", cwd.display()))?;
+        let mut project = RustProject::from_path(&root)?;
+        Self::apply_stored_data(std::slice::from_mut(&mut project));
+
+        println!("Project: {} ({})", project.name, root.display());
+
+        let Ok(Some(target_info)) = TargetFinder::find_target_info(&root) else {
+            println!("No target directory to clean.");
+            return Ok(RunSummary {
+                dry_run: self.config.dry_run,
+                ..RunSummary::default()
+            });
+        };
+        // Staleness, protection and keep markers, the way a scan fills them in. Cleaning
+        // the project it runs in is what this command is for; the tool's own source stays protected
+        if self.config.protect_own_projects {
+            self.config.protected_projects = RustProject::source_projects();
+        }
+        project.target_info = Some(target_info);
+        project.update_status(&self.config)?;
+        let target_info = project
+            .target_info
+            .take()
+            .ok_or("Target directory vanished")?;
+
+        println!(
+            "Target:  {} ({}, {}{})",
+            target_info.path.display(),
+            size_label(&target_info),
+            if target_info.is_stale {
+                "stale"
+            } else {
+                "fresh"
+            },
+            metric_note(self.config.staleness_metric, target_info.metric)
+        );
+        if let Some(reason) = target_info.availability.reason() {
+            return Err(
+                format!("Refusing to clean {}: target is {}", root.display(), reason).into(),
+            );
+        }
+        if let Some(note) = target_info.breakdown.estimate_note() {
+            println!(
+                "         Size {} (scan.exact_sizes measures every file)",
+                note
+            );
+        }
+        let usage = compilers::usage(&target_info.path);
+        if let Some(version) = &usage.last_version {
+            let others = usage.compilers.saturating_sub(1);
+            if others > 0 {
+                println!("Rustc:   {} (and {} other compilers)", version, others);
+            } else {
+                println!("Rustc:   {}", version);
+            }
+        }
+        if !usage.outdated.is_empty() {
+            let size: u64 = usage
+                .outdated
+                .iter()
+                .map(|p| TargetFinder::path_size(p))
+                .sum();
+            println!(
+                "         {} from older compilers (`clear-target clean --only old-compilers`)",
+                format_bytes(size)
+            );
+        }
+        println!(
+            "Mode:    {}",
+            match CleanMode::from_config(&self.config) {
+                CleanMode::DryRun => "dry run",
+                CleanMode::Delete => "live",
+                CleanMode::Trash => "live (move to trash)",
+            }
+        );
+        println!();
+
+        let entries = TargetFinder::list_target_entries(&target_info.path)?;
+        if entries.is_empty() {
+            println!("Target directory is empty.");
+            return Ok(RunSummary {
+                dry_run: self.config.dry_run,
+                ..RunSummary::default()
+            });
+        }
+
+        for (i, entry) in entries.iter().enumerate() {
+            println!(
+                "  {:>2}) {:>10}  {}",
+                i + 1,
+                format_bytes(entry.size_bytes),
+                entry.name
+            );
+        }
+        println!();
+
+        let Some(chosen) = self.prompt_entry_selection(entries.len())? else {
+            return Ok(RunSummary::cancelled(self.config.dry_run));
+        };
+        let chosen: Vec<_> = chosen.into_iter().map(|i| entries[i].clone()).collect();
+
+        // The project is cleaned from within, even when outside the search paths
+        let options = CleanOptions::from_config(&self.config).with_root(root.clone());
+        let mut ui = CliUI::new(self.config.dry_run);
+        let result = TargetCleaner::clean_target_entries(
+            &root,
+            &target_info,
+            &chosen,
+            &options,
+            &mut |event| {
+                ui.advance(Stage::Cleaning, event.done, event.total, None);
+                ui.on_event(EngineEvent::TargetCleaned(event));
+            },
+        )?;
+        ui.run()?;
+        Ok(RunSummary::from_cleanup(result, self.config.dry_run))
+    }
+
+    /// Asks which target entries to clean; returns `None` if the user quits
+    fn prompt_entry_selection(
+        &self,
+        entry_count: usize,
+    ) -> Result<Option<Vec<usize>>, Box<dyn Error>> {
+        loop {
+            print!("Clean [a]ll, entries by number (e.g. 1,3), or [q]uit: ");
+            stdout().flush()?;
+
+            // Closing stdin reads as an empty answer, which quits
+            let answer = self.read_answer()?;
+            let answer = answer.as_str();
+            match answer {
+                "" | "q" | "Q" => return Ok(None),
+                "a" | "A" => return Ok(Some((0..entry_count).collect())),
+                _ => {}
+            }
+
+            let picked: Result<Vec<usize>, _> = answer
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|part| !part.is_empty())
+                .map(|part| match part.parse::<usize>() {
+                    Ok(n) if (1..=entry_count).contains(&n) => Ok(n - 1),
+                    _ => Err(part.to_string()),
+                })
+                .collect();
+
+            match picked {
+                Ok(mut indices) => {
+                    indices.sort_unstable();
+                    indices.dedup();
+                    return Ok(Some(indices));
+                }
+                Err(part) => println!("Invalid entry: {}", part),
+            }
+        }
+    }
+
+    /// Fills in the tags and cleanup history recorded in earlier sessions
+    fn apply_stored_data(projects: &mut [RustProject]) {
+        match TagStore::load() {
+            Ok(store) => store.apply(projects),
+            Err(e) => eprintln!("Warning: Failed to load project tags: {}", e),
+        }
+        match AuditLog::load() {
+            Ok(entries) => AuditLog::apply(&entries, projects),
+            Err(e) => eprintln!("Warning: Failed to load the cleanup history: {}", e),
+        }
+    }
+
+    /// Reads newline-separated project paths from a file or stdin (`-`)
+    fn load_projects_from(source: &Path) -> Result<Vec<RustProject>, Box<dyn Error>> {
+        let content = if source == Path::new("-") {
+            let mut buffer = String::new();
+            std::io::stdin().read_to_string(&mut buffer)?;
+            buffer
+        } else {
+            std::fs::read_to_string(source)?
+        };
+
+        let mut projects = Vec::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let project_path = Path::new(line);
+            match RustProject::from_path(project_path) {
+                Ok(project) => match TargetFinder::find_target_info(project_path) {
+                    Ok(Some(target_info)) => projects.push(project.with_target_info(target_info)),
+                    Ok(None) => {
+                        eprintln!("Skipping {}: no target directory", project_path.display())
+                    }
+                    Err(e) => eprintln!("Skipping {}: {}", project_path.display(), e),
+                },
+                Err(e) => eprintln!("Skipping {}: {}", project_path.display(), e),
+            }
+        }
+
+        if let Err(e) = sizes::save() {
+            eprintln!("Warning: Failed to save the size cache: {}", e);
+        }
+        Ok(projects)
+    }
+
+    /// Scans for projects through the engine, reporting progress through the headless output
+    fn scan_headless(&mut self, ui: &mut CliUI) -> Result<Vec<RustProject>, Box<dyn Error>> {
+        let mut projects = self.scan_headless_all(ui)?;
+        projects.retain(|project| project.target_info.is_some());
+        Ok(projects)
+    }
+
+    /// Like [`Self::scan_headless`], keeping projects without a target directory
+    fn scan_headless_all(&mut self, ui: &mut CliUI) -> Result<Vec<RustProject>, Box<dyn Error>> {
+        self.engine
+            .handle(EngineCommand::ScanRequested, &mut |event| {
+                ui.on_event(event)
+            })?;
+        self.scan_limited = ui.scan_limited().map(str::to_string);
+        self.report_denied();
+        Ok(ui.take_projects())
+    }
+
+    /// Scans for projects through the engine while showing a loading animation
+    fn scan_projects(&mut self) -> Result<Vec<RustProject>, Box<dyn Error>> {
+        // Print header once
+        println!("Scanning for Rust projects...");
+
+        // (1) start the animation, fed by sizing progress
+        let (progress, progress_rx) = watch::channel((0, 0));
+        let loading_indicator = self.engine.tasks().spawn(Self::animate(progress_rx));
 
         // (2) do your scanning
-        let projects = self.scanner.find_projects()?;
+        let mut projects = Vec::new();
+        let mut limited = None;
+        let mut diagnostics = Vec::new();
+        let scanned = self
+            .engine
+            .handle(EngineCommand::ScanRequested, &mut |event| match event {
+                EngineEvent::Progress {
+                    stage: Stage::Sizing,
+                    done,
+                    total,
+                    ..
+                } => {
+                    progress.send_replace((done, total));
+                }
+                EngineEvent::ProjectFound(project) => projects.push(*project),
+                EngineEvent::ProjectSkipped(diagnostic) => diagnostics.push(diagnostic),
+                EngineEvent::ScanFinished {
+                    limited: reason, ..
+                } => limited = reason,
+                _ => {}
+            });
 
         // (3) stop animation
-        tx.send(()).ok();
-        loading_indicator.join().ok();
+        drop(progress);
+        self.engine.tasks().wait(loading_indicator).ok();
+        scanned?;
 
         println!(
             "Found {} Rust projects with target directories",
-            projects.len()
+            projects.iter().filter(|p| p.target_info.is_some()).count()
         );
+        if let Some(reason) = &limited {
+            eprintln!("Warning: Scan limit reached: {}", reason);
+        }
+        for diagnostic in &diagnostics {
+            eprintln!(
+                "Warning: Skipped {}: {}",
+                diagnostic.path.display(),
+                diagnostic.reason
+            );
+        }
+        self.scan_limited = limited;
+        self.scan_diagnostics = diagnostics;
+        self.report_denied();
 
-        // (4) start ratatui
-        let mut tui = CleanerTUI::new(projects, self.config.clone())?;
-        tui.run()?;
+        Ok(projects)
+    }
 
-        Ok(())
+    /// Shows the scanning animation until the progress sender is dropped
+    async fn animate(mut progress: watch::Receiver<(usize, usize)>) {
+        // let crab_frames = [
+        //     "🦀 ▽   ",
+        //     "~ ▽   ",
+        //     "  ▽   ",
+        //     "  ▽ ~ ",
+        //     "  ▽   ",
+        //     "▽ ~   ",
+        //     "  ▽   ",
+        // ];
+        let crab_frames = [
+            "   ╲╱   🦀  ▽     / ╲  ",
+            "   ╲╱   🦀  ~     / ╲  ",
+            "   ╲╱   🦀       / ╲  ",
+            "   ╲╱   🦀      / ╲  ",
+            "   ╲╱   🦀     / ╲  ",
+            "   ╲╱   🦀    / ╲  ",
+            "   ╲╱   🦀   / ╲  ",
+            "   ╲╱   ▽ ~      / ╲  ",
+            "   ╲╱     ▽     / ╲  ",
+        ];
+        let mut ticks = tokio::time::interval(Duration::from_millis(120));
+        let mut i = 0;
+        loop {
+            tokio::select! {
+                _ = ticks.tick() => {}
+                changed = progress.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                    continue;
+                }
+            }
+
+            let (done, total) = *progress.borrow();
+            if total > 0 {
+                print!("\rScanning... {} {}/{}", crab_frames[i], done, total);
+            } else {
+                print!("\rScanning... {}", crab_frames[i]);
+            }
+            stdout().flush().ok();
+            i = (i + 1) % crab_frames.len();
+        }
+
+        // After stop → clear animation
+        print!("\rScanning complete!                  \n");
+        stdout().flush().ok();
     }
 }
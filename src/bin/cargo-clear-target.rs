@@ -0,0 +1,46 @@
+//! Entry point for `cargo clear-target`
+//!
+//! Cargo runs `cargo-clear-target clear-target <args>` for it. This drops the extra
+//! argument and hands over to the `clear-target` binary installed next to this one,
+//! telling it that it runs as a cargo subcommand so that it defaults to the crate in
+//! the current directory instead of scanning everything.
+
+use std::env;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process::{Command, ExitCode};
+
+fn main() -> ExitCode {
+    let mut args: Vec<OsString> = env::args_os().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == "clear-target") {
+        args.remove(0);
+    }
+
+    // Fall back to the PATH when the binaries were installed apart
+    let name = format!("clear-target{}", env::consts::EXE_SUFFIX);
+    let program = env::current_exe()
+        .ok()
+        .map(|exe| exe.with_file_name(&name))
+        .filter(|path| path.is_file())
+        .unwrap_or_else(|| PathBuf::from(&name));
+    let mut command = Command::new(&program);
+    command.args(args).env("CLEAR_TARGET_VIA_CARGO", "1");
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+
+        // Only returns on failure; replacing the process keeps the terminal and signals
+        let error = command.exec();
+        eprintln!("Error: failed to run {}: {}", program.display(), error);
+        ExitCode::FAILURE
+    }
+    #[cfg(not(unix))]
+    match command.status() {
+        Ok(status) => ExitCode::from(status.code().unwrap_or(1) as u8),
+        Err(error) => {
+            eprintln!("Error: failed to run {}: {}", program.display(), error);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::cache::cache_dir;
+use crate::cleaner::scope::CleanScope;
+use crate::scanner::rust_project::RustProject;
+
+/// Name of the file cleanups are logged to, inside the cache directory
+const AUDIT_FILE: &str = "audit.jsonl";
+
+/// One live cleanup of a project's target, as logged
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// When the target was cleaned
+    pub time: DateTime<Local>,
+    /// Display path of the project (`host:path` for remote projects)
+    pub project: String,
+    /// Bytes freed
+    pub freed_bytes: u64,
+    /// Part of the target that was cleaned, when not all of it
+    #[serde(default)]
+    pub scope: Option<String>,
+}
+
+/// Summary of the logged cleanups of one project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CleanupHistory {
+    /// When the project was last cleaned
+    pub last_cleaned: DateTime<Local>,
+    /// Bytes freed by the last cleanup
+    pub last_freed: u64,
+    /// Number of logged cleanups
+    pub times_cleaned: usize,
+}
+
+/// Append-only log of the live cleanups performed, kept in the cache directory
+pub struct AuditLog;
+
+impl AuditLog {
+    /// Appends a cleanup of a project to the log
+    pub fn record(
+        project: &RustProject,
+        freed_bytes: u64,
+        scope: Option<CleanScope>,
+    ) -> Result<(), Box<dyn Error>> {
+        let entry = AuditEntry {
+            time: Local::now(),
+            project: project.display_path(),
+            freed_bytes,
+            scope: scope.map(|scope| scope.label().to_string()),
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(Self::file()?)?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        Ok(())
+    }
+
+    /// Reads all logged cleanups, oldest first, skipping lines that cannot be parsed
+    pub fn load() -> Result<Vec<AuditEntry>, Box<dyn Error>> {
+        let path = Self::file()?;
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        Ok(fs::read_to_string(path)?
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// Drops the logged cleanups of the given projects (by display path) from the log
+    pub fn forget(projects: &[String]) -> Result<(), Box<dyn Error>> {
+        let kept: Vec<AuditEntry> = Self::load()?
+            .into_iter()
+            .filter(|entry| !projects.contains(&entry.project))
+            .collect();
+        Self::write(&kept)
+    }
+
+    /// Drops the cleanups logged longer than `max_age` ago, returning how many were dropped
+    pub fn prune(max_age: Duration) -> Result<usize, Box<dyn Error>> {
+        if !Self::file()?.exists() {
+            return Ok(0);
+        }
+        let cutoff = Local::now() - max_age;
+        let entries = Self::load()?;
+        let total = entries.len();
+        let kept: Vec<AuditEntry> = entries
+            .into_iter()
+            .filter(|entry| entry.time >= cutoff)
+            .collect();
+        if kept.len() < total {
+            Self::write(&kept)?;
+        }
+        Ok(total - kept.len())
+    }
+
+    /// Replaces the log with the given entries
+    fn write(entries: &[AuditEntry]) -> Result<(), Box<dyn Error>> {
+        let mut content = String::new();
+        for entry in entries {
+            content.push_str(&serde_json::to_string(entry)?);
+            content.push('\n');
+        }
+        fs::write(Self::file()?, content)?;
+        Ok(())
+    }
+
+    /// Fills in the cleanup history of the given projects
+    pub fn apply(entries: &[AuditEntry], projects: &mut [RustProject]) {
+        let mut histories: HashMap<&str, CleanupHistory> = HashMap::new();
+        for entry in entries {
+            histories
+                .entry(&entry.project)
+                .and_modify(|history| {
+                    history.last_cleaned = entry.time;
+                    history.last_freed = entry.freed_bytes;
+                    history.times_cleaned += 1;
+                })
+                .or_insert_with(|| CleanupHistory {
+                    last_cleaned: entry.time,
+                    last_freed: entry.freed_bytes,
+                    times_cleaned: 1,
+                });
+        }
+
+        for project in projects {
+            project.history = histories.get(project.display_path().as_str()).cloned();
+        }
+    }
+
+    fn file() -> Result<PathBuf, Box<dyn Error>> {
+        Ok(cache_dir()?.join(AUDIT_FILE))
+    }
+}
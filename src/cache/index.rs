@@ -0,0 +1,141 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::cache::cache_dir;
+use crate::scanner::rust_project::RustProject;
+
+/// Name of the file the project index is stored in, inside the cache directory
+const INDEX_FILE: &str = "index.json";
+
+/// Complete scans in a row a project can be missing from before it counts as gone
+const MISSED_SCANS_BEFORE_GONE: u32 = 3;
+
+/// When a project was first and last found, as recorded in the index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    /// Project name when last found
+    pub name: String,
+    /// Path the project was found under, as used by tags and the cleanup history
+    pub display_path: String,
+    pub first_seen: DateTime<Local>,
+    pub last_seen: DateTime<Local>,
+    /// Complete scans of the project's search path since it was last found
+    #[serde(default)]
+    pub missed_scans: u32,
+}
+
+/// Every local project ever found, keyed by canonical path, persisted across runs
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProjectIndex {
+    projects: BTreeMap<PathBuf, IndexEntry>,
+    /// Canonical paths found during the current scan
+    #[serde(skip)]
+    seen: HashSet<PathBuf>,
+}
+
+impl ProjectIndex {
+    /// Loads the index, starting empty if nothing was saved yet
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        let path = Self::file()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Writes the index back to the cache directory
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        fs::write(Self::file()?, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Records that a local project was found by the current scan
+    pub fn record(&mut self, project: &RustProject) {
+        if project.host.is_some() || project.orphan {
+            return;
+        }
+        let path = fs::canonicalize(&project.path).unwrap_or_else(|_| project.path.clone());
+        let now = Local::now();
+        let entry = self
+            .projects
+            .entry(path.clone())
+            .or_insert_with(|| IndexEntry {
+                name: project.name.clone(),
+                display_path: project.display_path(),
+                first_seen: now,
+                last_seen: now,
+                missed_scans: 0,
+            });
+        entry.name = project.name.clone();
+        entry.display_path = project.display_path();
+        entry.last_seen = now;
+        entry.missed_scans = 0;
+        self.seen.insert(path);
+    }
+
+    /// Records that a project the scan found but could not read still exists
+    pub fn keep(&mut self, path: &Path) {
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        if let Some(entry) = self.projects.get_mut(&path) {
+            entry.missed_scans = 0;
+        }
+        self.seen.insert(path);
+    }
+
+    /// Counts a missed scan for every indexed project the scan covered but did not find
+    ///
+    /// Only call this after a complete scan. Projects it did not cover, e.g. under a
+    /// search path that was since removed or ignored, are left alone.
+    pub fn finish_scan(&mut self, covered: impl Fn(&Path) -> bool) {
+        for (path, entry) in &mut self.projects {
+            if !self.seen.contains(path) && covered(path) {
+                entry.missed_scans += 1;
+            }
+        }
+        self.seen.clear();
+    }
+
+    /// Projects that were moved or deleted: their Cargo.toml is gone, or several
+    /// complete scans in a row did not find them
+    pub fn gone(&self) -> Vec<(&Path, &IndexEntry)> {
+        self.projects
+            .iter()
+            .filter(|(path, entry)| {
+                !path.join("Cargo.toml").is_file() || entry.missed_scans >= MISSED_SCANS_BEFORE_GONE
+            })
+            .map(|(path, entry)| (path.as_path(), entry))
+            .collect()
+    }
+
+    /// All indexed projects, by canonical path
+    pub fn entries(&self) -> impl Iterator<Item = (&Path, &IndexEntry)> {
+        self.projects
+            .iter()
+            .map(|(path, entry)| (path.as_path(), entry))
+    }
+
+    /// Forgets the projects no scan has found for longer than `max_age`, returning how many
+    pub fn prune(&mut self, max_age: Duration) -> usize {
+        let cutoff = Local::now() - max_age;
+        let before = self.projects.len();
+        self.projects.retain(|_, entry| entry.last_seen >= cutoff);
+        before - self.projects.len()
+    }
+
+    /// Forgets the given projects, returning their entries
+    pub fn remove(&mut self, paths: &[PathBuf]) -> Vec<IndexEntry> {
+        paths
+            .iter()
+            .filter_map(|path| self.projects.remove(path))
+            .collect()
+    }
+
+    fn file() -> Result<PathBuf, Box<dyn Error>> {
+        Ok(cache_dir()?.join(INDEX_FILE))
+    }
+}
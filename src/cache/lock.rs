@@ -0,0 +1,79 @@
+use std::error::Error;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+use crate::cache::cache_dir;
+
+/// Name of the lock file inside the cache directory
+const LOCK_FILE_NAME: &str = "clear-target.lock";
+
+/// Guard preventing two instances from cleaning at the same time
+///
+/// Holds an exclusive lock on a file in the cache directory, which also records the
+/// owning process id for the message shown to a second instance. The operating system
+/// releases the lock when the process exits, however it exits, so a lock is never
+/// left behind; the file itself stays.
+#[derive(Debug)]
+pub struct InstanceLock {
+    path: PathBuf,
+    /// Open while the lock is held; closing it releases the lock
+    file: File,
+}
+
+impl InstanceLock {
+    /// Acquires the lock in the cache directory
+    pub fn acquire() -> Result<Self, Box<dyn Error>> {
+        Self::acquire_at(&cache_dir()?.join(LOCK_FILE_NAME))
+    }
+
+    /// Acquires the lock at the given path, failing if another process holds it
+    fn acquire_at(path: &Path) -> Result<Self, Box<dyn Error>> {
+        // Never truncated before the lock is held: it names the current owner
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let owner = Self::read_owner(&mut file)
+                    .map_or_else(String::new, |pid| format!(" (pid {})", pid));
+                return Err(format!("Another instance{} is already running", owner).into());
+            }
+            Err(TryLockError::Error(e)) => {
+                return Err(format!("Could not lock {}: {}", path.display(), e).into());
+            }
+        }
+
+        file.set_len(0)?;
+        file.rewind()?;
+        writeln!(file, "{}", std::process::id())?;
+        file.flush()?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+        })
+    }
+
+    /// Reads the process id the holder of the lock recorded, if it has yet
+    ///
+    /// Windows does not let others read a locked file, so there it is never known.
+    fn read_owner(file: &mut File) -> Option<u32> {
+        let mut content = String::new();
+        file.read_to_string(&mut content).ok()?;
+        content.trim().parse().ok()
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        // The file stays: removing it would let a process that opened it just before
+        // lock a file no longer found under `path`, next to one that locks a new one
+        if let Err(e) = self.file.unlock() {
+            eprintln!("Warning: Failed to release {}: {}", self.path.display(), e);
+        }
+    }
+}
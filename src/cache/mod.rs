@@ -0,0 +1,23 @@
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+pub mod audit;
+pub mod index;
+pub mod lock;
+pub mod projects;
+pub mod retention;
+pub mod sizes;
+pub mod snapshot;
+pub mod tags;
+
+/// Returns the tool's cache directory, creating it if necessary
+pub fn cache_dir() -> Result<PathBuf, Box<dyn Error>> {
+    let base = dirs::cache_dir()
+        .or_else(|| dirs::home_dir().map(|home| home.join(".cache")))
+        .ok_or("Could not determine a cache directory")?;
+
+    let dir = base.join("rust_clear_target");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
@@ -0,0 +1,77 @@
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::cache::cache_dir;
+use crate::scanner::rust_project::RustProject;
+
+/// Name of the file the project list is stored in, inside the cache directory
+///
+/// One project per line, so scans can append projects as they are sized.
+const PROJECTS_FILE: &str = "projects.jsonl";
+
+/// Project list kept up to date by `clear-target watch` and by every local scan
+pub struct ProjectCache;
+
+impl ProjectCache {
+    /// Loads the cached project list, if one has been written
+    pub fn load() -> Result<Option<Vec<RustProject>>, Box<dyn Error>> {
+        let path = Self::file()?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let mut projects = Vec::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            projects.push(serde_json::from_str(&line?)?);
+        }
+        Ok(Some(projects))
+    }
+
+    /// Replaces the cached project list
+    pub fn save(projects: &[RustProject]) -> Result<(), Box<dyn Error>> {
+        let mut writer = Self::writer()?;
+        for project in projects {
+            writer.append(project)?;
+        }
+        writer.finish()
+    }
+
+    /// Starts replacing the cached project list one project at a time
+    pub fn writer() -> Result<ProjectCacheWriter, Box<dyn Error>> {
+        // Write a sibling file first so readers never see a half-written list
+        let path = Self::file()?;
+        let partial = path.with_extension("jsonl.tmp");
+        Ok(ProjectCacheWriter {
+            file: BufWriter::new(File::create(&partial)?),
+            partial,
+            path,
+        })
+    }
+
+    fn file() -> Result<PathBuf, Box<dyn Error>> {
+        Ok(cache_dir()?.join(PROJECTS_FILE))
+    }
+}
+
+/// Cached project list being written; it replaces the previous one once finished
+pub struct ProjectCacheWriter {
+    file: BufWriter<File>,
+    partial: PathBuf,
+    path: PathBuf,
+}
+
+impl ProjectCacheWriter {
+    /// Appends a project to the list
+    pub fn append(&mut self, project: &RustProject) -> Result<(), Box<dyn Error>> {
+        writeln!(self.file, "{}", serde_json::to_string(project)?)?;
+        Ok(())
+    }
+
+    /// Replaces the cached list with the projects appended
+    pub fn finish(mut self) -> Result<(), Box<dyn Error>> {
+        self.file.flush()?;
+        fs::rename(&self.partial, &self.path)?;
+        Ok(())
+    }
+}
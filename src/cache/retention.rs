@@ -0,0 +1,73 @@
+use std::error::Error;
+use std::time::Duration;
+
+use crate::cache::audit::AuditLog;
+use crate::cache::index::ProjectIndex;
+use crate::cache::sizes;
+use crate::cleaner::trash::Trash;
+use crate::config::Retention;
+
+/// What was pruned from the tool's own records
+#[derive(Debug, Default)]
+pub struct Pruned {
+    /// Cleanups dropped from the audit log
+    pub history: usize,
+    /// Targets permanently removed from the trash
+    pub trash: usize,
+    /// Space freed by removing them
+    pub trash_bytes: u64,
+    /// Projects forgotten by the index
+    pub index: usize,
+    /// Cached target sizes forgotten
+    pub sizes: usize,
+}
+
+/// Ages out the records the tool keeps in its cache directory
+///
+/// Each kind of record is pruned on its own, so one that cannot be read does not keep
+/// the others growing; the first error is returned after trying them all.
+pub fn prune(retention: &Retention) -> Result<Pruned, Box<dyn Error>> {
+    let mut pruned = Pruned::default();
+    let mut first_error = None;
+    let mut note = |result: Result<(), Box<dyn Error>>| {
+        if let Err(e) = result {
+            first_error.get_or_insert(e);
+        }
+    };
+
+    if let Some(max_age) = days(retention.history) {
+        note(AuditLog::prune(max_age).map(|count| pruned.history = count));
+    }
+    if let Some(max_age) = days(retention.trash) {
+        note(
+            Trash::open()
+                .and_then(|trash| trash.prune(max_age))
+                .map(|entries| {
+                    pruned.trash = entries.len();
+                    pruned.trash_bytes = entries.iter().map(|entry| entry.size_bytes).sum();
+                }),
+        );
+    }
+    if let Some(max_age) = days(retention.index) {
+        note(ProjectIndex::load().and_then(|mut index| {
+            pruned.index = index.prune(max_age);
+            if pruned.index > 0 {
+                index.save()?;
+            }
+            Ok(())
+        }));
+    }
+    if let Some(max_age) = days(retention.sizes) {
+        note(sizes::prune(max_age).map(|count| pruned.sizes = count));
+    }
+
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(pruned),
+    }
+}
+
+/// Retention in days as a duration, or `None` for records kept forever
+fn days(days: u64) -> Option<Duration> {
+    (days > 0).then(|| Duration::from_secs(days.saturating_mul(24 * 60 * 60)))
+}
@@ -0,0 +1,224 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::cache::cache_dir;
+use crate::scanner::target_finder::{SizeBreakdown, UNIT_DIRS, exact_sizes};
+
+/// Name of the file sizes are stored in, inside the cache directory
+const SIZES_FILE: &str = "sizes.json";
+/// Levels of a target read for its fingerprint, enough for `<triple>/<profile>`
+const FINGERPRINT_DEPTH: usize = 3;
+
+/// How often the time an entry was last used is updated
+const USE_STAMP_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Size of a target directory as of a given state of its top levels
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SizeEntry {
+    /// Newest modification time (nanoseconds since the epoch) of the target and its direct children
+    mtime: u128,
+    /// Number of direct children of the target
+    entry_count: u64,
+    /// Computed size in bytes
+    size_bytes: u64,
+    /// Computed split of the size between profiles and docs
+    #[serde(default)]
+    breakdown: SizeBreakdown,
+    /// When a scan last used the entry (seconds since the epoch)
+    #[serde(default = "now_secs")]
+    used: u64,
+}
+
+/// Previously computed target sizes, so unchanged targets skip re-sizing
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SizeCache {
+    entries: HashMap<PathBuf, SizeEntry>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+static CACHE: Mutex<Option<SizeCache>> = Mutex::new(None);
+
+/// Turns the size cache on or off for the rest of the run
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns the size breakdown of a target directory, computing it only if the target changed
+///
+/// A target counts as unchanged while the newest mtime of the directory and its direct
+/// children (`debug`, `release`, ...) and the number of those children stay the same.
+/// Cargo rewrites files in those directories on every build, so this catches rebuilds
+/// without walking the whole tree.
+pub fn cached_size(
+    path: &Path,
+    compute: impl FnOnce() -> Result<SizeBreakdown, Box<dyn Error>>,
+) -> Result<SizeBreakdown, Box<dyn Error>> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return compute();
+    }
+    let Some((mtime, entry_count)) = fingerprint(path) else {
+        return compute();
+    };
+
+    let mut guard = CACHE.lock().unwrap();
+    let cache = guard.get_or_insert_with(|| SizeCache::load().unwrap_or_default());
+    if let Some(entry) = cache.entries.get_mut(path)
+        && entry.mtime == mtime
+        && entry.entry_count == entry_count
+        // Entries written before breakdowns were recorded have none
+        && entry.breakdown.total() == entry.size_bytes
+        // Nor file counts, which every non-empty target has
+        && (entry.breakdown.files > 0 || entry.size_bytes == 0)
+        // An estimate does not do once exact sizes are asked for
+        && !(exact_sizes() && entry.breakdown.is_estimated())
+    {
+        // Stamping every use would rewrite the cache on every run
+        let now = now_secs();
+        if now.saturating_sub(entry.used) >= USE_STAMP_INTERVAL.as_secs() {
+            entry.used = now;
+            cache.dirty = true;
+        }
+        return Ok(entry.breakdown);
+    }
+    drop(guard);
+
+    let breakdown = compute()?;
+    store(path, mtime, entry_count, breakdown);
+    Ok(breakdown)
+}
+
+/// Computes the size breakdown of a target directory anew, replacing any cached one
+pub fn refresh(
+    path: &Path,
+    compute: impl FnOnce() -> Result<SizeBreakdown, Box<dyn Error>>,
+) -> Result<SizeBreakdown, Box<dyn Error>> {
+    let breakdown = compute()?;
+    if ENABLED.load(Ordering::Relaxed)
+        && let Some((mtime, entry_count)) = fingerprint(path)
+    {
+        store(path, mtime, entry_count, breakdown);
+    }
+    Ok(breakdown)
+}
+
+fn store(path: &Path, mtime: u128, entry_count: u64, breakdown: SizeBreakdown) {
+    let mut guard = CACHE.lock().unwrap();
+    let cache = guard.get_or_insert_with(|| SizeCache::load().unwrap_or_default());
+    cache.entries.insert(
+        path.to_path_buf(),
+        SizeEntry {
+            mtime,
+            entry_count,
+            size_bytes: breakdown.total(),
+            breakdown,
+            used: now_secs(),
+        },
+    );
+    cache.dirty = true;
+}
+
+/// Writes newly computed sizes to the cache directory
+pub fn save() -> Result<(), Box<dyn Error>> {
+    let mut guard = CACHE.lock().unwrap();
+    if let Some(cache) = guard.as_mut()
+        && cache.dirty
+    {
+        // Forget targets that have since been removed
+        cache.entries.retain(|path, _| path.exists());
+        fs::write(SizeCache::file()?, serde_json::to_string(&*cache)?)?;
+        cache.dirty = false;
+    }
+    Ok(())
+}
+
+/// Forgets the sizes no scan has used for longer than `max_age`, returning how many
+pub fn prune(max_age: Duration) -> Result<usize, Box<dyn Error>> {
+    let cutoff = now_secs().saturating_sub(max_age.as_secs());
+    let mut guard = CACHE.lock().unwrap();
+    let cache = guard.get_or_insert_with(|| SizeCache::load().unwrap_or_default());
+    let before = cache.entries.len();
+    cache.entries.retain(|_, entry| entry.used >= cutoff);
+    let pruned = before - cache.entries.len();
+    if pruned > 0 {
+        cache.dirty = true;
+        drop(guard);
+        save()?;
+    }
+    Ok(pruned)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+/// Newest mtime among the top levels of a target, and the number of entries in them
+///
+/// Reads down to `<triple>/<profile>/` and counts the entries of the per-unit
+/// directories found there (`deps`, `incremental`, ...) without reading their times:
+/// `cargo check` and `cargo test` add files only there, which also updates the time of
+/// the directory itself.
+fn fingerprint(path: &Path) -> Option<(u128, u64)> {
+    let mtime_of = |metadata: fs::Metadata| {
+        metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|since| since.as_nanos())
+    };
+
+    let mut newest = mtime_of(fs::metadata(path).ok()?)?;
+    let mut entry_count = 0;
+    let mut level = vec![path.to_path_buf()];
+    for _ in 0..FINGERPRINT_DEPTH {
+        let mut next = Vec::new();
+        for entry in level
+            .iter()
+            .flat_map(|dir| fs::read_dir(dir).into_iter().flatten())
+        {
+            let Ok(entry) = entry else { continue };
+            entry_count += 1;
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let is_dir = metadata.is_dir();
+            if let Some(mtime) = mtime_of(metadata) {
+                newest = newest.max(mtime);
+            }
+            if !is_dir {
+                continue;
+            }
+            if UNIT_DIRS.iter().any(|unit| entry.file_name() == *unit) {
+                entry_count +=
+                    fs::read_dir(entry.path()).map_or(0, |entries| entries.count() as u64);
+            } else {
+                next.push(entry.path());
+            }
+        }
+        level = next;
+    }
+    Some((newest, entry_count))
+}
+
+impl SizeCache {
+    fn load() -> Result<Self, Box<dyn Error>> {
+        let path = Self::file()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    fn file() -> Result<PathBuf, Box<dyn Error>> {
+        Ok(cache_dir()?.join(SIZES_FILE))
+    }
+}
@@ -0,0 +1,61 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::cache::cache_dir;
+use crate::vfs;
+
+/// Name of the file the last report's totals are stored in, inside the cache directory
+const SNAPSHOT_FILE: &str = "report.json";
+
+/// Name of the file the totals of the last `stats` run are stored in
+pub const STATS_FILE: &str = "stats.json";
+
+/// Totals from a report, remembered so the next one can show what changed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// When the report was generated
+    pub generated: DateTime<Local>,
+    /// Number of target directories
+    pub targets: usize,
+    /// Combined size of all target directories
+    pub total_bytes: u64,
+    /// Combined size of the stale target directories
+    pub stale_bytes: u64,
+}
+
+impl Snapshot {
+    /// Loads the totals of the previous report, if there was one
+    pub fn load() -> Result<Option<Self>, Box<dyn Error>> {
+        Self::load_from(SNAPSHOT_FILE)
+    }
+
+    /// Remembers these totals for the next report
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        self.save_to(SNAPSHOT_FILE)
+    }
+
+    /// Loads totals remembered under a file name in the cache directory, if any
+    pub fn load_from(name: &str) -> Result<Option<Self>, Box<dyn Error>> {
+        let path = Self::file(name)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+    }
+
+    /// Remembers these totals under a file name in the cache directory, unless simulating
+    pub fn save_to(&self, name: &str) -> Result<(), Box<dyn Error>> {
+        if vfs::current().is_virtual() {
+            return Ok(());
+        }
+        fs::write(Self::file(name)?, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    fn file(name: &str) -> Result<PathBuf, Box<dyn Error>> {
+        Ok(cache_dir()?.join(name))
+    }
+}
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::cache::cache_dir;
+use crate::scanner::rust_project::{BuildHealth, RustProject};
+use crate::vfs;
+
+/// Name of the file tags are stored in, inside the cache directory
+const TAGS_FILE: &str = "tags.toml";
+
+/// User-assigned project tags and build marks, persisted in the cache directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TagStore {
+    /// Tags keyed by the project's display path (`host:path` for remote projects)
+    #[serde(default)]
+    projects: BTreeMap<String, Vec<String>>,
+    /// Outcome of the last build, keyed like the tags
+    #[serde(default)]
+    health: BTreeMap<String, BuildHealth>,
+}
+
+impl TagStore {
+    /// Loads the stored tags, starting empty if nothing was saved yet
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        let path = Self::file()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(toml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Writes the tags back to the cache directory; a simulation keeps them in memory
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        if vfs::current().is_virtual() {
+            return Ok(());
+        }
+        fs::write(Self::file()?, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Replaces the tags of a project, forgetting it when no tags are left
+    pub fn set(&mut self, project: &RustProject, tags: Vec<String>) {
+        let key = project.display_path();
+        if tags.is_empty() {
+            self.projects.remove(&key);
+        } else {
+            self.projects.insert(key, tags);
+        }
+    }
+
+    /// Records the outcome of a project's last build, or forgets it with `None`
+    pub fn set_health(&mut self, project: &RustProject, health: Option<BuildHealth>) {
+        let key = project.display_path();
+        match health {
+            Some(health) => self.health.insert(key, health),
+            None => self.health.remove(&key),
+        };
+    }
+
+    /// Forgets the tags and build mark stored under a display path
+    pub fn forget(&mut self, display_path: &str) {
+        self.projects.remove(display_path);
+        self.health.remove(display_path);
+    }
+
+    /// Fills in the stored tags and build marks of the given projects
+    pub fn apply(&self, projects: &mut [RustProject]) {
+        for project in projects {
+            let key = project.display_path();
+            if let Some(tags) = self.projects.get(&key) {
+                project.tags = tags.clone();
+            }
+            project.build_health = self.health.get(&key).copied();
+        }
+    }
+
+    /// Parses user input like `work, experiments` into a sorted list of tags
+    pub fn parse(input: &str) -> Vec<String> {
+        let mut tags: Vec<String> = input
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    fn file() -> Result<PathBuf, Box<dyn Error>> {
+        Ok(cache_dir()?.join(TAGS_FILE))
+    }
+}
@@ -0,0 +1,169 @@
+use std::path::{Path, PathBuf};
+
+use crate::scanner::target_finder::TargetFinder;
+use crate::vfs;
+
+/// Last line of defense before a target directory is removed or emptied
+///
+/// Refuses the path unless it resolves to a directory under one of the search paths,
+/// is not `/`, the home directory or (unless `mount_expected`) a mount root, and is
+/// named like the target directory cargo uses for `project_path`. A target left behind
+/// by a deleted project has no manifest to check the name against, so it must carry
+/// the markers cargo puts in its target directories instead.
+pub fn check_target(
+    project_path: &Path,
+    target_path: &Path,
+    roots: &[PathBuf],
+    mount_expected: bool,
+) -> Result<(), String> {
+    let refuse = |reason: &str| {
+        Err(format!(
+            "Refused to delete {}: {}",
+            target_path.display(),
+            reason
+        ))
+    };
+
+    // Resolve the parent only, so a symlinked target is checked as the link itself
+    let (Some(parent), Some(name)) = (target_path.parent(), target_path.file_name()) else {
+        return refuse("not a directory inside another");
+    };
+    let fs = vfs::current();
+    let Ok(parent) = fs.canonicalize(if parent.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        parent
+    }) else {
+        return refuse("its parent directory cannot be resolved");
+    };
+    let path = parent.join(name);
+
+    if path.parent().is_none() || path == Path::new("/") {
+        return refuse("it is the filesystem root");
+    }
+    if dirs::home_dir()
+        .and_then(|home| fs.canonicalize(&home).ok())
+        .is_some_and(|home| path == home || home.starts_with(&path))
+    {
+        return refuse("it is or contains the home directory");
+    }
+    if !mount_expected && is_mount_root(&path) {
+        return refuse("it is a mount root");
+    }
+    let under_root = roots
+        .iter()
+        .filter_map(|root| fs.canonicalize(root).ok())
+        .any(|root| path != root && path.starts_with(&root));
+    if !under_root {
+        return refuse("it is outside the search paths");
+    }
+    if !fs.exists(&project_path.join("Cargo.toml")) {
+        if !TargetFinder::is_cargo_target(&path) {
+            return refuse("it has no project and does not look like a cargo target");
+        }
+        return Ok(());
+    }
+    let expected = TargetFinder::resolve_target_dir(project_path);
+    if expected.file_name() != Some(name) {
+        return refuse(&format!(
+            "expected a directory named {}",
+            expected
+                .file_name()
+                .map_or_else(|| "target".into(), |name| name.to_string_lossy())
+        ));
+    }
+    Ok(())
+}
+
+/// Whether a directory is on a different device than its parent
+#[cfg(unix)]
+fn is_mount_root(path: &Path) -> bool {
+    use std::fs;
+    use std::os::unix::fs::MetadataExt;
+
+    let (Ok(metadata), Some(parent)) = (fs::symlink_metadata(path), path.parent()) else {
+        return false;
+    };
+    !metadata.file_type().is_symlink()
+        && fs::metadata(parent).is_ok_and(|parent| parent.dev() != metadata.dev())
+}
+
+/// Whether a directory is the root of a drive
+#[cfg(not(unix))]
+fn is_mount_root(path: &Path) -> bool {
+    path.parent().is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    /// Adds a project with a built target to the shared memory filesystem
+    fn add_project(project: &Path) {
+        let fs = vfs::test_fs();
+        let now = SystemTime::now();
+        fs.add_text(
+            project.join("Cargo.toml"),
+            "[package]\nname = \"app\"\n",
+            now,
+        );
+        fs.add_file(project.join("target/debug/app"), 10, now);
+        fs.add_file(project.join("build/out.o"), 10, now);
+    }
+
+    fn refusal(project: &Path, target: &Path, roots: &[&str], mount_expected: bool) -> String {
+        let roots: Vec<PathBuf> = roots.iter().map(PathBuf::from).collect();
+        check_target(project, target, &roots, mount_expected).unwrap_err()
+    }
+
+    #[test]
+    fn accepts_an_ordinary_target() {
+        let project = Path::new("/sim/guard/app");
+        add_project(project);
+        let roots = [PathBuf::from("/sim/guard")];
+        assert_eq!(
+            check_target(project, &project.join("target"), &roots, false),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn refuses_the_filesystem_root() {
+        add_project(Path::new("/sim/guard-root/app"));
+        let error = refusal(Path::new("/"), Path::new("/"), &["/"], true);
+        assert!(error.starts_with("Refused to delete /"), "{}", error);
+    }
+
+    #[test]
+    fn refuses_the_home_directory() {
+        let home = dirs::home_dir().expect("tests run with a home directory");
+        vfs::test_fs().add_dir(&home, SystemTime::now());
+        let parent = home.parent().unwrap().to_str().unwrap().to_string();
+        let error = refusal(home.parent().unwrap(), &home, &[&parent], true);
+        assert!(error.contains("home directory"), "{}", error);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn refuses_a_mount_root() {
+        add_project(Path::new("/sim/guard-mount/app"));
+        let error = refusal(Path::new("/"), Path::new("/proc"), &["/"], false);
+        assert!(error.contains("mount root"), "{}", error);
+    }
+
+    #[test]
+    fn refuses_a_directory_cargo_does_not_build_into() {
+        let project = Path::new("/sim/guard-name/app");
+        add_project(project);
+        let error = refusal(project, &project.join("build"), &["/sim/guard-name"], false);
+        assert!(
+            error.contains("expected a directory named target"),
+            "{}",
+            error
+        );
+
+        let error = refusal(project, &project.join("target"), &["/sim/elsewhere"], false);
+        assert!(error.contains("outside the search paths"), "{}", error);
+    }
+}
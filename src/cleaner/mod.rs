@@ -1 +1,8 @@
+pub mod guard;
+pub mod pending;
+pub mod risk;
+pub mod scope;
 pub mod targer_cleaner;
+pub mod time_machine;
+pub mod trash;
+pub mod verify;
@@ -0,0 +1,242 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::scanner::target_finder::TargetInfo;
+use crate::vfs;
+
+/// Infix marking a directory renamed away for deletion, e.g. `target.deleting-1700000000`
+const PENDING_INFIX: &str = ".deleting-";
+
+/// Background removal of a renamed directory, with the path it had
+type Removal = (PathBuf, JoinHandle<Result<(), String>>);
+
+/// Removals still running in the background
+static RUNNING: Mutex<Vec<Removal>> = Mutex::new(Vec::new());
+
+/// Deletes a directory in two phases: an atomic rename out of the way, then removal
+///
+/// Once renamed the directory is gone from its project as far as cargo and the
+/// scanner are concerned, even if the removal that follows is interrupted; the next
+/// cleanup of the project finishes it (see [`recover`]). The removal itself runs in the
+/// background until [`finish`] is called.
+pub fn delete(path: &Path) -> Result<(), Box<dyn Error>> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| format!("Cannot delete {}", path.display()))?
+        .to_string_lossy();
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos());
+    let pending = path.with_file_name(format!("{}{}{}", name, PENDING_INFIX, stamp));
+    if fs::rename(path, &pending).is_err() {
+        // Renaming can be refused where removing is not (e.g. a sticky parent directory)
+        fs::remove_dir_all(path)?;
+        return Ok(());
+    }
+
+    let original = path.to_path_buf();
+    let handle = std::thread::spawn(move || {
+        fs::remove_dir_all(&pending).map_err(|e| format!("{}: {}", pending.display(), e))
+    });
+    RUNNING.lock().unwrap().push((original, handle));
+    Ok(())
+}
+
+/// Waits for the background removals, returning the original paths that failed and why
+///
+/// A directory that could not be removed is renamed back when nothing has taken its
+/// place, so a failed deletion leaves the target where it was.
+pub fn finish() -> Vec<(PathBuf, String)> {
+    let running = std::mem::take(&mut *RUNNING.lock().unwrap());
+    running
+        .into_iter()
+        .filter_map(|(original, handle)| {
+            let error = match handle.join() {
+                Ok(Ok(())) => return None,
+                Ok(Err(error)) => error,
+                Err(_) => "removal panicked".to_string(),
+            };
+            restore_if_free(&original);
+            Some((original, error))
+        })
+        .collect()
+}
+
+/// Finishes deletions of a target's parts interrupted by an earlier run
+///
+/// Looks next to the target (in its project, or next to a relocated `target-dir`) and
+/// next to the build outputs kept in the project, for those paths only, and two levels
+/// into the target, where partial cleanups rename the parts of profiles. Only
+/// directories named exactly as [`delete`] names them are touched: they are removed,
+/// or restored to their original name if that is free and they still cannot be.
+pub fn recover(target_info: &TargetInfo) {
+    // A simulation must not finish real deletions
+    if vfs::current().is_virtual() {
+        return;
+    }
+    let outside = std::iter::once(&target_info.path)
+        .chain(&target_info.web_outputs)
+        .chain(&target_info.embedded_dirs);
+    for path in outside {
+        if let (Some(parent), Some(name)) = (path.parent(), path.file_name()) {
+            recover_in(parent, |original| original == name);
+        }
+    }
+    // e.g. `target/debug/incremental` or `target/<triple>/debug/incremental`
+    let children = subdirs(&target_info.path);
+    let grandchildren: Vec<PathBuf> = children.iter().flat_map(|dir| subdirs(dir)).collect();
+    for dir in std::iter::once(&target_info.path)
+        .chain(&children)
+        .chain(&grandchildren)
+    {
+        recover_in(dir, |_| true);
+    }
+}
+
+/// Removes the pending directories in `dir` whose original name is accepted
+fn recover_in(dir: &Path, accept: impl Fn(&str) -> bool) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Some(original) = pending_original(&name).filter(|original| accept(original)) else {
+            continue;
+        };
+        if !entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+            continue;
+        }
+        if fs::remove_dir_all(entry.path()).is_err() {
+            let original = dir.join(original);
+            if !original.exists() {
+                fs::rename(entry.path(), &original).ok();
+            }
+        }
+    }
+}
+
+/// Original name of a directory renamed by [`delete`]: `<original>.deleting-<digits>`
+fn pending_original(name: &str) -> Option<&str> {
+    let (original, stamp) = name.rsplit_once(PENDING_INFIX)?;
+    let valid = !original.is_empty()
+        && !stamp.is_empty()
+        && stamp.bytes().all(|byte| byte.is_ascii_digit());
+    valid.then_some(original)
+}
+
+/// Directories directly inside `dir` other than pending ones, not following symlinks
+fn subdirs(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+        .filter(|entry| pending_original(&entry.file_name().to_string_lossy()).is_none())
+        .map(|entry| entry.path())
+        .collect()
+}
+
+/// Renames the pending directory of `original` back, if nothing has taken its place
+fn restore_if_free(original: &Path) {
+    if original.exists() {
+        return;
+    }
+    let (Some(parent), Some(name)) = (original.parent(), original.file_name()) else {
+        return;
+    };
+    let pending = fs::read_dir(parent)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .find(|entry| {
+            pending_original(&entry.file_name().to_string_lossy()) == Some(&*name.to_string_lossy())
+        });
+    if let Some(pending) = pending {
+        fs::rename(pending.path(), original).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fresh directory on disk for one test; the moves here are real renames
+    fn temp_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "clear-target-pending-{}-{}",
+            test,
+            std::process::id()
+        ));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn deletes_in_the_background() {
+        let dir = temp_dir("delete");
+        fs::create_dir_all(dir.join("target/debug")).unwrap();
+        fs::write(dir.join("target/debug/app"), "bin").unwrap();
+
+        delete(&dir.join("target")).unwrap();
+        assert!(!dir.join("target").exists());
+        assert!(finish().is_empty());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn finishes_an_interrupted_delete() {
+        let dir = temp_dir("recover");
+        fs::create_dir_all(dir.join("target.deleting-1700000000/debug")).unwrap();
+        fs::create_dir_all(dir.join("other.deleting-1700000000")).unwrap();
+
+        recover_in(&dir, |original| original == "target");
+        assert!(!dir.join("target.deleting-1700000000").exists());
+        assert!(dir.join("other.deleting-1700000000").exists());
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn renames_back_only_when_the_name_is_free() {
+        let dir = temp_dir("restore");
+        fs::create_dir_all(dir.join("target.deleting-42/debug")).unwrap();
+        restore_if_free(&dir.join("target"));
+        assert!(dir.join("target/debug").exists());
+        assert!(!dir.join("target.deleting-42").exists());
+
+        fs::create_dir_all(dir.join("target.deleting-43")).unwrap();
+        restore_if_free(&dir.join("target"));
+        assert!(dir.join("target.deleting-43").exists());
+        assert!(dir.join("target/debug").exists());
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn leaves_names_that_only_look_pending() {
+        let dir = temp_dir("lookalike");
+        let names = [
+            "notes.deleting-old",
+            "target.deleting-",
+            ".deleting-12",
+            "target.deleting-12a",
+        ];
+        for name in names {
+            fs::create_dir_all(dir.join(name)).unwrap();
+        }
+        recover_in(&dir, |_| true);
+        for name in names {
+            assert!(dir.join(name).exists(), "{} was removed", name);
+            assert_eq!(pending_original(name), None);
+        }
+        assert_eq!(
+            pending_original("incremental.deleting-5"),
+            Some("incremental")
+        );
+        fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,204 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::scanner::rust_project::RustProject;
+use crate::scanner::{build_time, git};
+
+/// A target built within this long is likely still in use
+const RECENT_BUILD: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Something about a project that makes cleaning its target likely to be regretted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RiskFactor {
+    /// The target was written to within the last day
+    RecentlyBuilt,
+    /// Tracked files have uncommitted changes, so the work is in progress
+    DirtyTree,
+    /// The project pins a toolchain that may be slow or hard to get again
+    PinnedToolchain,
+    /// The target is not stale by the configured threshold
+    NotStale,
+    /// Release binaries were built after the last commit and cannot be rebuilt from it
+    UncommittedReleaseBinaries,
+    /// Rebuilding the target likely takes longer than `settings.rebuild_budget`
+    LongRebuild,
+}
+
+impl RiskFactor {
+    /// Short description shown next to the target
+    pub fn label(self) -> &'static str {
+        match self {
+            RiskFactor::RecentlyBuilt => "built today",
+            RiskFactor::DirtyTree => "uncommitted changes",
+            RiskFactor::PinnedToolchain => "pinned toolchain",
+            RiskFactor::NotStale => "not stale",
+            RiskFactor::UncommittedReleaseBinaries => "release binaries newer than HEAD",
+            RiskFactor::LongRebuild => "long rebuild",
+        }
+    }
+
+    /// Contribution to the risk score
+    fn weight(self) -> u8 {
+        match self {
+            RiskFactor::RecentlyBuilt | RiskFactor::UncommittedReleaseBinaries => 3,
+            RiskFactor::DirtyTree | RiskFactor::NotStale | RiskFactor::LongRebuild => 2,
+            RiskFactor::PinnedToolchain => 1,
+        }
+    }
+}
+
+/// How much a cleanup of one target is likely to be regretted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RiskLevel {
+    /// Nothing speaks against cleaning
+    Low,
+    /// Worth a second look
+    Medium,
+    /// Likely to be regretted
+    High,
+}
+
+/// Risk of cleaning one project's target, from the factors that apply to it
+#[derive(Debug, Clone, Default)]
+pub struct Risk {
+    /// Display path of the project
+    pub project: String,
+    /// Factors found, most severe first
+    pub factors: Vec<RiskFactor>,
+    /// Estimated time to rebuild the target, when it exceeds the budget
+    pub rebuild: Option<Duration>,
+}
+
+impl Risk {
+    /// Sum of the weights of the factors, 0 when nothing speaks against cleaning
+    pub fn score(&self) -> u8 {
+        self.factors.iter().map(|factor| factor.weight()).sum()
+    }
+
+    /// Level of the score: low at 0, high from 4 on
+    pub fn level(&self) -> RiskLevel {
+        match self.score() {
+            0 => RiskLevel::Low,
+            1..=3 => RiskLevel::Medium,
+            _ => RiskLevel::High,
+        }
+    }
+
+    /// Factors joined for display, e.g. `built today, long rebuild (~25 min)`
+    pub fn describe(&self) -> String {
+        self.factors
+            .iter()
+            .map(|factor| match (factor, self.rebuild) {
+                (RiskFactor::LongRebuild, Some(rebuild)) => {
+                    format!(
+                        "{} (~{})",
+                        factor.label(),
+                        build_time::format_estimate(rebuild)
+                    )
+                }
+                _ => factor.label().to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Rates cleaning the targets of the selected local projects
+///
+/// Runs git in each project and reads its fingerprints, so it is meant for the few
+/// projects about to be cleaned rather than for everything a scan found.
+pub fn assess(projects: &[RustProject], selected: &[bool], rebuild_budget: Duration) -> Vec<Risk> {
+    projects
+        .iter()
+        .zip(selected)
+        .filter(|(project, selected)| **selected && project.host.is_none())
+        .filter_map(|(project, _)| assess_project(project, rebuild_budget))
+        .collect()
+}
+
+/// Estimated rebuild time of a target when it exceeds a nonzero budget
+pub fn long_rebuild(target: &Path, budget: Duration) -> Option<Duration> {
+    if budget.is_zero() {
+        return None;
+    }
+    build_time::estimate(target).filter(|estimate| *estimate > budget)
+}
+
+/// Rates cleaning one project's target
+fn assess_project(project: &RustProject, rebuild_budget: Duration) -> Option<Risk> {
+    let target_info = project.target_info.as_ref()?;
+    let mut factors = Vec::new();
+    if target_info
+        .last_accessed
+        .elapsed()
+        .is_ok_and(|age| age < RECENT_BUILD)
+    {
+        factors.push(RiskFactor::RecentlyBuilt);
+    }
+    if has_release_binaries_after(&target_info.path, git::last_commit(&project.path)) {
+        factors.push(RiskFactor::UncommittedReleaseBinaries);
+    }
+    if git::is_dirty(&project.path) {
+        factors.push(RiskFactor::DirtyTree);
+    }
+    if !target_info.is_stale {
+        factors.push(RiskFactor::NotStale);
+    }
+    let rebuild = long_rebuild(&target_info.path, rebuild_budget);
+    if rebuild.is_some() {
+        factors.push(RiskFactor::LongRebuild);
+    }
+    if ["rust-toolchain", "rust-toolchain.toml"]
+        .iter()
+        .any(|file| project.path.join(file).exists())
+    {
+        factors.push(RiskFactor::PinnedToolchain);
+    }
+    Some(Risk {
+        project: project.display_path(),
+        factors,
+        rebuild,
+    })
+}
+
+/// Whether a release profile holds executables built after `commit` (or at all, without one)
+fn has_release_binaries_after(target: &Path, commit: Option<SystemTime>) -> bool {
+    release_dirs(target).iter().any(|release| {
+        fs::read_dir(release)
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .filter(|entry| is_executable(&entry.path()))
+            .filter_map(|entry| entry.metadata().and_then(|m| m.modified()).ok())
+            .any(|built| commit.is_none_or(|commit| built > commit))
+    })
+}
+
+/// `release` profile directories of a target, including cross-compiled ones
+fn release_dirs(target: &Path) -> Vec<PathBuf> {
+    let mut dirs = vec![target.join("release")];
+    dirs.extend(
+        fs::read_dir(target)
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .map(|entry| entry.path().join("release"))
+            .filter(|release| release.is_dir()),
+    );
+    dirs
+}
+
+/// Whether a file is a program rather than a library or build metadata
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+/// Whether a file is a program rather than a library or build metadata
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file() && path.extension().is_some_and(|ext| ext == "exe")
+}
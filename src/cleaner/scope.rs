@@ -0,0 +1,208 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::scanner::compilers;
+use crate::scanner::cross::CrossTool;
+use crate::scanner::target_finder::{CRITERION_DIR, TargetInfo};
+use crate::vfs::{self, EntryKind, Walk};
+
+/// Part of a target directory a cleanup can be limited to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum CleanScope {
+    /// Incremental compilation caches (`target/*/incremental`), cheap to regenerate
+    Incremental,
+    /// Generated rustdoc output (`target/doc`)
+    Doc,
+    /// rust-analyzer's separate build directory (`target/rust-analyzer`)
+    RustAnalyzer,
+    /// Fingerprints of units whose build outputs are gone (`target/*/.fingerprint`)
+    Fingerprints,
+    /// Artifacts built by compilers older than the installed toolchain
+    OldCompilers,
+    /// Web build outputs next to the target (`dist` from trunk, `pkg` from wasm-pack)
+    Web,
+    /// Embedded toolchain directories next to the target (`.embuild`, ESP-IDF's `build`)
+    Embedded,
+    /// Benchmark and profiling outputs (`target/criterion`, `flamegraph.svg`, `perf.data`)
+    Benchmarks,
+    /// Builds of target triples made with cross (`target/<triple>`)
+    Cross,
+    /// Builds of target triples made with cargo-zigbuild (`target/<triple>`)
+    Zigbuild,
+}
+
+impl CleanScope {
+    /// Short description of what the scope removes, for prompts and messages
+    pub fn label(self) -> &'static str {
+        match self {
+            CleanScope::Incremental => "incremental caches",
+            CleanScope::Doc => "generated docs",
+            CleanScope::RustAnalyzer => "rust-analyzer caches",
+            CleanScope::Fingerprints => "stale fingerprints",
+            CleanScope::OldCompilers => "artifacts of older compilers",
+            CleanScope::Web => "web build outputs",
+            CleanScope::Embedded => "embedded toolchain directories",
+            CleanScope::Benchmarks => "benchmark and profiling outputs",
+            CleanScope::Cross => "cross builds",
+            CleanScope::Zigbuild => "zigbuild builds",
+        }
+    }
+
+    /// Finds the directories inside a target that belong to this scope
+    pub fn paths(self, target_info: &TargetInfo) -> Vec<PathBuf> {
+        if self == CleanScope::RustAnalyzer {
+            // Its location was already detected while scanning
+            return target_info.rust_analyzer.iter().cloned().collect();
+        }
+        if self == CleanScope::OldCompilers {
+            return compilers::usage(&target_info.path).outdated;
+        }
+        if self == CleanScope::Web {
+            return target_info.web_outputs.clone();
+        }
+        if self == CleanScope::Embedded {
+            return target_info.embedded_dirs.clone();
+        }
+        if self == CleanScope::Benchmarks {
+            let criterion = target_info.path.join(CRITERION_DIR);
+            let mut paths: Vec<PathBuf> = vfs::current()
+                .is_dir(&criterion)
+                .then_some(criterion)
+                .into_iter()
+                .collect();
+            paths.extend(target_info.profiling_files.iter().cloned());
+            return paths;
+        }
+        if let Some(tool) = self.cross_tool() {
+            return target_info
+                .cross_builds
+                .iter()
+                .filter(|build| build.tool == tool)
+                .map(|build| target_info.path.join(&build.triple))
+                .collect();
+        }
+
+        let target = &target_info.path;
+        let mut paths = Vec::new();
+        // Profiles sit at target/<profile>, or target/<triple>/<profile> when cross-compiling
+        let mut walker = Walk::new(vfs::current(), target).max_depth(3);
+        while let Some(entry) = walker.next() {
+            let Ok(entry) = entry else { continue };
+            if entry.depth == 0 || entry.kind != EntryKind::Dir {
+                continue;
+            }
+            let name = entry.path.file_name().unwrap_or_default().to_string_lossy();
+            if self == CleanScope::Fingerprints && name == ".fingerprint" {
+                paths.extend(stale_fingerprints(&entry.path));
+                walker.skip_current_dir();
+            } else if self.matches(&name) {
+                paths.push(entry.path.clone());
+                walker.skip_current_dir();
+            } else if matches!(
+                name.as_ref(),
+                "deps" | "build" | ".fingerprint" | "examples" | CRITERION_DIR
+            ) {
+                // Large directories that never contain a scope of their own
+                walker.skip_current_dir();
+            }
+        }
+        paths
+    }
+
+    /// Whether a directory with this name belongs to the scope
+    fn matches(self, name: &str) -> bool {
+        match self {
+            CleanScope::Incremental => name == "incremental",
+            CleanScope::Doc => name == "doc",
+            CleanScope::RustAnalyzer
+            | CleanScope::Fingerprints
+            | CleanScope::OldCompilers
+            | CleanScope::Web
+            | CleanScope::Embedded
+            | CleanScope::Benchmarks
+            | CleanScope::Cross
+            | CleanScope::Zigbuild => false,
+        }
+    }
+
+    /// Cross-compilation tool whose builds the scope removes
+    fn cross_tool(self) -> Option<CrossTool> {
+        match self {
+            CleanScope::Cross => Some(CrossTool::Cross),
+            CleanScope::Zigbuild => Some(CrossTool::Zigbuild),
+            _ => None,
+        }
+    }
+}
+
+/// Fingerprint entries of units whose artifacts are gone from the profile directory
+///
+/// Entries are named `<crate>-<hash>`, one per unit (library, binary, test, build script
+/// and its run), and the unit's outputs in `deps`, `build` and `examples` carry the same
+/// hash. A changed feature set, compiler or dependency gives a unit a new hash and leaves
+/// the old entry behind without outputs; entries whose hash still has outputs are in use.
+fn stale_fingerprints(dir: &Path) -> Vec<PathBuf> {
+    let fs = vfs::current();
+    let Some(profile) = dir.parent() else {
+        return Vec::new();
+    };
+    let live: HashSet<String> = ["deps", "build", "examples"]
+        .iter()
+        .flat_map(|name| fs.read_dir(&profile.join(name)).into_iter().flatten())
+        .filter_map(|entry| unit_hash(&entry.name()).map(str::to_string))
+        .collect();
+
+    fs.read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter(|entry| unit_hash(&entry.name()).is_some_and(|hash| !live.contains(hash)))
+        .map(|entry| entry.path)
+        .collect()
+}
+
+/// Hash of the unit an artifact or fingerprint named like `libfoo-1a2b3c.rlib` belongs to
+fn unit_hash(name: &str) -> Option<&str> {
+    let (_, rest) = name.rsplit_once('-')?;
+    let hash = rest.split('.').next().unwrap_or(rest);
+    (!hash.is_empty() && hash.chars().all(|c| c.is_ascii_hexdigit())).then_some(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    #[test]
+    fn drops_only_fingerprints_without_outputs() {
+        let fs = vfs::test_fs();
+        let profile = Path::new("/sim/fingerprints/target/debug");
+        let now = SystemTime::now();
+        // One crate with its library, test, build script and build script run units
+        for unit in [
+            "app-1a2b", "app-3c4d", "app-5e6f", "app-7a8b", "app-9c0d", "dep-aaaa",
+        ] {
+            fs.add_text(
+                profile.join(".fingerprint").join(unit).join("lib-app"),
+                "0",
+                now,
+            );
+        }
+        fs.add_file(profile.join("deps/libapp-1a2b.rlib"), 10, now);
+        fs.add_file(profile.join("deps/libapp-1a2b.rmeta"), 10, now);
+        fs.add_file(profile.join("deps/app-3c4d"), 10, now);
+        fs.add_file(profile.join("deps/app-3c4d.d"), 10, now);
+        fs.add_file(profile.join("build/app-5e6f/build-script-build"), 10, now);
+        fs.add_dir(profile.join("build/app-7a8b/out"), now);
+        fs.add_file(profile.join("deps/libdep-bbbb.rlib"), 10, now);
+
+        let mut stale = stale_fingerprints(&profile.join(".fingerprint"));
+        stale.sort();
+        assert_eq!(
+            stale,
+            vec![
+                profile.join(".fingerprint/app-9c0d"),
+                profile.join(".fingerprint/dep-aaaa"),
+            ]
+        );
+    }
+}
@@ -1,104 +1,1000 @@
 use std::error::Error;
-use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
+use crate::cache::audit::AuditLog;
+use crate::cleaner::guard;
+use crate::cleaner::pending;
+use crate::cleaner::scope::CleanScope;
+use crate::cleaner::time_machine;
+use crate::cleaner::trash::Trash;
+use crate::config::{Config, ProjectRule, SymlinkPolicy};
+use crate::remote::RemoteHost;
 use crate::scanner::rust_project::RustProject;
+use crate::scanner::target_finder::{
+    TargetEntry, TargetFinder, TargetInfo, free_space, free_space_by_filesystem,
+};
+use crate::size::format_bytes;
+use crate::tasks::CancelToken;
+use crate::timing::{self, Phase};
+use crate::vfs;
+/// How selected target directories are removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CleanMode {
+    /// Only report what would be removed
+    DryRun,
+    /// Permanently delete
+    Delete,
+    /// Move into the trash so it can be restored later
+    Trash,
+}
+
+impl CleanMode {
+    /// Determines the clean mode from the configuration
+    pub fn from_config(config: &Config) -> Self {
+        if config.dry_run {
+            CleanMode::DryRun
+        } else if config.trash {
+            CleanMode::Trash
+        } else {
+            CleanMode::Delete
+        }
+    }
+}
+
+/// Settings controlling how target directories are cleaned
+#[derive(Debug, Clone)]
+pub struct CleanOptions {
+    /// How selected target directories are removed
+    pub mode: CleanMode,
+    /// Command prefix (e.g. `sudo`) used to remove targets containing files owned by another user
+    pub elevate_command: Vec<String>,
+    /// What to do with target directories that are symlinks
+    pub symlinked_targets: SymlinkPolicy,
+    /// Per-project rules, for the target subpaths to keep
+    pub project_rules: Vec<ProjectRule>,
+    /// Only remove this part of each target instead of the whole directory
+    pub scope: Option<CleanScope>,
+    /// Stops the cleanup between target directories once cancelled
+    pub cancel: Option<CancelToken>,
+    /// Exclude cleaned targets from Time Machine backups (macOS)
+    pub exclude_from_backups: bool,
+    /// Directories every removed target must lie under, normally the search paths
+    pub roots: Vec<PathBuf>,
+}
+
+impl CleanOptions {
+    /// Builds clean options from the configuration
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            mode: CleanMode::from_config(config),
+            elevate_command: config.elevate_command.clone(),
+            symlinked_targets: config.symlinked_targets,
+            project_rules: config.project_rules.clone(),
+            scope: None,
+            cancel: None,
+            exclude_from_backups: config.exclude_from_time_machine,
+            roots: config.search_paths.clone(),
+        }
+    }
+
+    /// Limits the cleanup to one part of each target directory
+    pub fn with_scope(mut self, scope: Option<CleanScope>) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Lets the cleanup be stopped between target directories
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Also allows removing targets under a directory outside the search paths
+    pub fn with_root(mut self, root: PathBuf) -> Self {
+        self.roots.push(root);
+        self
+    }
+}
+
+/// What was done to a target directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CleanAction {
+    /// The whole directory was removed
+    Removed,
+    /// The contents were removed, keeping the directory (a mount point or linked directory)
+    Emptied,
+    /// Only the symlink leading to the directory was removed
+    Unlinked,
+    /// Part of the directory was removed, by clean scope or kept subpaths
+    Pruned,
+}
+
+/// One selected target directory handled during a cleanup
+#[derive(Debug, Clone)]
+pub struct CleanEvent {
+    /// Project the target directory belongs to
+    pub project: PathBuf,
+    /// The target directory, or the entry inside it that was handled
+    pub target: PathBuf,
+    /// Bytes removed, or that would be in a dry run; 0 when cleaning failed
+    pub bytes_deleted: u64,
+    /// What was done, when cleaning succeeded
+    pub action: CleanAction,
+    /// How the target was removed
+    pub mode: CleanMode,
+    /// Why the target could not be cleaned
+    pub error: Option<String>,
+    /// Problems after a successful clean, like failing to log it
+    pub warnings: Vec<String>,
+    /// Targets handled so far, this one included
+    pub done: usize,
+    /// Targets to handle in this cleanup
+    pub total: usize,
+}
+
+impl CleanEvent {
+    /// Line describing what happened, e.g. `Deleted: /src/app/target (1.2 GB)`
+    pub fn describe(&self) -> String {
+        if let Some(error) = &self.error {
+            return error.clone();
+        }
+        let dry_run = self.mode == CleanMode::DryRun;
+        let verb = match (self.action, self.mode) {
+            (CleanAction::Removed, CleanMode::DryRun) => "Would delete",
+            (CleanAction::Removed, CleanMode::Trash) => "Moved to trash",
+            (CleanAction::Removed, CleanMode::Delete) => "Deleted",
+            (CleanAction::Emptied, _) if dry_run => "Would empty",
+            (CleanAction::Emptied, _) => "Emptied",
+            (CleanAction::Unlinked, _) if dry_run => "Would remove symlink",
+            (CleanAction::Unlinked, _) => "Removed symlink",
+            (CleanAction::Pruned, _) if dry_run => "Would partly delete",
+            (CleanAction::Pruned, _) => "Partly deleted",
+        };
+        format!(
+            "{}: {} ({})",
+            verb,
+            self.target.display(),
+            format_bytes(self.bytes_deleted)
+        )
+    }
+}
+
+/// A cleanup under way, handling one selected target directory per [`CleanEvent`]
+///
+/// Nothing is removed until the events are consumed. Stopping early, or cancelling the
+/// token in the options, leaves the remaining targets untouched; [`Cleanup::finish`]
+/// then waits for background removals and returns the totals.
+pub struct Cleanup<'a> {
+    projects: &'a [RustProject],
+    selected: &'a [bool],
+    options: &'a CleanOptions,
+    trash: Option<Trash>,
+    /// Free space per filesystem before a live cleanup started
+    free_before: Option<Vec<(PathBuf, u64)>>,
+    /// Index of the next project to look at
+    next: usize,
+    done: usize,
+    total: usize,
+    result: CleanupResult,
+}
+
+impl Iterator for Cleanup<'_> {
+    type Item = CleanEvent;
+
+    fn next(&mut self) -> Option<CleanEvent> {
+        loop {
+            let project = self.projects.get(self.next)?;
+            let Some(target_info) = project
+                .target_info
+                .as_ref()
+                .filter(|_| self.selected.get(self.next).copied().unwrap_or(false))
+            else {
+                self.next += 1;
+                continue;
+            };
+            if self
+                .options
+                .cancel
+                .as_ref()
+                .is_some_and(CancelToken::is_cancelled)
+            {
+                return None;
+            }
+            self.next += 1;
+
+            let mode = self.options.mode;
+            // A simulated cleanup leaves no trace outside the simulated filesystem
+            let recorded = mode != CleanMode::DryRun && !vfs::current().is_virtual();
+            let outcome = timing::measure(Phase::Cleaning, || {
+                TargetCleaner::clean_project(project, target_info, self.options, &self.trash)
+            });
+            let mut warnings = Vec::new();
+            let (bytes_deleted, action, error) = match outcome {
+                Ok((freed, action)) => {
+                    if recorded && let Err(e) = AuditLog::record(project, freed, self.options.scope)
+                    {
+                        warnings.push(format!("Failed to log the cleanup: {}", e));
+                    }
+                    if recorded
+                        && self.options.exclude_from_backups
+                        && project.host.is_none()
+                        && let Err(e) =
+                            time_machine::exclude(&target_info.path, &self.options.elevate_command)
+                    {
+                        warnings.push(e);
+                    }
+                    self.result.total_freed += freed;
+                    self.result.projects_cleaned += 1;
+                    (freed, action, None)
+                }
+                Err(error) => {
+                    self.result.errors.push(error.clone());
+                    (0, CleanAction::Removed, Some(error))
+                }
+            };
+            self.done += 1;
+            return Some(CleanEvent {
+                project: project.path.clone(),
+                target: target_info.path.clone(),
+                bytes_deleted,
+                action,
+                mode,
+                error,
+                warnings,
+                done: self.done,
+                total: self.total,
+            });
+        }
+    }
+}
+
+impl Cleanup<'_> {
+    /// Ends the cleanup, returning what it freed and every error
+    pub fn finish(mut self) -> CleanupResult {
+        let remaining = self.total - self.done;
+        if remaining > 0 {
+            self.result.errors.push(format!(
+                "Cancelled; {} target directories were left untouched",
+                remaining
+            ));
+        }
+        let projects = self.projects;
+        TargetCleaner::finish_pending(&mut self.result, |path| {
+            projects
+                .iter()
+                .filter_map(|project| project.target_info.as_ref())
+                .find(|target_info| target_info.path == path)
+                .map_or(0, |target_info| target_info.size_bytes)
+        });
+        self.result.measure_reclaimed(self.free_before.as_deref());
+        self.result
+    }
+}
+
 /// Utility for cleaning up target directories
 pub struct TargetCleaner;
 
 impl TargetCleaner {
-    /// Clean up target directories for the selected projects
-    pub fn clean_selected_projects(
-        projects: &[RustProject],
-        selected_indices: &[bool],
-        dry_run: bool,
+    /// Starts cleaning the target directories of the selected projects
+    ///
+    /// Each target is handled as the returned [`Cleanup`] is iterated.
+    pub fn clean<'a>(
+        projects: &'a [RustProject],
+        selected_indices: &'a [bool],
+        options: &'a CleanOptions,
+    ) -> Result<Cleanup<'a>, Box<dyn Error>> {
+        let mode = options.mode;
+        let total = projects
+            .iter()
+            .zip(selected_indices)
+            .filter(|(project, selected)| **selected && project.target_info.is_some())
+            .count();
+        let trash = Self::open_trash(mode)?;
+        let free_before = (mode != CleanMode::DryRun && !vfs::current().is_virtual()).then(|| {
+            free_space_by_filesystem(
+                projects
+                    .iter()
+                    .zip(selected_indices)
+                    .filter(|(project, selected)| **selected && project.host.is_none())
+                    .filter_map(|(project, _)| project.target_info.as_ref()?.path.parent()),
+            )
+        });
+
+        Ok(Cleanup {
+            projects,
+            selected: selected_indices,
+            options,
+            trash,
+            free_before,
+            next: 0,
+            done: 0,
+            total,
+            result: CleanupResult {
+                total_freed: 0,
+                projects_cleaned: 0,
+                errors: Vec::new(),
+                space_reclaimed: None,
+                snapshots: 0,
+            },
+        })
+    }
+
+    /// Removes one project's target directory, returning the bytes freed and how
+    fn clean_project(
+        project: &RustProject,
+        target_info: &TargetInfo,
+        options: &CleanOptions,
+        trash: &Option<Trash>,
+    ) -> Result<(u64, CleanAction), String> {
+        let mode = options.mode;
+        let (path, size) = (&target_info.path, target_info.size_bytes);
+        let keep = ProjectRule::keep_paths(&options.project_rules, project);
+        if project.host.is_none() {
+            guard::check_target(
+                &project.path,
+                path,
+                &options.roots,
+                target_info.device.is_some(),
+            )?;
+            // Runs under the instance lock, so no other run is still removing these
+            if mode != CleanMode::DryRun {
+                pending::recover(target_info);
+            }
+        }
+
+        match (&project.host, target_info.foreign_owner) {
+            (Some(_), _) if options.scope.is_some() => Err(format!(
+                "Skipped {}: partial cleanups are not supported for remote targets",
+                project.display_path()
+            )),
+            (Some(_), _) if !keep.is_empty() => Err(format!(
+                "Skipped {}: kept paths ([[project]] keep) are not supported for remote targets",
+                project.display_path()
+            )),
+            (Some(host), _) => {
+                Self::remove_remote(host, path, mode).map(|_| (size, CleanAction::Removed))
+            }
+            (None, _)
+                if target_info.link_target.is_some()
+                    && options.symlinked_targets == SymlinkPolicy::Skip =>
+            {
+                Self::remove_link(path, size, &keep, mode, trash, options.symlinked_targets)
+            }
+            (None, _) if let Some(scope) = options.scope => {
+                Self::remove_scope(target_info, scope, &keep, mode, trash)
+                    .map(|freed| (freed, CleanAction::Pruned))
+            }
+            // Unlinking leaves the linked directory, kept paths included, untouched
+            (None, _)
+                if target_info.link_target.is_some()
+                    && options.symlinked_targets == SymlinkPolicy::Unlink =>
+            {
+                Self::remove_link(path, size, &keep, mode, trash, options.symlinked_targets)
+            }
+            (None, _) if !keep.is_empty() => Self::remove_except(path, &keep, mode, trash)
+                .map(|freed| (freed, CleanAction::Pruned)),
+            (None, _) if target_info.link_target.is_some() => {
+                Self::remove_link(path, size, &keep, mode, trash, options.symlinked_targets)
+            }
+            // A mount point cannot be removed, only emptied
+            (None, _) if target_info.device.is_some() => {
+                Self::remove_contents(path, size, &keep, mode, trash)
+            }
+            (None, Some(_))
+                if mode == CleanMode::Delete
+                    && !options.elevate_command.is_empty()
+                    && !vfs::current().is_virtual() =>
+            {
+                Self::remove_elevated(path, &options.elevate_command)
+                    .map(|_| (size, CleanAction::Removed))
+            }
+            (None, Some(uid)) => Self::remove_path(path, size, mode, trash)
+                .map(|_| (size, CleanAction::Removed))
+                .map_err(|e| {
+                    format!(
+                        "{} (contains files owned by uid {}, likely from a container build; \
+                         set settings.elevate_command, e.g. [\"sudo\"], to remove it)",
+                        e, uid
+                    )
+                }),
+            (None, None) => {
+                Self::remove_path(path, size, mode, trash).map(|_| (size, CleanAction::Removed))
+            }
+        }
+    }
+
+    /// Clean up individual entries inside a single project's target directory
+    ///
+    /// Reports each entry to `on_event` once it was removed or failed to be. The project
+    /// counts as cleaned once if any of its entries were removed.
+    pub fn clean_target_entries(
+        project_path: &Path,
+        target_info: &TargetInfo,
+        entries: &[TargetEntry],
+        options: &CleanOptions,
+        on_event: &mut dyn FnMut(CleanEvent),
     ) -> Result<CleanupResult, Box<dyn Error>> {
+        let mode = options.mode;
         let mut total_freed = 0u64;
         let mut errors = Vec::new();
+        let trash = Self::open_trash(mode)?;
+        let free_before = (mode != CleanMode::DryRun && !vfs::current().is_virtual())
+            .then(|| free_space_by_filesystem(target_info.path.parent()));
+        guard::check_target(
+            project_path,
+            &target_info.path,
+            &options.roots,
+            target_info.device.is_some(),
+        )?;
 
-        for (i, project) in projects.iter().enumerate() {
-            if selected_indices.get(i).copied().unwrap_or(false) {
-                if let Some(ref target_info) = project.target_info {
-                    let _project_name = &project.name;
-                    let target_path = &target_info.path;
-                    let size = target_info.size_bytes;
-
-                    if dry_run {
-                        // Just simulate deletion in dry run mode
-                        println!(
-                            "Would delete: {} ({})",
-                            target_path.display(),
-                            format_bytes(size)
-                        );
-                        total_freed += size;
-                    } else {
-                        // Actually delete the target directory
-                        match Self::delete_target_directory(target_path) {
-                            Ok(_) => {
-                                println!(
-                                    "Deleted: {} ({})",
-                                    target_path.display(),
-                                    format_bytes(size)
-                                );
-                                total_freed += size;
-                            }
-                            Err(e) => {
-                                let error =
-                                    format!("Failed to delete {}: {}", target_path.display(), e);
-                                eprintln!("Error: {}", error);
-                                errors.push(error);
-                            }
-                        }
-                    }
+        for (i, entry) in entries.iter().enumerate() {
+            let outcome = if entry.path.parent() != Some(target_info.path.as_path()) {
+                Err(format!(
+                    "Refused to delete {}: not inside {}",
+                    entry.path.display(),
+                    target_info.path.display()
+                ))
+            } else {
+                timing::measure(Phase::Cleaning, || {
+                    Self::remove_path(&entry.path, entry.size_bytes, mode, &trash)
+                })
+            };
+            let error = match outcome {
+                Ok(()) => {
+                    total_freed += entry.size_bytes;
+                    None
                 }
-            }
+                Err(error) => {
+                    errors.push(error.clone());
+                    Some(error)
+                }
+            };
+            on_event(CleanEvent {
+                project: project_path.to_path_buf(),
+                target: entry.path.clone(),
+                bytes_deleted: if error.is_none() { entry.size_bytes } else { 0 },
+                action: CleanAction::Removed,
+                mode,
+                error,
+                warnings: Vec::new(),
+                done: i + 1,
+                total: entries.len(),
+            });
         }
 
-        Ok(CleanupResult {
+        let mut result = CleanupResult {
             total_freed,
+            projects_cleaned: 0,
             errors,
-        })
+            space_reclaimed: None,
+            snapshots: 0,
+        };
+        Self::finish_pending(&mut result, |path| {
+            entries
+                .iter()
+                .find(|entry| entry.path == path)
+                .map_or(0, |entry| entry.size_bytes)
+        });
+        result.measure_reclaimed(free_before.as_deref());
+        // Only the whole project counts as cleaned, not each entry
+        result.projects_cleaned = usize::from(result.errors.len() < entries.len());
+        Ok(result)
+    }
+
+    /// Opens the trash when the mode needs it
+    ///
+    /// A simulated filesystem has no trash on disk to move into, so its targets are deleted.
+    fn open_trash(mode: CleanMode) -> Result<Option<Trash>, Box<dyn Error>> {
+        match mode {
+            CleanMode::Trash if !vfs::current().is_virtual() => Ok(Some(Trash::open()?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Removes a single path according to the clean mode
+    fn remove_path(
+        path: &Path,
+        size: u64,
+        mode: CleanMode,
+        trash: &Option<Trash>,
+    ) -> Result<(), String> {
+        let fs = vfs::current();
+        let outcome = match (mode, trash) {
+            // Just simulate deletion in dry run mode
+            (CleanMode::DryRun, _) => return Ok(()),
+            (CleanMode::Trash, Some(trash)) if fs.exists(path) => trash.put(path, size).map(|_| ()),
+            _ if fs.is_dir(path) => Self::delete_target_directory(path),
+            _ => fs.remove_file(path).map_err(|e| e.into()),
+        };
+        outcome.map_err(|e| format!("Failed to delete {}: {}", path.display(), e))
+    }
+
+    /// Handles a target directory that is a symlink according to the symlink policy
+    ///
+    /// Returns the bytes freed: unlinking leaves the data in place and frees nothing.
+    fn remove_link(
+        path: &Path,
+        size: u64,
+        keep: &[PathBuf],
+        mode: CleanMode,
+        trash: &Option<Trash>,
+        policy: SymlinkPolicy,
+    ) -> Result<(u64, CleanAction), String> {
+        match (policy, mode) {
+            (SymlinkPolicy::Skip, _) => Err(format!(
+                "Skipped {}: target is a symlink (see scan.symlinked_targets)",
+                path.display()
+            )),
+            (SymlinkPolicy::Contents, _) => Self::remove_contents(path, size, keep, mode, trash),
+            (SymlinkPolicy::Unlink, CleanMode::DryRun) => Ok((0, CleanAction::Unlinked)),
+            (SymlinkPolicy::Unlink, _) => vfs::current()
+                .remove_file(path)
+                .map(|_| (0, CleanAction::Unlinked))
+                .map_err(|e| format!("Failed to remove symlink {}: {}", path.display(), e)),
+        }
+    }
+
+    /// Empties a directory, keeping the directory itself (and any symlink or mount leading to it)
+    ///
+    /// Kept subpaths are left in place, and only the bytes around them count as freed.
+    fn remove_contents(
+        path: &Path,
+        size: u64,
+        keep: &[PathBuf],
+        mode: CleanMode,
+        trash: &Option<Trash>,
+    ) -> Result<(u64, CleanAction), String> {
+        if !keep.is_empty() {
+            return Self::remove_except(path, keep, mode, trash)
+                .map(|freed| (freed, CleanAction::Pruned));
+        }
+        if mode == CleanMode::DryRun {
+            return Ok((size, CleanAction::Emptied));
+        }
+        Self::empty_directory(path, trash)
+            .map(|_| (size, CleanAction::Emptied))
+            .map_err(|e| format!("Failed to empty {}: {}", path.display(), e))
+    }
+
+    /// Removes the parts of a target in a scope, returning the bytes freed
+    ///
+    /// Parts overlapping a kept subpath are left alone.
+    fn remove_scope(
+        target_info: &TargetInfo,
+        scope: CleanScope,
+        keep: &[PathBuf],
+        mode: CleanMode,
+        trash: &Option<Trash>,
+    ) -> Result<u64, String> {
+        let path = &target_info.path;
+        let parts: Vec<PathBuf> = scope
+            .paths(target_info)
+            .into_iter()
+            .filter(|part| {
+                let relative = part.strip_prefix(path).unwrap_or(part);
+                !keep
+                    .iter()
+                    .any(|k| k.starts_with(relative) || relative.starts_with(k))
+            })
+            .collect();
+        if parts.is_empty() {
+            return Ok(0);
+        }
+
+        let mut freed = 0;
+        let mut errors = Vec::new();
+        for part in parts {
+            let size = TargetFinder::path_size(&part);
+            match Self::remove_path(&part, size, mode, trash) {
+                Ok(()) => freed += size,
+                Err(error) => errors.push(error),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(freed)
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+
+    /// Removes everything inside a target except the kept subpaths, returning the bytes freed
+    ///
+    /// Directories leading to a kept path are descended into rather than removed whole.
+    fn remove_except(
+        path: &Path,
+        keep: &[PathBuf],
+        mode: CleanMode,
+        trash: &Option<Trash>,
+    ) -> Result<u64, String> {
+        let mut freed = 0;
+        let mut errors = Vec::new();
+        Self::prune(
+            path,
+            Path::new(""),
+            keep,
+            mode,
+            trash,
+            &mut freed,
+            &mut errors,
+        );
+
+        if errors.is_empty() {
+            Ok(freed)
+        } else {
+            Err(format!(
+                "Failed to clean {}: {}",
+                path.display(),
+                errors.join("; ")
+            ))
+        }
+    }
+
+    /// Removes the entries of `dir` (at `relative` inside the target) that are not kept
+    fn prune(
+        dir: &Path,
+        relative: &Path,
+        keep: &[PathBuf],
+        mode: CleanMode,
+        trash: &Option<Trash>,
+        freed: &mut u64,
+        errors: &mut Vec<String>,
+    ) {
+        let fs = vfs::current();
+        let entries = match fs.read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                errors.push(format!("{}: {}", dir.display(), e));
+                return;
+            }
+        };
+
+        for entry in entries {
+            let relative = relative.join(entry.name());
+            let path = entry.path;
+            if keep.contains(&relative) {
+                continue;
+            }
+            let is_dir = entry.kind == vfs::EntryKind::Dir;
+            if is_dir && keep.iter().any(|k| k.starts_with(&relative)) {
+                Self::prune(&path, &relative, keep, mode, trash, freed, errors);
+                continue;
+            }
+
+            let size = TargetFinder::path_size(&path);
+            let outcome = match (mode, trash) {
+                (CleanMode::DryRun, _) => Ok(()),
+                (CleanMode::Trash, Some(trash)) => trash.put(&path, size).map(|_| ()),
+                _ if is_dir => fs.remove_dir_all(&path).map_err(|e| e.into()),
+                _ => fs.remove_file(&path).map_err(|e| e.into()),
+            };
+            match outcome {
+                Ok(()) => *freed += size,
+                Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+    }
+
+    /// Removes every entry inside a directory, moving them to the trash when one is given
+    fn empty_directory(path: &Path, trash: &Option<Trash>) -> Result<(), Box<dyn Error>> {
+        let fs = vfs::current();
+        for entry in fs.read_dir(path)? {
+            match trash {
+                Some(trash) => {
+                    trash.put(&entry.path, 0)?;
+                }
+                None if entry.kind == vfs::EntryKind::Dir => fs.remove_dir_all(&entry.path)?,
+                None => fs.remove_file(&entry.path)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes a directory through the configured elevation command (e.g. `sudo rm -rf`)
+    fn remove_elevated(path: &Path, elevate_command: &[String]) -> Result<(), String> {
+        let status = Command::new(&elevate_command[0])
+            .args(&elevate_command[1..])
+            .args(["rm", "-rf", "--"])
+            .arg(path)
+            .status();
+
+        match status {
+            Ok(status) if status.success() => Ok(()),
+            Ok(status) => Err(format!(
+                "Failed to delete {}: {} exited with {}",
+                path.display(),
+                elevate_command.join(" "),
+                status
+            )),
+            Err(e) => Err(format!("Failed to delete {}: {}", path.display(), e)),
+        }
+    }
+
+    /// Removes a target directory on a remote host over SSH
+    fn remove_remote(host: &str, path: &Path, mode: CleanMode) -> Result<(), String> {
+        let outcome = match mode {
+            CleanMode::DryRun => return Ok(()),
+            CleanMode::Trash => Err("trash mode is not supported for remote targets".into()),
+            CleanMode::Delete if vfs::current().is_virtual() => {
+                Err("remote targets cannot be simulated".into())
+            }
+            CleanMode::Delete => RemoteHost::new(host).remove_dir(path),
+        };
+        outcome.map_err(|e| format!("Failed to delete {}:{}: {}", host, path.display(), e))
     }
 
     /// Delete a target directory and all its contents
     fn delete_target_directory(target_path: &Path) -> Result<(), Box<dyn Error>> {
+        let fs = vfs::current();
         // Check if the path exists before trying to delete
-        if !target_path.exists() {
+        if !fs.exists(target_path) {
             return Ok(()); // Already deleted
         }
+        if fs.is_virtual() {
+            return Ok(fs.remove_dir_all(target_path)?);
+        }
 
-        // Remove the directory and all its contents
-        fs::remove_dir_all(target_path)?;
-        Ok(())
+        // Renamed away at once; the contents are removed in the background
+        pending::delete(target_path)
+    }
+
+    /// Waits for background removals, turning failed ones back into errors in the result
+    fn finish_pending(result: &mut CleanupResult, sizes: impl Fn(&Path) -> u64) {
+        for (path, error) in pending::finish() {
+            let error = format!("Failed to delete {}: {}", path.display(), error);
+            result.total_freed = result.total_freed.saturating_sub(sizes(&path));
+            result.projects_cleaned = result.projects_cleaned.saturating_sub(1);
+            result.errors.push(error);
+        }
     }
 }
 
+/// How much free space grew on the filesystems sampled in `before`, if any could be measured
+fn reclaimed_since(before: &[(PathBuf, u64)]) -> Option<u64> {
+    if before.is_empty() {
+        return None;
+    }
+    Some(
+        before
+            .iter()
+            .map(|(dir, free)| free_space(dir).unwrap_or(*free).saturating_sub(*free))
+            .sum(),
+    )
+}
+
 /// Result of a cleanup operation
-#[derive(Debug)]
+#[derive(Debug, Clone, Default)]
 pub struct CleanupResult {
     /// Total bytes freed
     pub total_freed: u64,
+    /// Number of target directories cleaned
+    pub projects_cleaned: usize,
     /// List of errors that occurred
     pub errors: Vec<String>,
+    /// Growth of free space on the affected filesystems, measured around a live cleanup
+    ///
+    /// Differs from `total_freed` when files were hard-linked or reflinked elsewhere,
+    /// sizes were estimated, or other programs wrote to the disk meanwhile.
+    pub space_reclaimed: Option<u64>,
+    /// Local Time Machine snapshots on the affected volumes after a live cleanup
+    ///
+    /// They keep the removed files' blocks until macOS thins them, so the freed space
+    /// may not show up as free right away.
+    pub snapshots: usize,
+}
+
+impl CleanupResult {
+    /// Adds another cleanup to this one
+    pub fn merge(&mut self, other: CleanupResult) {
+        self.total_freed += other.total_freed;
+        self.projects_cleaned += other.projects_cleaned;
+        self.errors.extend(other.errors);
+        self.space_reclaimed = match (self.space_reclaimed, other.space_reclaimed) {
+            (None, None) => None,
+            (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+        };
+        self.snapshots = self.snapshots.max(other.snapshots);
+    }
+
+    /// Records how much free space the filesystems measured in `before` gained
+    fn measure_reclaimed(&mut self, before: Option<&[(PathBuf, u64)]>) {
+        let Some(before) = before else { return };
+        self.space_reclaimed = reclaimed_since(before);
+        if self.total_freed > 0 {
+            self.snapshots = before
+                .iter()
+                .map(|(dir, _)| time_machine::local_snapshots(dir))
+                .max()
+                .unwrap_or(0);
+        }
+    }
 }
 
-/// Format bytes into a human-readable string
-fn format_bytes(bytes: u64) -> String {
-    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
-    const THRESHOLD: f64 = 1024.0;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::rust_project_scaner::RustProjectScanner;
+    use crate::vfs::FileSystem;
+    use std::time::{Duration, SystemTime};
+
+    /// Lays out a project built three months ago in the shared memory filesystem
+    fn add_project(root: &Path, name: &str, deps_bytes: u64) -> PathBuf {
+        let fs = vfs::test_fs();
+        let built = SystemTime::now() - Duration::from_secs(90 * 24 * 60 * 60);
+        let project = root.join(name);
+        let manifest = format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\n", name);
+        fs.add_text(project.join("Cargo.toml"), &manifest, built);
+        fs.add_text(project.join("src/main.rs"), "fn main() {}\n", built);
+        fs.add_text(
+            project.join("target/CACHEDIR.TAG"),
+            "# This file is a cache directory tag created by cargo.\n",
+            built,
+        );
+        fs.add_file(
+            project.join("target/debug/deps/libapp.rlib"),
+            deps_bytes,
+            built,
+        );
+        fs.add_file(project.join("target/debug/incremental/app/s-1"), 300, built);
+        fs.add_file(project.join("target/release/app"), 1000, built);
+        project
+    }
+
+    fn scan(root: &Path) -> Vec<RustProject> {
+        let scanner = RustProjectScanner::new_with_ignores(&[root.to_path_buf()], &[], &[])
+            .expect("root exists");
+        let mut dirs = scanner
+            .find_project_dirs(&CancelToken::default())
+            .expect("scan succeeds");
+        dirs.sort();
+        dirs.into_iter()
+            .map(|dir| RustProjectScanner::analyze(dir).expect("project is readable"))
+            .collect()
+    }
+
+    fn options(root: &Path, mode: CleanMode) -> CleanOptions {
+        CleanOptions {
+            mode,
+            elevate_command: Vec::new(),
+            symlinked_targets: SymlinkPolicy::Skip,
+            project_rules: Vec::new(),
+            scope: None,
+            cancel: None,
+            exclude_from_backups: false,
+            roots: vec![root.to_path_buf()],
+        }
+    }
 
-    if bytes == 0 {
-        return "0 B".to_string();
+    fn target_size(project: &RustProject) -> u64 {
+        project.target_info.as_ref().map_or(0, |t| t.size_bytes)
     }
 
-    let bytes_f = bytes as f64;
-    let unit_index = (bytes_f.log10() / THRESHOLD.log10()).floor() as usize;
-    let unit_index = unit_index.min(UNITS.len() - 1);
-    let scaled = bytes_f / THRESHOLD.powi(unit_index as i32);
+    #[test]
+    fn cleans_only_the_selected_targets() {
+        let root = Path::new("/sim/selected");
+        let a = add_project(root, "a", 5000);
+        let b = add_project(root, "b", 7000);
+        let projects = scan(root);
+        assert_eq!(projects.len(), 2);
+        assert_eq!(target_size(&projects[0]), 5000 + 300 + 1000 + 55);
+
+        let options = options(root, CleanMode::Delete);
+        let mut cleanup = TargetCleaner::clean(&projects, &[true, false], &options).unwrap();
+        let events: Vec<CleanEvent> = cleanup.by_ref().collect();
+        let result = cleanup.finish();
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].project, a);
+        assert_eq!(result.total_freed, target_size(&projects[0]));
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        let fs = vfs::test_fs();
+        assert!(!fs.exists(&a.join("target")));
+        assert!(fs.exists(&a.join("Cargo.toml")));
+        assert!(fs.exists(&b.join("target/debug/deps/libapp.rlib")));
+
+        let rescanned = scan(root);
+        assert!(rescanned[0].target_info.is_none());
+        assert_eq!(target_size(&rescanned[1]), target_size(&projects[1]));
+    }
+
+    #[test]
+    fn dry_run_removes_nothing() {
+        let root = Path::new("/sim/dry-run");
+        let a = add_project(root, "a", 5000);
+        let projects = scan(root);
+
+        let options = options(root, CleanMode::DryRun);
+        let mut cleanup = TargetCleaner::clean(&projects, &[true], &options).unwrap();
+        assert_eq!(cleanup.by_ref().count(), 1);
+        let result = cleanup.finish();
+
+        assert_eq!(result.total_freed, target_size(&projects[0]));
+        assert!(vfs::test_fs().exists(&a.join("target/release/app")));
+    }
+
+    #[test]
+    fn cancelling_leaves_the_remaining_targets() {
+        let root = Path::new("/sim/cancel");
+        add_project(root, "a", 5000);
+        let b = add_project(root, "b", 7000);
+        let projects = scan(root);
+
+        let cancel = CancelToken::default();
+        let options = options(root, CleanMode::Delete).with_cancel(cancel.clone());
+        let mut cleanup = TargetCleaner::clean(&projects, &[true, true], &options).unwrap();
+        assert!(cleanup.next().is_some());
+        cancel.cancel();
+        assert!(cleanup.next().is_none());
+        let result = cleanup.finish();
+
+        assert_eq!(result.projects_cleaned, 1);
+        assert_eq!(result.errors.len(), 1);
+        assert!(vfs::test_fs().exists(&b.join("target")));
+    }
+
+    #[test]
+    fn scoped_cleanup_keeps_the_rest_of_the_target() {
+        let root = Path::new("/sim/scope");
+        let a = add_project(root, "a", 5000);
+        let projects = scan(root);
+
+        let options = options(root, CleanMode::Delete).with_scope(Some(CleanScope::Incremental));
+        let mut cleanup = TargetCleaner::clean(&projects, &[true], &options).unwrap();
+        let events: Vec<CleanEvent> = cleanup.by_ref().collect();
+        let result = cleanup.finish();
+
+        assert_eq!(events[0].action, CleanAction::Pruned);
+        assert_eq!(result.total_freed, 300);
+        let fs = vfs::test_fs();
+        assert!(!fs.exists(&a.join("target/debug/incremental")));
+        assert!(fs.exists(&a.join("target/debug/deps/libapp.rlib")));
+    }
+
+    #[test]
+    fn emptying_a_mounted_target_keeps_the_kept_paths() {
+        let root = Path::new("/sim/mounted-keep");
+        let a = add_project(root, "a", 5000);
+        let mut projects = scan(root);
+        if let Some(target_info) = projects[0].target_info.as_mut() {
+            target_info.device = Some(7);
+        }
+
+        let mut options = options(root, CleanMode::Delete);
+        options.project_rules = vec![ProjectRule {
+            path: Some(a.clone()),
+            name: None,
+            keep: vec![PathBuf::from("release/app")],
+        }];
+        let mut cleanup = TargetCleaner::clean(&projects, &[true], &options).unwrap();
+        let events: Vec<CleanEvent> = cleanup.by_ref().collect();
+        let result = cleanup.finish();
+
+        assert_eq!(events[0].action, CleanAction::Pruned);
+        assert_eq!(result.total_freed, target_size(&projects[0]) - 1000);
+        let fs = vfs::test_fs();
+        assert!(fs.exists(&a.join("target/release/app")));
+        assert!(!fs.exists(&a.join("target/debug")));
+    }
+
+    #[test]
+    fn refuses_targets_outside_the_roots() {
+        let root = Path::new("/sim/outside");
+        let a = add_project(root, "a", 5000);
+        let projects = scan(root);
+
+        let options = options(Path::new("/sim/elsewhere"), CleanMode::Delete);
+        let mut cleanup = TargetCleaner::clean(&projects, &[true], &options).unwrap();
+        let events: Vec<CleanEvent> = cleanup.by_ref().collect();
+        let result = cleanup.finish();
 
-    if unit_index == 0 {
-        format!("{} {}", bytes, UNITS[unit_index])
-    } else {
-        format!("{:.2} {}", scaled, UNITS[unit_index])
+        assert!(
+            events[0]
+                .error
+                .as_deref()
+                .is_some_and(|e| e.contains("outside"))
+        );
+        assert_eq!(result.total_freed, 0);
+        assert!(vfs::test_fs().exists(&a.join("target")));
     }
 }
@@ -0,0 +1,78 @@
+use std::path::Path;
+#[cfg(target_os = "macos")]
+use std::process::Command;
+
+/// Keeps a target directory out of Time Machine backups
+///
+/// A target that still exists gets a sticky exclusion, which travels with the directory.
+/// A removed one is excluded by its fixed path, so the directory cargo recreates there is
+/// left out as well; macOS only allows that as root, hence the elevation command.
+#[cfg(target_os = "macos")]
+pub fn exclude(path: &Path, elevate_command: &[String]) -> Result<(), String> {
+    let mut command = if path.exists() {
+        let mut command = Command::new("tmutil");
+        command.arg("addexclusion");
+        command
+    } else if let Some((program, args)) = elevate_command.split_first() {
+        let mut command = Command::new(program);
+        command.args(args).args(["tmutil", "addexclusion", "-p"]);
+        command
+    } else {
+        let mut command = Command::new("tmutil");
+        command.args(["addexclusion", "-p"]);
+        command
+    };
+
+    match command.arg(path).status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!(
+            "Failed to exclude {} from Time Machine: tmutil exited with {}{}",
+            path.display(),
+            status,
+            if path.exists() || !elevate_command.is_empty() {
+                ""
+            } else {
+                " (excluding a removed directory needs settings.elevate_command, e.g. [\"sudo\"])"
+            }
+        )),
+        Err(e) => Err(format!(
+            "Failed to exclude {} from Time Machine: {}",
+            path.display(),
+            e
+        )),
+    }
+}
+
+/// Time Machine only exists on macOS
+#[cfg(not(target_os = "macos"))]
+pub fn exclude(path: &Path, _elevate_command: &[String]) -> Result<(), String> {
+    Err(format!(
+        "Cannot exclude {} from Time Machine: only supported on macOS",
+        path.display()
+    ))
+}
+
+/// Number of local Time Machine snapshots of the volume holding `path`
+///
+/// Files deleted after a snapshot was taken keep their blocks until macOS thins the
+/// snapshot, so a cleanup frees less than it removed while any exist.
+#[cfg(target_os = "macos")]
+pub fn local_snapshots(path: &Path) -> usize {
+    let Ok(output) = Command::new("tmutil")
+        .arg("listlocalsnapshots")
+        .arg(path)
+        .output()
+    else {
+        return 0;
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| line.starts_with("com.apple.TimeMachine."))
+        .count()
+}
+
+/// Local snapshots are specific to Time Machine on macOS
+#[cfg(not(target_os = "macos"))]
+pub fn local_snapshots(_path: &Path) -> usize {
+    0
+}
@@ -0,0 +1,280 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::cache::cache_dir;
+
+/// Name of the manifest file stored next to each trashed directory
+const MANIFEST_NAME: &str = "entry.toml";
+/// Name under which the trashed content is stored inside an entry directory
+const CONTENT_NAME: &str = "content";
+
+/// Counter keeping the ids of entries trashed within the same instant apart
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// Quarantine area for removed target directories, kept under the cache dir
+pub struct Trash {
+    root: PathBuf,
+}
+
+/// A previously removed target directory that can be restored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashEntry {
+    /// Identifier of the entry (its directory name in the trash)
+    #[serde(skip)]
+    pub id: String,
+    /// Where the directory lived before it was removed
+    pub original_path: PathBuf,
+    /// Size of the directory when it was removed
+    pub size_bytes: u64,
+    /// Removal time as seconds since the Unix epoch
+    pub removed_at: u64,
+}
+
+impl TrashEntry {
+    /// Returns the removal time as a `SystemTime`
+    pub fn removed_time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.removed_at)
+    }
+}
+
+impl Trash {
+    /// Opens the trash in the tool's cache directory
+    pub fn open() -> Result<Self, Box<dyn Error>> {
+        let root = cache_dir()?.join("trash");
+        fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    /// Moves a path into the trash, recording where it came from
+    pub fn put(&self, path: &Path, size_bytes: u64) -> Result<TrashEntry, Box<dyn Error>> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+        let name = path
+            .parent()
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "target".to_string());
+        // Siblings are trashed in quick succession, and another instance may share the
+        // trash; an existing entry directory is never reused
+        let (id, entry_dir) = loop {
+            let sequence = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+            let id = format!("{}-{}-{}", now.as_nanos(), sequence, name);
+            let entry_dir = self.root.join(&id);
+            match fs::create_dir(&entry_dir) {
+                Ok(()) => break (id, entry_dir),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e.into()),
+            }
+        };
+
+        let entry = TrashEntry {
+            id,
+            original_path: path.to_path_buf(),
+            size_bytes,
+            removed_at: now.as_secs(),
+        };
+        let stored = toml::to_string(&entry)
+            .map_err(Into::into)
+            .and_then(|manifest| Ok(fs::write(entry_dir.join(MANIFEST_NAME), manifest)?))
+            .and_then(|_| move_path(path, &entry_dir.join(CONTENT_NAME)));
+        if let Err(e) = stored {
+            // Only the directory created above, and only while it holds nothing of the
+            // user's: a complete copy is kept when the original was partly removed
+            if !entry_dir.join(CONTENT_NAME).exists() {
+                let _ = fs::remove_dir_all(&entry_dir);
+            }
+            return Err(e);
+        }
+
+        Ok(entry)
+    }
+
+    /// Lists trashed entries, most recently removed first
+    pub fn list(&self) -> Result<Vec<TrashEntry>, Box<dyn Error>> {
+        let mut entries = Vec::new();
+
+        for dir in fs::read_dir(&self.root)?.filter_map(Result::ok) {
+            let manifest = dir.path().join(MANIFEST_NAME);
+            let Ok(content) = fs::read_to_string(&manifest) else {
+                continue;
+            };
+            if let Ok(mut entry) = toml::from_str::<TrashEntry>(&content) {
+                entry.id = dir.file_name().to_string_lossy().into_owned();
+                entries.push(entry);
+            }
+        }
+
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.removed_at));
+        Ok(entries)
+    }
+
+    /// Permanently removes the entries trashed longer than `max_age` ago, returning them
+    pub fn prune(&self, max_age: Duration) -> Result<Vec<TrashEntry>, Box<dyn Error>> {
+        // An age reaching back before the epoch keeps everything
+        let Some(cutoff) = SystemTime::now().checked_sub(max_age) else {
+            return Ok(Vec::new());
+        };
+        let mut pruned = Vec::new();
+        for entry in self.list()? {
+            if entry.removed_time() < cutoff {
+                fs::remove_dir_all(self.root.join(&entry.id))?;
+                pruned.push(entry);
+            }
+        }
+        Ok(pruned)
+    }
+
+    /// Moves a trashed entry back to its original location
+    pub fn restore(&self, entry: &TrashEntry) -> Result<(), Box<dyn Error>> {
+        if entry.original_path.exists() {
+            return Err(format!(
+                "Cannot restore: {} already exists",
+                entry.original_path.display()
+            )
+            .into());
+        }
+
+        if let Some(parent) = entry.original_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let entry_dir = self.root.join(&entry.id);
+        move_path(&entry_dir.join(CONTENT_NAME), &entry.original_path)?;
+        fs::remove_dir_all(&entry_dir)?;
+        Ok(())
+    }
+}
+
+/// Moves a file or directory, copying when source and destination are on different devices
+fn move_path(from: &Path, to: &Path) -> Result<(), Box<dyn Error>> {
+    match fs::rename(from, to) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::CrossesDevices => {
+            if let Err(e) = copy_recursive(from, to) {
+                // The original is untouched; an incomplete copy is worth nothing
+                let _ = fs::remove_dir_all(to).or_else(|_| fs::remove_file(to));
+                return Err(e);
+            }
+            if from.is_dir() {
+                fs::remove_dir_all(from)?;
+            } else {
+                fs::remove_file(from)?;
+            }
+            Ok(())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Copies a file or directory tree
+fn copy_recursive(from: &Path, to: &Path) -> Result<(), Box<dyn Error>> {
+    if !from.is_dir() {
+        fs::copy(from, to)?;
+        return Ok(());
+    }
+
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    /// Trash and a project directory of its own for one test
+    fn setup(test: &str) -> (Trash, PathBuf) {
+        let dir = std::env::temp_dir().join(format!(
+            "clear-target-trash-{}-{}",
+            test,
+            std::process::id()
+        ));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(dir.join("trash")).unwrap();
+        fs::create_dir_all(dir.join("app")).unwrap();
+        (
+            Trash {
+                root: dir.join("trash"),
+            },
+            dir,
+        )
+    }
+
+    fn add_target(project: &Path) -> PathBuf {
+        let target = project.join("target");
+        fs::create_dir_all(target.join("debug")).unwrap();
+        fs::write(target.join("debug/app"), "bin").unwrap();
+        target
+    }
+
+    #[test]
+    fn gives_entries_trashed_together_their_own_ids() {
+        let (trash, dir) = setup("ids");
+        let debug = add_target(&dir.join("app")).join("debug");
+        fs::create_dir_all(dir.join("app/target/release")).unwrap();
+
+        let first = trash.put(&debug, 3).unwrap();
+        let second = trash.put(&dir.join("app/target/release"), 0).unwrap();
+        assert_ne!(first.id, second.id);
+        assert_eq!(trash.list().unwrap().len(), 2);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn restores_an_entry_to_where_it_was() {
+        let (trash, dir) = setup("restore");
+        let target = add_target(&dir.join("app"));
+
+        let entry = trash.put(&target, 3).unwrap();
+        assert!(!target.exists());
+        trash.restore(&entry).unwrap();
+        assert_eq!(fs::read_to_string(target.join("debug/app")).unwrap(), "bin");
+        assert!(trash.list().unwrap().is_empty());
+
+        let entry = trash.put(&target, 3).unwrap();
+        fs::create_dir_all(&target).unwrap();
+        assert!(trash.restore(&entry).is_err());
+        assert_eq!(trash.list().unwrap().len(), 1);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn prunes_only_entries_older_than_the_age() {
+        let (trash, dir) = setup("prune");
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        for (id, days) in [("old", 31), ("recent", 29)] {
+            let entry = TrashEntry {
+                id: id.to_string(),
+                original_path: dir.join(id).join("target"),
+                size_bytes: 1,
+                removed_at: now - days * DAY,
+            };
+            fs::create_dir_all(trash.root.join(id).join(CONTENT_NAME)).unwrap();
+            fs::write(
+                trash.root.join(id).join(MANIFEST_NAME),
+                toml::to_string(&entry).unwrap(),
+            )
+            .unwrap();
+        }
+
+        let pruned = trash.prune(Duration::from_secs(30 * DAY)).unwrap();
+        assert_eq!(pruned.len(), 1);
+        assert_eq!(pruned[0].id, "old");
+        assert!(!trash.root.join("old").exists());
+        assert!(trash.root.join("recent").exists());
+        assert!(trash.prune(Duration::MAX).unwrap().is_empty());
+        fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,50 @@
+use crate::scanner::rust_project::RustProject;
+use crate::scanner::target_finder::TargetFinder;
+
+/// Relative change of a target's size that is worth a warning
+const SIGNIFICANT_CHANGE: f64 = 0.1;
+
+/// Absolute change below which a target's size never counts as changed
+const MIN_CHANGE_BYTES: u64 = 1024 * 1024;
+
+/// A selected target whose size moved noticeably between the scan and the cleanup
+#[derive(Debug, Clone)]
+pub struct SizeChange {
+    /// Display path of the project
+    pub project: String,
+    /// Size recorded by the scan (or taken from the size cache)
+    pub scanned: u64,
+    /// Size measured just now
+    pub current: u64,
+}
+
+/// Re-measures the targets of the selected projects and updates their sizes
+///
+/// Returns the targets whose size changed significantly, e.g. because they were
+/// rebuilt or partly cleaned after the scan, or the cached size was out of date.
+pub fn verify_sizes(projects: &mut [RustProject], selected: &[bool]) -> Vec<SizeChange> {
+    let mut changes = Vec::new();
+    for (project, _) in projects
+        .iter_mut()
+        .zip(selected)
+        .filter(|(project, selected)| **selected && project.host.is_none())
+    {
+        let display_path = project.display_path();
+        let Some(target_info) = project.target_info.as_mut() else {
+            continue;
+        };
+        let scanned = target_info.size_bytes;
+        let current = TargetFinder::measure_size(&target_info.path);
+        target_info.size_bytes = current;
+
+        let change = scanned.abs_diff(current);
+        if change >= MIN_CHANGE_BYTES && change as f64 >= scanned as f64 * SIGNIFICANT_CHANGE {
+            changes.push(SizeChange {
+                project: display_path,
+                scanned,
+                current,
+            });
+        }
+    }
+    changes
+}
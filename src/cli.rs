@@ -0,0 +1,180 @@
+use clap::{Parser, Subcommand, ValueHint};
+use clap_complete::Shell;
+use std::path::PathBuf;
+
+use crate::cleaner::scope::CleanScope;
+use crate::report::ReportFormat;
+use crate::scanner::rust_project::BuildHealth;
+
+/// Command-line interface for the Rust target cleaner
+#[derive(Debug, Parser)]
+#[command(
+    name = "clear-target",
+    version,
+    about = "Find and clean stale Rust target directories"
+)]
+pub struct Cli {
+    /// Print a one-line JSON summary of the run to stdout when finished
+    #[arg(long, global = true)]
+    pub summary_json: bool,
+
+    /// Scan and clean a remote build server over SSH (e.g. user@build-server)
+    ///
+    /// The remote host must have clear-target installed and on its PATH.
+    #[arg(long, global = true, value_name = "HOST", value_hint = ValueHint::Hostname)]
+    pub host: Option<String>,
+
+    /// Use the project list maintained by `clear-target watch` instead of scanning
+    #[arg(long, global = true)]
+    pub cached: bool,
+
+    /// Select projects matching an expression instead of the stale ones
+    ///
+    /// For example `size > 1GB && age > 30d && path ~ "~/oss"`. Fields are size, age
+    /// (days since last use, e.g. 2w), path, name, tag, remote (git host and owner, e.g.
+    /// github.com/mycompany), build (passing, broken or unknown) and stale; `~` means "contains".
+    /// Cleans the matches headless, pre-selects them in the UI and filters `list`.
+    #[arg(long, global = true, value_name = "EXPR")]
+    pub select: Option<String>,
+
+    /// Open a graphical window instead of the terminal UI
+    #[cfg(feature = "gui")]
+    #[arg(long)]
+    pub gui: bool,
+
+    /// Disable colored output (also disabled by a non-empty NO_COLOR variable)
+    #[arg(long, global = true)]
+    pub no_color: bool,
+
+    /// Apply the settings of a `[profile.<NAME>]` table of the configuration, e.g. `work`
+    /// (defaults to the CLEAR_TARGET_PROFILE variable)
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Also find target directories left behind without a Cargo.toml (scan.orphans)
+    #[arg(long, global = true)]
+    pub orphans: bool,
+
+    /// Scan every user's home directory instead of the search paths and group the
+    /// results per user (needs root)
+    #[arg(long, global = true)]
+    pub all_users: bool,
+
+    /// Report time spent in discovery, sizing, staleness checks, rendering and cleaning
+    #[arg(long, global = true)]
+    pub timing: bool,
+
+    /// Clean for real, but only in an in-memory view of the disk: the run reports what it
+    /// removed while nothing on disk, in the trash or in the caches changes
+    #[arg(long, global = true)]
+    pub simulate: bool,
+
+    /// Subcommand to run (defaults to the interactive terminal UI)
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Build outcomes accepted by `clear-target mark`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum BuildMark {
+    Passing,
+    Broken,
+    Clear,
+}
+
+impl BuildMark {
+    /// Health to store for the project, `None` to remove the mark
+    pub fn health(self) -> Option<BuildHealth> {
+        match self {
+            BuildMark::Passing => Some(BuildHealth::Passing),
+            BuildMark::Broken => Some(BuildHealth::Broken),
+            BuildMark::Clear => None,
+        }
+    }
+}
+
+/// Available subcommands
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Clean all stale target directories without starting the UI
+    Clean {
+        /// Read newline-separated project paths from FILE ('-' for stdin) instead of scanning
+        #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+        paths_from: Option<PathBuf>,
+        /// Only remove this part of each stale target, e.g. the incremental caches or docs
+        #[arg(long, value_enum, value_name = "PART")]
+        only: Option<CleanScope>,
+        /// Clean each target as soon as it is sized instead of after the scan, keeping no
+        /// project list in memory (for huge scans on small CI machines)
+        #[arg(long, conflicts_with = "paths_from")]
+        stream: bool,
+    },
+    /// Clean every project listed in a plan saved from the UI with 'w'
+    Apply {
+        /// Plan file ('-' for stdin)
+        #[arg(value_name = "PLAN", default_value = "clear-target-plan.txt", value_hint = ValueHint::FilePath)]
+        plan: PathBuf,
+    },
+    /// Analyze the project in the current directory and offer to clean its target
+    Here,
+    /// Fuzzy-find projects by name or path, mark them with Tab and clean the picks
+    Pick,
+    /// Browse trashed target directories and restore them
+    Restore,
+    /// Keep the cached project list up to date by watching the search paths for changes
+    Watch,
+    /// Print a read-only report of the largest and stale targets, with the trend since the last one
+    Report {
+        /// Write the report to a file in this format instead of printing it
+        #[arg(long, value_enum, value_name = "FORMAT")]
+        report_format: Option<ReportFormat>,
+        /// File to write a formatted report to (defaults to target-report.<ext>)
+        #[arg(
+            long,
+            short,
+            value_name = "FILE",
+            requires = "report_format",
+            value_hint = ValueHint::FilePath
+        )]
+        output: Option<PathBuf>,
+    },
+    /// Print totals over all projects: sizes, stale vs fresh, largest targets, per search path
+    Stats,
+    /// List projects with target directories without cleaning anything
+    List {
+        /// Print the listing as JSON
+        #[arg(long)]
+        json: bool,
+        /// Print each project as soon as it is sized, unsorted and keeping no project list
+        /// in memory; with --json, one object per line
+        #[arg(long)]
+        stream: bool,
+    },
+    /// Record whether the last build of a project succeeded, e.g. from a build wrapper:
+    /// `cargo build && clear-target mark passing || clear-target mark broken`
+    Mark {
+        /// Outcome of the build, or `clear` to forget it
+        #[arg(value_enum)]
+        build: BuildMark,
+        /// Project directory, or any directory inside it (defaults to the current one)
+        #[arg(value_hint = ValueHint::DirPath)]
+        path: Option<PathBuf>,
+    },
+    /// List projects found by earlier scans that have since been moved or deleted
+    Index {
+        /// Forget them, along with their tags, build marks and cleanup history
+        #[arg(long)]
+        purge: bool,
+        /// List every indexed project with when it was first and last found
+        #[arg(long, conflicts_with = "purge")]
+        all: bool,
+    },
+    /// Print the patterns of directories the scan skips, e.g. project templates and vendored crates
+    SkipPatterns,
+    /// Print a shell completion script (e.g. `clear-target completions bash > ~/.bash_completion.d/clear-target`)
+    Completions {
+        /// Shell to generate the script for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+}
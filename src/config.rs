@@ -1,5 +1,13 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+use crate::scanner::heuristics::{self, DEFAULT_SKIP_PATTERNS};
+use crate::scanner::rust_project::RustProject;
+use crate::scanner::users;
+use crate::size::parse_size;
+use crate::ui::is_browse_key;
+use std::collections::BTreeMap;
 use std::fs;
+use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
@@ -12,6 +20,8 @@ pub struct Config {
     pub exclude_patterns: Vec<String>,
     /// Directories to exclude from scanning
     pub ignore_paths: Vec<PathBuf>,
+    /// Glob patterns of directories whose projects are skipped, e.g. `*/templates/*`
+    pub skip_patterns: Vec<String>,
 
     /// Age threshold for considering a target directory stale
     pub stale_threshold: Duration,
@@ -19,23 +29,421 @@ pub struct Config {
     /// Number of days to consider a target directory as stale based on last access
     pub last_access_days: u64,
 
+    /// Which timestamps of a target tell when it was last used
+    pub staleness_metric: StalenessMetric,
+
     /// Whether to run in dry-run mode (show what would be deleted without actually deleting)
     pub dry_run: bool,
 
+    /// Whether live cleanups move target directories to the trash instead of deleting them
+    pub trash: bool,
+
+    /// Selected size above which live cleanups must be confirmed by typing
+    pub confirm_typed_above: u64,
+
+    /// Estimated rebuild time above which cleaning a target is warned about; zero never warns
+    pub rebuild_budget: Duration,
+
+    /// Seconds to wait, abortable with Esc, before a confirmed live cleanup starts
+    pub countdown_secs: u64,
+
+    /// Free space below which `clean` and `watch` act; above it they leave targets alone
+    pub auto_clean_when_free_below: Option<u64>,
+
+    /// Address `watch` serves a read-only page of target usage per user on, if any
+    pub report_address: Option<SocketAddr>,
+
+    /// Whether every user's home directory is scanned and results are grouped per user
+    pub all_users: bool,
+
+    /// Command prefix used to remove targets containing files owned by another user (e.g. `sudo`)
+    pub elevate_command: Vec<String>,
+
+    /// URL a JSON summary of every headless `clean` is posted to
+    pub webhook: Option<String>,
+
+    /// Address a summary of every headless `clean` is mailed to
+    pub notify_email: Option<String>,
+
+    /// Command the summary mail is piped to, with headers, for delivery
+    pub sendmail_command: Vec<String>,
+
+    /// Staleness policies overriding the stale threshold for tagged projects or remote owners
+    pub policies: Vec<Policy>,
+
+    /// Per-project settings, such as target subpaths that cleaning must keep
+    pub project_rules: Vec<ProjectRule>,
+
+    /// Whether the tool's own source project and the current project are protected
+    pub protect_own_projects: bool,
+
+    /// Canonical paths of the projects that cannot be selected for cleaning
+    pub protected_projects: Vec<PathBuf>,
+
+    /// Whether project discovery follows symlinked directories
+    pub follow_links: bool,
+
+    /// What cleaning does with target directories that are symlinks
+    pub symlinked_targets: SymlinkPolicy,
+
+    /// Whether target sizes are cached between runs and reused while targets are unchanged
+    pub size_cache: bool,
+
+    /// Whether every file is measured instead of estimating large directories from a sample
+    pub exact_sizes: bool,
+
+    /// Whether to scan as on a network filesystem, reading as little metadata as possible;
+    /// detected from the search paths when unset
+    pub network_fs: Option<bool>,
+
+    /// Maximum number of targets sized concurrently
+    pub jobs: usize,
+
+    /// Number of projects after which discovery stops sizing more
+    pub max_projects: Option<usize>,
+
+    /// Combined target size after which scanning stops sizing more projects
+    pub stop_after: Option<u64>,
+
+    /// Whether scans also report target directories left behind without a Cargo.toml
+    pub scan_orphans: bool,
+
+    /// Whether the home directories of WSL distributions are searched too (Windows only)
+    pub scan_wsl: bool,
+
     /// Whether to be verbose in output
     #[allow(dead_code)]
     pub verbose: bool,
 
     /// Whether to clear the terminal before starting the UI
     pub clear_terminal: bool,
+
+    /// Whether projects without a target directory are listed in the UI
+    pub show_empty: bool,
+
+    /// Whether the UI lists each project on a single line
+    pub compact: bool,
+
+    /// Columns of the compact layout after the project name, in order
+    pub columns: Vec<Column>,
+
+    /// Key selecting every stale project above `quick_clean_above` and asking to clean them
+    pub quick_clean_key: char,
+
+    /// Size from which the quick clean key selects a stale project
+    pub quick_clean_above: u64,
+
+    /// How long the tool keeps its own records in the cache directory
+    pub retention: Retention,
+
+    /// Whether selected targets are re-measured before a cleanup is confirmed or started
+    pub verify_sizes: bool,
+
+    /// Whether cleaned targets are excluded from Time Machine backups (macOS)
+    pub exclude_from_time_machine: bool,
+
+    /// Profile applied over the rest of the configuration, from `--profile` or
+    /// CLEAR_TARGET_PROFILE
+    pub profile: Option<String>,
+
+    /// Names of the profiles the configuration files define
+    pub profiles: Vec<String>,
+}
+
+/// Staleness policy for projects carrying a tag or cloned from a remote owner,
+/// from a `[[policy]]` table
+#[derive(Debug, Clone, Deserialize)]
+pub struct Policy {
+    /// Tag the rule applies to
+    pub tag: Option<String>,
+    /// Remote host and owner the rule applies to, e.g. `github.com/mycompany`
+    pub remote: Option<String>,
+    /// Login name of the owner of the project directories the rule applies to
+    pub user: Option<String>,
+    /// Days without use after which targets of matching projects are stale
+    pub lastseen: u64,
+}
+
+impl Policy {
+    /// Whether the rule applies to the project; a rule with several fields needs all to match
+    pub fn matches(&self, project: &RustProject) -> bool {
+        let tag = self
+            .tag
+            .as_ref()
+            .is_none_or(|tag| project.tags.contains(tag));
+        let remote = self.remote.as_ref().is_none_or(|remote| {
+            project.remote.as_ref().is_some_and(|owner| {
+                owner == &remote.to_lowercase()
+                    || owner.starts_with(&format!("{}/", remote.to_lowercase()))
+            })
+        });
+        // Looking up the owner reads the filesystem and the user database, so it comes last
+        tag && remote
+            && self
+                .user
+                .as_ref()
+                .is_none_or(|user| &users::owner_name(&project.path) == user)
+    }
+}
+
+/// Settings for the projects matching a path or name, from a `[[project]]` table
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProjectRule {
+    /// Project directory the rule applies to, canonicalized when it exists
+    pub path: Option<PathBuf>,
+    /// Package name the rule applies to, when no path is given
+    pub name: Option<String>,
+    /// Paths relative to the target directory that are never removed (e.g. `release/mybin`)
+    #[serde(default)]
+    pub keep: Vec<PathBuf>,
+}
+
+impl ProjectRule {
+    /// Whether the rule applies to the project
+    pub fn matches(&self, project: &RustProject) -> bool {
+        match (&self.path, &self.name) {
+            (Some(path), _) => *path == project.path,
+            (None, Some(name)) => *name == project.name,
+            (None, None) => false,
+        }
+    }
+
+    /// Target subpaths kept for a project by all rules matching it
+    pub fn keep_paths(rules: &[ProjectRule], project: &RustProject) -> Vec<PathBuf> {
+        rules
+            .iter()
+            .filter(|rule| rule.matches(project))
+            .flat_map(|rule| rule.keep.iter().cloned())
+            .collect()
+    }
+}
+
+/// Which timestamps of a target directory tell when it was last used
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StalenessMetric {
+    /// Modification times of the files in the target, updated by builds
+    #[default]
+    Mtime,
+    /// Access times of the built binaries and libraries, updated by running and linking them
+    ///
+    /// Falls back to modification times on filesystems mounted with `noatime`.
+    Atime,
+}
+
+/// Days the tool's own records are kept before they are pruned; 0 keeps them forever
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Retention {
+    /// Cleanups in the audit log, which the cleanup history shown in the UI is built from
+    pub history: u64,
+    /// Targets in the trash, counted from when they were trashed
+    pub trash: u64,
+    /// Projects in the index not found by any scan for this long
+    pub index: u64,
+    /// Cached target sizes not used by any scan for this long
+    pub sizes: u64,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            history: 365,
+            trash: 30,
+            index: 365,
+            sizes: 90,
+        }
+    }
+}
+
+/// A column of the compact project list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Column {
+    /// Project directory
+    Path,
+    /// Size of the target directory
+    Size,
+    /// When the target was last used
+    Age,
+    /// Whether the target is stale
+    Stale,
+    /// Cross-compilation targets built into the target directory
+    Triples,
+    /// Number of files in the target directory
+    Files,
+    /// Host and owner of the git remote
+    Remote,
+}
+
+/// How target directories that are symlinks are cleaned
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymlinkPolicy {
+    /// Leave them alone; they are listed but cannot be selected
+    #[default]
+    Skip,
+    /// Remove only the symlink, leaving the directory it points to untouched
+    Unlink,
+    /// Empty the directory the symlink points to, keeping the link and the directory
+    Contents,
+}
+
+/// Expands `~`, `$VAR`, `${VAR}` and Windows-style `%VAR%` in a configured path
+///
+/// Unknown variables are left as written, the way the shell and cmd.exe leave them.
+/// Parses a duration like `90s`, `10m` or `2h`; a bare number is minutes
+fn parse_duration(input: &str) -> Result<Duration, String> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("Invalid duration: {:?}", input))?;
+    let seconds_per_unit = match unit.trim() {
+        "s" => 1,
+        "" | "m" | "min" => 60,
+        "h" => 60 * 60,
+        other => return Err(format!("Unknown duration unit {:?} in {:?}", other, input)),
+    };
+    Ok(Duration::from_secs(number * seconds_per_unit))
+}
+
+pub fn expand_path(path: &str) -> PathBuf {
+    expand_path_with(path, dirs::home_dir(), |name| std::env::var(name).ok())
+}
+
+/// `expand_path` with the home directory and variable lookup supplied by the caller
+fn expand_path_with(
+    path: &str,
+    home: Option<PathBuf>,
+    var: impl Fn(&str) -> Option<String>,
+) -> PathBuf {
+    let home = home.map(|home| home.to_string_lossy().into_owned());
+    // `~` and `$VAR` in the text between %VAR% references; values are never expanded again
+    let expand_unix = |text: &str, at_start: bool| {
+        if at_start {
+            shellexpand::full_with_context_no_errors(text, || home.as_deref(), &var).into_owned()
+        } else {
+            shellexpand::env_with_context_no_errors(text, &var).into_owned()
+        }
+    };
+
+    let mut expanded = String::new();
+    let mut literal_start = 0;
+    let mut at_start = true;
+    let mut search_from = 0;
+    while let Some(offset) = path[search_from..].find('%') {
+        let start = search_from + offset;
+        let after = &path[start + 1..];
+        let name_len = after
+            .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '(' | ')')))
+            .unwrap_or(after.len());
+        let name = &after[..name_len];
+        match var(name).filter(|_| after[name_len..].starts_with('%')) {
+            Some(value) => {
+                expanded.push_str(&expand_unix(&path[literal_start..start], at_start));
+                expanded.push_str(&value);
+                at_start = false;
+                literal_start = start + name_len + 2;
+                search_from = literal_start;
+            }
+            // A lone percent sign or unknown variable is part of the path
+            None => search_from = start + 1,
+        }
+    }
+    expanded.push_str(&expand_unix(&path[literal_start..], at_start));
+    PathBuf::from(expanded)
 }
 
-/// TOML configuration structure for deserialization
+/// Configuration file names looked for, in order of preference
+const CONFIG_FILE_NAMES: [&str; 4] = [
+    "Cleaner.toml",
+    "Cleaner.yaml",
+    "Cleaner.yml",
+    "Cleaner.json",
+];
+
+/// Format of a configuration file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// Detects the format from the file extension; anything unknown is read as TOML
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml") => {
+                ConfigFormat::Yaml
+            }
+            Some(ext) if ext.eq_ignore_ascii_case("json") => ConfigFormat::Json,
+            _ => ConfigFormat::Toml,
+        }
+    }
+}
+
+/// Configuration file structure for deserialization, shared by all formats
 #[derive(Debug, Deserialize)]
 struct CleanerConfig {
+    include: Option<Vec<String>>,
+    profile: Option<BTreeMap<String, ConfigLayer>>,
+    #[serde(flatten)]
+    layer: ConfigLayer,
+}
+
+/// Sections of a configuration file, also making up each of its profiles
+#[derive(Debug, Default, Deserialize)]
+struct ConfigLayer {
     ignore: Option<IgnoreSection>,
     settings: Option<SettingsSection>,
     access: Option<AccessSection>,
+    scan: Option<ScanSection>,
+    notify: Option<NotifySection>,
+    ui: Option<UiSection>,
+    retention: Option<RetentionSection>,
+    watch: Option<WatchSection>,
+    policy: Option<Vec<Policy>>,
+    project: Option<Vec<ProjectRule>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NotifySection {
+    webhook: Option<String>,
+    email: Option<String>,
+    sendmail: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UiSection {
+    columns: Option<ColumnsSection>,
+    quick_clean_key: Option<String>,
+    quick_clean_above: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ColumnsSection {
+    show: Option<Vec<Column>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScanSection {
+    paths: Option<Vec<String>>,
+    follow_links: Option<bool>,
+    symlinked_targets: Option<SymlinkPolicy>,
+    size_cache: Option<bool>,
+    exact_sizes: Option<bool>,
+    network_fs: Option<bool>,
+    jobs: Option<usize>,
+    max_projects: Option<usize>,
+    stop_after: Option<String>,
+    orphans: Option<bool>,
+    wsl: Option<bool>,
+    skip_defaults: Option<bool>,
+    skip: Option<Vec<String>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -46,13 +454,38 @@ struct IgnoreSection {
 #[derive(Debug, Deserialize)]
 struct SettingsSection {
     dry_run: Option<bool>,
+    trash: Option<bool>,
+    confirm_typed_above: Option<String>,
+    rebuild_budget: Option<String>,
+    countdown: Option<u64>,
+    auto_clean_when_free_below: Option<String>,
+    elevate_command: Option<Vec<String>>,
+    protect_own_projects: Option<bool>,
     verbose: Option<bool>,
     clear_terminal: Option<bool>,
+    show_empty: Option<bool>,
+    compact: Option<bool>,
+    verify_sizes: Option<bool>,
+    exclude_from_time_machine: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RetentionSection {
+    history: Option<u64>,
+    trash: Option<u64>,
+    index: Option<u64>,
+    sizes: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WatchSection {
+    report_address: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct AccessSection {
     lastseen: Option<u64>,
+    metric: Option<StalenessMetric>,
 }
 
 impl Default for Config {
@@ -67,11 +500,51 @@ impl Default for Config {
                 ".rustup".to_string(),
             ],
             ignore_paths: Vec::new(),
+            skip_patterns: DEFAULT_SKIP_PATTERNS
+                .iter()
+                .map(|(pattern, _)| pattern.to_string())
+                .collect(),
             stale_threshold: Duration::from_secs(7 * 24 * 60 * 60), // 7 days
             last_access_days: 7, // Default to 7 days for last access check
+            staleness_metric: StalenessMetric::Mtime,
             dry_run: true,
+            trash: false,
+            confirm_typed_above: 20 * 1024 * 1024 * 1024, // 20 GB
+            rebuild_budget: Duration::from_secs(10 * 60),
+            countdown_secs: 5,
+            auto_clean_when_free_below: None,
+            report_address: None,
+            all_users: false,
+            elevate_command: Vec::new(),
+            webhook: None,
+            notify_email: None,
+            sendmail_command: vec!["sendmail".to_string(), "-t".to_string()],
+            policies: Vec::new(),
+            project_rules: Vec::new(),
+            protect_own_projects: true,
+            protected_projects: Vec::new(),
+            follow_links: false,
+            symlinked_targets: SymlinkPolicy::Skip,
+            size_cache: true,
+            exact_sizes: false,
+            network_fs: None,
+            jobs: std::thread::available_parallelism().map_or(4, |n| n.get()),
+            max_projects: None,
+            stop_after: None,
+            scan_orphans: false,
+            scan_wsl: false,
             verbose: false,
             clear_terminal: true, // Default to clearing terminal before UI
+            show_empty: false,
+            compact: false,
+            columns: vec![Column::Size, Column::Age],
+            quick_clean_key: 'S',
+            quick_clean_above: 1024 * 1024 * 1024,
+            retention: Retention::default(),
+            verify_sizes: false,
+            exclude_from_time_machine: false,
+            profile: None,
+            profiles: Vec::new(),
         }
     }
 }
@@ -116,6 +589,12 @@ impl Config {
         self.ignore_paths = paths;
         self
     }
+    /// Applies a `[profile.<name>]` of the configuration files over the rest of them
+    pub fn with_profile(mut self, profile: Option<String>) -> Self {
+        self.profile = profile;
+        self
+    }
+
     #[allow(dead_code)]
     /// Sets whether to clear the terminal before starting the UI
     pub fn with_clear(mut self, clear: bool) -> Self {
@@ -123,7 +602,121 @@ impl Config {
         self
     }
 
-    /// Load configuration from a Cleaner.toml file using proper TOML deserialization
+    /// Stale threshold for a project, taken from the first policy matching its tags or remote
+    pub fn stale_threshold_for(&self, project: &RustProject) -> Duration {
+        self.policies
+            .iter()
+            .find(|policy| policy.matches(project))
+            .map(|policy| Duration::from_secs(policy.lastseen.saturating_mul(24 * 60 * 60)))
+            .unwrap_or(self.stale_threshold)
+    }
+
+    /// Whether a local project is protected from being selected for cleaning
+    pub fn is_protected(&self, project: &RustProject) -> bool {
+        project.host.is_none()
+            && !self.protected_projects.is_empty()
+            && fs::canonicalize(&project.path)
+                .is_ok_and(|path| self.protected_projects.contains(&path))
+    }
+
+    /// Appends paths to `[ignore] paths` in the configuration file
+    ///
+    /// A Cleaner.toml keeps its comments and layout; YAML and JSON files are rewritten.
+    pub fn add_ignore_paths(
+        config_path: &Path,
+        paths: &[PathBuf],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let content = if config_path.exists() {
+            fs::read_to_string(config_path)?
+        } else {
+            String::new()
+        };
+        let format = ConfigFormat::from_path(config_path);
+        if format != ConfigFormat::Toml {
+            return Self::add_ignore_paths_to_value(config_path, format, &content, paths);
+        }
+        let mut document: toml_edit::DocumentMut = content.parse()?;
+
+        let ignore = document
+            .entry("ignore")
+            .or_insert_with(toml_edit::table)
+            .as_table_mut()
+            .ok_or("[ignore] in Cleaner.toml is not a table")?;
+        let existing = ignore
+            .entry("paths")
+            .or_insert_with(|| toml_edit::value(toml_edit::Array::new()))
+            .as_array_mut()
+            .ok_or("ignore.paths in Cleaner.toml is not an array")?;
+        for path in paths {
+            let path = path.to_string_lossy();
+            if !existing.iter().any(|p| p.as_str() == Some(path.as_ref())) {
+                existing.push(path.as_ref());
+            }
+        }
+
+        fs::write(config_path, document.to_string())?;
+        Ok(())
+    }
+
+    /// `add_ignore_paths` for the formats edited as a plain value tree
+    fn add_ignore_paths_to_value(
+        config_path: &Path,
+        format: ConfigFormat,
+        content: &str,
+        paths: &[PathBuf],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut document: serde_json::Value = match (format, content.trim().is_empty()) {
+            (_, true) => serde_json::json!({}),
+            (ConfigFormat::Yaml, false) => serde_yaml::from_str(content)?,
+            _ => serde_json::from_str(content)?,
+        };
+
+        let ignore = document
+            .as_object_mut()
+            .ok_or("The configuration file does not contain a mapping")?
+            .entry("ignore")
+            .or_insert_with(|| serde_json::json!({}))
+            .as_object_mut()
+            .ok_or("ignore in the configuration file is not a mapping")?;
+        let existing = ignore
+            .entry("paths")
+            .or_insert_with(|| serde_json::json!([]))
+            .as_array_mut()
+            .ok_or("ignore.paths in the configuration file is not a list")?;
+        for path in paths {
+            let path = path.to_string_lossy();
+            if !existing.iter().any(|p| p.as_str() == Some(path.as_ref())) {
+                existing.push(path.as_ref().into());
+            }
+        }
+
+        let content = match format {
+            ConfigFormat::Yaml => serde_yaml::to_string(&document)?,
+            _ => serde_json::to_string_pretty(&document)? + "\n",
+        };
+        fs::write(config_path, content)?;
+        Ok(())
+    }
+
+    /// Configuration file in a directory: Cleaner.toml, or else a YAML or JSON variant
+    ///
+    /// Returns the Cleaner.toml path when none exists, so it can be created.
+    pub fn find_config_file(dir: &Path) -> PathBuf {
+        CONFIG_FILE_NAMES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+            .unwrap_or_else(|| dir.join(CONFIG_FILE_NAMES[0]))
+    }
+
+    /// Load configuration from a Cleaner.toml, or a YAML or JSON file detected by its extension
+    ///
+    /// Files listed in `include` are loaded first, in order, and the including file is
+    /// applied on top of them: later files override single values and extend lists
+    /// (search and ignore paths, skip patterns, policies and project rules); commands
+    /// and column sets are single values. The selected profile, wherever it is defined,
+    /// is applied last, over the settings of every file, and its search paths replace
+    /// theirs. The home directory is searched only when no file names any.
     pub fn load_cleaner_config(
         &mut self,
         config_path: &Path,
@@ -131,43 +724,481 @@ impl Config {
         if !config_path.exists() {
             return Ok(()); // It's okay if the file doesn't exist
         }
+        let default_search_paths = std::mem::take(&mut self.search_paths);
+        let result = self.load_layers(config_path);
+        if self.search_paths.is_empty() {
+            self.search_paths = default_search_paths;
+        }
+        result
+    }
+
+    /// Loads a file with its includes, then the selected profile over all of them
+    fn load_layers(&mut self, config_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let mut profile_layers = Vec::new();
+        self.load_layer(config_path, &mut Vec::new(), &mut profile_layers)?;
+        if profile_layers
+            .iter()
+            .any(|layer| layer.scan.as_ref().is_some_and(|scan| scan.paths.is_some()))
+        {
+            self.search_paths.clear();
+        }
+        for layer in profile_layers {
+            self.apply_layer(layer)?;
+        }
+        Ok(())
+    }
+
+    /// Loads one configuration file after the files it includes
+    ///
+    /// The selected profile's settings are collected into `profile_layers`, in the order
+    /// their files are loaded, to be applied once every file has been.
+    fn load_layer(
+        &mut self,
+        config_path: &Path,
+        loading: &mut Vec<PathBuf>,
+        profile_layers: &mut Vec<ConfigLayer>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let canonical = fs::canonicalize(config_path)
+            .map_err(|e| format!("Cannot read {}: {}", config_path.display(), e))?;
+        if loading.contains(&canonical) {
+            return Err(format!("{} includes itself", config_path.display()).into());
+        }
 
         let content = fs::read_to_string(config_path)?;
-        let config: CleanerConfig = toml::from_str(&content)?;
-        println!("CleanerConfig {:?}", config);
+        let config: CleanerConfig = match ConfigFormat::from_path(config_path) {
+            ConfigFormat::Toml => toml::from_str(&content)?,
+            ConfigFormat::Yaml => serde_yaml::from_str(&content)?,
+            ConfigFormat::Json => serde_json::from_str(&content)?,
+        };
+
+        if let Some(includes) = &config.include {
+            let base = config_path.parent().unwrap_or(Path::new("."));
+            loading.push(canonical);
+            for include in includes {
+                self.load_layer(&base.join(expand_path(include)), loading, profile_layers)?;
+            }
+            loading.pop();
+        }
+
+        self.apply_layer(config.layer)?;
+        if let Some(mut profiles) = config.profile {
+            self.profiles.extend(profiles.keys().cloned());
+            if let Some(profile) = self.profile.as_ref().and_then(|name| profiles.remove(name)) {
+                profile_layers.push(profile);
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the settings of one file, or of one of its profiles, over the current ones
+    fn apply_layer(&mut self, layer: ConfigLayer) -> Result<(), Box<dyn std::error::Error>> {
         // Process ignore paths
-        if let Some(ignore) = config.ignore {
-            if let Some(paths) = ignore.paths {
-                for path_str in paths {
-                    let path = PathBuf::from(path_str);
-                    // Add path regardless of whether it exists now
-                    // The scanner will handle non-existent paths gracefully
-                    self.ignore_paths.push(path);
-                }
+        if let Some(ignore) = layer.ignore
+            && let Some(paths) = ignore.paths
+        {
+            for path_str in paths {
+                let path = expand_path(&path_str);
+                // Add path regardless of whether it exists now
+                // The scanner will handle non-existent paths gracefully
+                self.ignore_paths.push(path);
             }
         }
 
         // Process settings
-        if let Some(settings) = config.settings {
+        if let Some(settings) = layer.settings {
             if let Some(dry_run) = settings.dry_run {
                 self.dry_run = dry_run;
             }
+            if let Some(trash) = settings.trash {
+                self.trash = trash;
+            }
+            if let Some(threshold) = settings.confirm_typed_above {
+                self.confirm_typed_above = parse_size(&threshold)?;
+            }
+            if let Some(budget) = settings.rebuild_budget {
+                self.rebuild_budget = parse_duration(&budget)?;
+            }
+            if let Some(countdown) = settings.countdown {
+                self.countdown_secs = countdown;
+            }
+            if let Some(threshold) = settings.auto_clean_when_free_below {
+                self.auto_clean_when_free_below = Some(parse_size(&threshold)?);
+            }
+            if let Some(elevate_command) = settings.elevate_command {
+                self.elevate_command = elevate_command;
+            }
+            if let Some(protect) = settings.protect_own_projects {
+                self.protect_own_projects = protect;
+            }
             if let Some(verbose) = settings.verbose {
                 self.verbose = verbose;
             }
             if let Some(clear_terminal) = settings.clear_terminal {
                 self.clear_terminal = clear_terminal;
             }
+            if let Some(show_empty) = settings.show_empty {
+                self.show_empty = show_empty;
+            }
+            if let Some(compact) = settings.compact {
+                self.compact = compact;
+            }
+            if let Some(verify_sizes) = settings.verify_sizes {
+                self.verify_sizes = verify_sizes;
+            }
+            if let Some(exclude) = settings.exclude_from_time_machine {
+                self.exclude_from_time_machine = exclude;
+            }
+        }
+
+        if let Some(retention) = layer.retention {
+            if let Some(history) = retention.history {
+                self.retention.history = history;
+            }
+            if let Some(trash) = retention.trash {
+                self.retention.trash = trash;
+            }
+            if let Some(index) = retention.index {
+                self.retention.index = index;
+            }
+            if let Some(sizes) = retention.sizes {
+                self.retention.sizes = sizes;
+            }
         }
 
         // Process access settings
-        if let Some(access) = config.access {
+        if let Some(watch) = layer.watch
+            && let Some(address) = watch.report_address
+        {
+            self.report_address = Some(
+                address
+                    .parse()
+                    .map_err(|e| format!("Invalid watch.report_address {:?}: {}", address, e))?,
+            );
+        }
+
+        if let Some(access) = layer.access {
             if let Some(lastseen) = access.lastseen {
                 self.last_access_days = lastseen;
                 self.stale_threshold = Duration::from_secs(lastseen * 24 * 60 * 60);
             }
+            if let Some(metric) = access.metric {
+                self.staleness_metric = metric;
+            }
+        }
+
+        if let Some(scan) = layer.scan {
+            if let Some(paths) = scan.paths {
+                for path in paths.iter().map(|path| expand_path(path)) {
+                    if !self.search_paths.contains(&path) {
+                        self.search_paths.push(path);
+                    }
+                }
+            }
+            if let Some(follow_links) = scan.follow_links {
+                self.follow_links = follow_links;
+            }
+            if let Some(symlinked_targets) = scan.symlinked_targets {
+                self.symlinked_targets = symlinked_targets;
+            }
+            if let Some(size_cache) = scan.size_cache {
+                self.size_cache = size_cache;
+            }
+            if let Some(exact_sizes) = scan.exact_sizes {
+                self.exact_sizes = exact_sizes;
+            }
+            if let Some(network_fs) = scan.network_fs {
+                self.network_fs = Some(network_fs);
+            }
+            if let Some(jobs) = scan.jobs {
+                self.jobs = jobs.max(1);
+            }
+            if let Some(max_projects) = scan.max_projects {
+                self.max_projects = Some(max_projects);
+            }
+            if let Some(stop_after) = scan.stop_after {
+                self.stop_after = Some(parse_size(&stop_after)?);
+            }
+            if let Some(orphans) = scan.orphans {
+                self.scan_orphans = orphans;
+            }
+            if let Some(wsl) = scan.wsl {
+                self.scan_wsl = wsl;
+            }
+            if scan.skip_defaults == Some(false) {
+                self.skip_patterns
+                    .retain(|pattern| heuristics::reason(pattern).is_none());
+            }
+            for pattern in scan.skip.into_iter().flatten() {
+                if !self.skip_patterns.contains(&pattern) {
+                    self.skip_patterns.push(pattern);
+                }
+            }
+        }
+
+        if let Some(notify) = layer.notify {
+            if let Some(webhook) = notify.webhook {
+                self.webhook = Some(webhook);
+            }
+            if let Some(email) = notify.email {
+                self.notify_email = Some(email);
+            }
+            if let Some(sendmail) = notify.sendmail {
+                if sendmail.is_empty() {
+                    return Err("[notify] sendmail must name a command".into());
+                }
+                self.sendmail_command = sendmail;
+            }
+        }
+
+        if let Some(ui) = layer.ui {
+            if let Some(show) = ui.columns.and_then(|columns| columns.show) {
+                self.columns = show;
+            }
+            if let Some(key) = ui.quick_clean_key {
+                let mut chars = key.chars();
+                self.quick_clean_key = match (chars.next(), chars.next()) {
+                    (Some(c), None) if is_browse_key(c) => {
+                        return Err(format!(
+                            "ui.quick_clean_key {:?} is already bound to another action",
+                            key
+                        )
+                        .into());
+                    }
+                    (Some(c), None) => c,
+                    _ => {
+                        return Err(format!(
+                            "ui.quick_clean_key must be a single character, not {:?}",
+                            key
+                        )
+                        .into());
+                    }
+                };
+            }
+            if let Some(threshold) = ui.quick_clean_above {
+                self.quick_clean_above = parse_size(&threshold)?;
+            }
+        }
+
+        // The first matching policy applies, so a later file's policies go in front
+        if let Some(policies) = layer.policy {
+            if policies
+                .iter()
+                .any(|policy| policy.tag.is_none() && policy.remote.is_none())
+            {
+                return Err("Every [[policy]] needs a tag or a remote".into());
+            }
+            self.policies.splice(0..0, policies);
+        }
+
+        if let Some(mut project_rules) = layer.project {
+            for rule in &mut project_rules {
+                // Project paths are canonical, so `..` and symlinks in the rule must match them
+                if let Some(path) = &rule.path {
+                    let path = expand_path(&path.to_string_lossy());
+                    rule.path = Some(fs::canonicalize(&path).unwrap_or(path));
+                }
+            }
+            self.project_rules.extend(project_rules);
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(path: &str) -> PathBuf {
+        expand_path_with(path, Some(PathBuf::from("/home/user")), |name| match name {
+            "HOME" => Some("/home/user".to_string()),
+            "WORK" => Some("/srv/work".to_string()),
+            "USERPROFILE" => Some(r"C:\Users\user".to_string()),
+            "ProgramFiles(x86)" => Some(r"C:\Program Files (x86)".to_string()),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn expands_tilde() {
+        assert_eq!(expand("~/projects"), PathBuf::from("/home/user/projects"));
+        assert_eq!(expand("~"), PathBuf::from("/home/user"));
+    }
+
+    #[test]
+    fn expands_unix_variables() {
+        assert_eq!(expand("$HOME/work"), PathBuf::from("/home/user/work"));
+        assert_eq!(expand("${WORK}/repos"), PathBuf::from("/srv/work/repos"));
+    }
+
+    #[test]
+    fn expands_windows_variables() {
+        assert_eq!(
+            expand(r"%USERPROFILE%\projects"),
+            PathBuf::from(r"C:\Users\user\projects")
+        );
+        assert_eq!(
+            expand(r"%ProgramFiles(x86)%\Rust"),
+            PathBuf::from(r"C:\Program Files (x86)\Rust")
+        );
+    }
+
+    #[test]
+    fn leaves_unknown_variables_and_lone_percent_signs() {
+        assert_eq!(expand("$NOPE/x"), PathBuf::from("$NOPE/x"));
+        assert_eq!(expand("%NOPE%/x"), PathBuf::from("%NOPE%/x"));
+        assert_eq!(expand("/data/100%/x"), PathBuf::from("/data/100%/x"));
+    }
+
+    #[test]
+    fn does_not_expand_variable_values_twice() {
+        let expanded = expand_path_with("%A%", None, |name| match name {
+            "A" => Some("$B".to_string()),
+            "B" => Some("b".to_string()),
+            _ => None,
+        });
+        assert_eq!(expanded, PathBuf::from("$B"));
+    }
+
+    /// Writes configuration files into a fresh directory under the system temp dir
+    fn write_files(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("clear-target-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (name, content) in files {
+            fs::write(dir.join(name), content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn applies_profile_from_included_file_last() {
+        let dir = write_files(
+            "profile",
+            &[
+                (
+                    "shared.toml",
+                    "[settings]\ncountdown = 1\n\n[profile.work.settings]\ndry_run = false\ncountdown = 9\n",
+                ),
+                (
+                    "Cleaner.toml",
+                    "include = [\"shared.toml\"]\n\n[settings]\ndry_run = true\ncountdown = 3\n",
+                ),
+            ],
+        );
+        let mut config = Config::default().with_profile(Some("work".to_string()));
+        config
+            .load_cleaner_config(&dir.join("Cleaner.toml"))
+            .unwrap();
+        assert!(!config.dry_run);
+        assert_eq!(config.countdown_secs, 9);
+        assert_eq!(config.profiles, vec!["work".to_string()]);
+
+        let mut config = Config::default();
+        config
+            .load_cleaner_config(&dir.join("Cleaner.toml"))
+            .unwrap();
+        assert!(config.dry_run);
+        assert_eq!(config.countdown_secs, 3);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn extends_search_paths_across_included_files() {
+        let dir = write_files(
+            "search-paths",
+            &[
+                (
+                    "shared.toml",
+                    "[scan]\npaths = [\"/srv/a\"]\n\n[profile.work.scan]\npaths = [\"/srv/work\"]\n",
+                ),
+                (
+                    "Cleaner.toml",
+                    "include = [\"shared.toml\"]\n\n[scan]\npaths = [\"/srv/b\", \"/srv/a\"]\n",
+                ),
+                ("empty.toml", "[settings]\ncountdown = 2\n"),
+            ],
+        );
+        let mut config = Config::default();
+        config
+            .load_cleaner_config(&dir.join("Cleaner.toml"))
+            .unwrap();
+        assert_eq!(
+            config.search_paths,
+            vec![PathBuf::from("/srv/a"), PathBuf::from("/srv/b")]
+        );
+
+        let mut config = Config::default().with_profile(Some("work".to_string()));
+        config
+            .load_cleaner_config(&dir.join("Cleaner.toml"))
+            .unwrap();
+        assert_eq!(config.search_paths, vec![PathBuf::from("/srv/work")]);
+
+        let mut config = Config::default();
+        config.load_cleaner_config(&dir.join("empty.toml")).unwrap();
+        assert_eq!(config.search_paths, Config::default().search_paths);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn refuses_quick_clean_key_bound_elsewhere() {
+        let dir = write_files(
+            "quick-clean-key",
+            &[
+                ("taken.toml", "[ui]\nquick_clean_key = \"d\"\n"),
+                ("free.toml", "[ui]\nquick_clean_key = \"X\"\n"),
+            ],
+        );
+        let error = Config::default()
+            .load_cleaner_config(&dir.join("taken.toml"))
+            .unwrap_err();
+        assert!(error.to_string().contains("already bound"));
+
+        let mut config = Config::default();
+        config.load_cleaner_config(&dir.join("free.toml")).unwrap();
+        assert_eq!(config.quick_clean_key, 'X');
+        assert!(!is_browse_key(Config::default().quick_clean_key));
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn canonicalizes_project_rule_paths() {
+        let dir = write_files("project-rules", &[]);
+        fs::create_dir_all(dir.join("proj")).unwrap();
+        let config_file = format!(
+            "[[project]]\npath = {:?}\nkeep = [\"release/app\"]\n\n[[project]]\npath = {:?}\n",
+            dir.join("proj/../proj"),
+            dir.join("gone"),
+        );
+        fs::write(dir.join("Cleaner.toml"), config_file).unwrap();
+
+        let mut config = Config::default();
+        config
+            .load_cleaner_config(&dir.join("Cleaner.toml"))
+            .unwrap();
+        let paths: Vec<_> = config
+            .project_rules
+            .iter()
+            .map(|rule| rule.path.clone().unwrap())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                fs::canonicalize(dir.join("proj")).unwrap(),
+                dir.join("gone")
+            ]
+        );
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("10m"), Ok(Duration::from_secs(600)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("15"), Ok(Duration::from_secs(900)));
+        assert!(parse_duration("2d").is_err());
+        assert!(parse_duration("m").is_err());
+    }
+}
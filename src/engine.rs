@@ -0,0 +1,430 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::cache::index::ProjectIndex;
+use crate::cache::projects::ProjectCache;
+use crate::cache::sizes;
+use crate::cache::tags::TagStore;
+use crate::cleaner::targer_cleaner::{CleanEvent, CleanOptions, CleanupResult, TargetCleaner};
+use crate::config::Config;
+use crate::progress::{ProgressSink, Stage};
+use crate::remote::RemoteHost;
+use crate::scanner::rust_project::RustProject;
+use crate::scanner::rust_project_scaner::{RustProjectScanner, ScanDiagnostic};
+use crate::scanner::target_finder;
+use crate::size::format_bytes;
+use crate::tasks::Tasks;
+use crate::timing::{self, Phase};
+use crate::vfs;
+
+/// Projects sized per batch for each concurrent job during a local scan
+const BATCH_PER_JOB: usize = 8;
+/// How many more targets are sized at once on a network filesystem than `scan.jobs`
+const NETWORK_JOBS_FACTOR: usize = 4;
+
+/// Requests a frontend sends to the engine
+#[derive(Debug)]
+pub enum EngineCommand {
+    /// Find projects in the search paths, on the remote host or in the watch cache
+    ScanRequested,
+    /// Clean the target directories of the selected projects
+    CleanRequested {
+        projects: Vec<RustProject>,
+        selected: Vec<bool>,
+        options: CleanOptions,
+    },
+}
+
+/// What the engine reports back while handling a command
+#[derive(Debug)]
+pub enum EngineEvent {
+    /// Items of a scan or cleanup finished so far, the last of them being `item`
+    Progress {
+        stage: Stage,
+        done: usize,
+        total: usize,
+        item: Option<PathBuf>,
+    },
+    /// A project was found, with its target directory if it has one
+    ProjectFound(Box<RustProject>),
+    /// A directory holding a Cargo.toml could not be read and was left out
+    ProjectSkipped(ScanDiagnostic),
+    /// Scanning finished; every project has been reported
+    ///
+    /// `limited` explains why some projects were left out when a scan limit was hit.
+    ScanFinished {
+        projects: usize,
+        limited: Option<String>,
+    },
+    /// A selected target directory was cleaned, or failed to be
+    TargetCleaned(CleanEvent),
+    /// Something the user should know that is neither progress nor a result
+    Notice(String),
+    /// Something went wrong without stopping the scan or cleanup
+    Warning(String),
+    /// Cleaning finished
+    CleanFinished(CleanupResult),
+}
+
+/// Scanning and cleaning, independent of the frontend driving them
+///
+/// Frontends (the CLI commands, the TUI) send [`EngineCommand`]s and react to the
+/// [`EngineEvent`]s reported while each one is handled.
+#[derive(Clone)]
+pub struct Engine {
+    config: Config,
+    scanner: RustProjectScanner,
+    /// Remote host to scan and clean instead of the local machine
+    remote: Option<RemoteHost>,
+    /// Whether to use the project list maintained by `watch` instead of scanning
+    cached_projects: bool,
+    /// Runtime that scans and cleanups run on
+    tasks: Arc<Tasks>,
+}
+
+impl Engine {
+    /// Creates an engine for the given configuration
+    pub fn new(config: Config) -> Result<Self, Box<dyn Error>> {
+        let scanner = RustProjectScanner::new_with_ignores(
+            &config.search_paths,
+            &config.exclude_patterns,
+            &config.ignore_paths,
+        )?
+        .with_follow_links(config.follow_links)
+        .with_skip_patterns(&config.skip_patterns)
+        .with_orphans(config.scan_orphans);
+        // On a network filesystem the jobs mostly wait on the server
+        let jobs = if target_finder::network_fs() {
+            config.jobs * NETWORK_JOBS_FACTOR
+        } else {
+            config.jobs
+        };
+        let tasks = Arc::new(Tasks::new(jobs)?);
+
+        Ok(Self {
+            config,
+            scanner,
+            remote: None,
+            cached_projects: false,
+            tasks,
+        })
+    }
+
+    /// Scans and cleans the given remote host over SSH instead of the local machine
+    pub fn with_remote(mut self, remote: RemoteHost) -> Self {
+        self.remote = Some(remote);
+        self
+    }
+
+    /// Uses the project list maintained by `clear-target watch` instead of scanning
+    pub fn with_cached_projects(mut self) -> Self {
+        self.cached_projects = true;
+        self
+    }
+
+    /// Configuration the engine was created with
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Runtime that scans and cleanups run on
+    pub fn tasks(&self) -> &Tasks {
+        &self.tasks
+    }
+
+    /// Scanner for the configured search paths
+    pub fn scanner(&self) -> &RustProjectScanner {
+        &self.scanner
+    }
+
+    /// Handles one command, reporting progress to `events` until it is done
+    pub fn handle(
+        &self,
+        command: EngineCommand,
+        events: &mut dyn FnMut(EngineEvent),
+    ) -> Result<(), Box<dyn Error>> {
+        match command {
+            EngineCommand::ScanRequested => self.scan(events),
+            EngineCommand::CleanRequested {
+                projects,
+                selected,
+                options,
+            } => self.clean(&projects, &selected, options, events),
+        }
+    }
+
+    fn scan(&self, events: &mut dyn FnMut(EngineEvent)) -> Result<(), Box<dyn Error>> {
+        let listed = match &self.remote {
+            Some(remote) => Some(timing::measure(Phase::Discovery, || {
+                events(EngineEvent::Notice(format!(
+                    "Scanning {} over SSH...",
+                    remote.destination()
+                )));
+                remote.list_projects()
+            })?),
+            None => self.cached(events)?,
+        };
+
+        let (count, limited) = match listed {
+            Some(mut projects) => {
+                let count = projects.iter().filter(|p| p.target_info.is_some()).count();
+                let tags = Self::load_tags(events);
+                tags.apply(&mut projects);
+                for mut project in projects {
+                    if let Err(e) = project.update_status(&self.config) {
+                        events(EngineEvent::Warning(format!(
+                            "{}: {}",
+                            project.display_path(),
+                            e
+                        )));
+                    }
+                    events(EngineEvent::ProjectFound(Box::new(project)));
+                }
+                (count, None)
+            }
+            None => self.scan_local(events)?,
+        };
+
+        if let Err(e) = sizes::save() {
+            events(EngineEvent::Warning(format!(
+                "Failed to save the size cache: {}",
+                e
+            )));
+        }
+
+        events(EngineEvent::ScanFinished {
+            projects: count,
+            limited,
+        });
+        Ok(())
+    }
+
+    /// Sizes one project and works out whether its target is stale and can be cleaned
+    ///
+    /// Runs on the sizing workers, so large scans check staleness concurrently too.
+    fn analyze(
+        dir: PathBuf,
+        tags: &TagStore,
+        config: &Config,
+    ) -> Result<RustProject, ScanDiagnostic> {
+        let mut project = RustProjectScanner::analyze(dir)?;
+        // Tags decide which staleness policy applies
+        tags.apply(std::slice::from_mut(&mut project));
+        project.update_status(config).map_err(|e| ScanDiagnostic {
+            path: project.path.clone(),
+            reason: format!("cannot check staleness: {}", e),
+        })?;
+        Ok(project)
+    }
+
+    /// Project tags, or none when they cannot be loaded
+    fn load_tags(events: &mut dyn FnMut(EngineEvent)) -> TagStore {
+        TagStore::load().unwrap_or_else(|e| {
+            events(EngineEvent::Warning(format!(
+                "Failed to load project tags: {}",
+                e
+            )));
+            TagStore::default()
+        })
+    }
+
+    /// Project list from the watch cache, when requested and available
+    fn cached(
+        &self,
+        events: &mut dyn FnMut(EngineEvent),
+    ) -> Result<Option<Vec<RustProject>>, Box<dyn Error>> {
+        if !self.cached_projects {
+            return Ok(None);
+        }
+        let projects = ProjectCache::load()?;
+        events(EngineEvent::Notice(match &projects {
+            Some(projects) => format!("Using {} cached projects", projects.len()),
+            None => "No cached project list (run `clear-target watch`); scanning".to_string(),
+        }));
+        Ok(projects)
+    }
+
+    /// Walks the search paths, then sizes the projects found concurrently
+    ///
+    /// Projects are sized in batches and reported (and written to the project cache)
+    /// as each batch completes, so the engine never holds the whole list. Returns the
+    /// number of projects reported with a target directory and, when a scan limit was
+    /// hit, why it stopped.
+    fn scan_local(
+        &self,
+        events: &mut dyn FnMut(EngineEvent),
+    ) -> Result<(usize, Option<String>), Box<dyn Error>> {
+        let scanner = self.scanner.clone();
+        let project_dirs = self.tasks.run_blocking(move |cancel| {
+            timing::measure(Phase::Discovery, || scanner.find_project_dirs(&cancel))
+        })?;
+        let mut project_dirs = project_dirs.map_err(|e| e as Box<dyn Error>)?;
+        for note in self.scanner.take_notes() {
+            events(EngineEvent::Notice(note));
+        }
+
+        let mut limited = None;
+        if let Some(max) = self.config.max_projects
+            && project_dirs.len() > max
+        {
+            limited = Some(format!(
+                "only {} of {} projects were sized (scan.max_projects)",
+                max,
+                project_dirs.len()
+            ));
+            project_dirs.truncate(max);
+        }
+
+        // What a simulation sees is not on disk, so it must not end up in the caches
+        let persist = !vfs::current().is_virtual();
+        let mut cache = persist.then(ProjectCache::writer).and_then(|writer| {
+            writer
+                .map_err(|e| {
+                    events(EngineEvent::Warning(format!(
+                        "Failed to write the project cache: {}",
+                        e
+                    )))
+                })
+                .ok()
+        });
+        let mut index = persist.then(ProjectIndex::load).and_then(|index| {
+            index
+                .map_err(|e| {
+                    events(EngineEvent::Warning(format!(
+                        "Failed to load the project index: {}",
+                        e
+                    )))
+                })
+                .ok()
+        });
+        let total = project_dirs.len();
+        let (mut done, mut found, mut size) = (0, 0, 0);
+        let tags = Arc::new(Self::load_tags(events));
+        let config = Arc::new(self.config.clone());
+        let mut remaining = project_dirs.into_iter();
+        loop {
+            let batch: Vec<PathBuf> = remaining
+                .by_ref()
+                .take(self.tasks.jobs() * BATCH_PER_JOB)
+                .collect();
+            if batch.is_empty() {
+                break;
+            }
+            let batch_len = batch.len();
+            let (tags, config) = (Arc::clone(&tags), Arc::clone(&config));
+            let analyze = move |dir| Self::analyze(dir, &tags, &config);
+            let mut progress = EventProgress(&mut *events);
+            let projects = self.tasks.map_blocking(batch, analyze, |sized, _| {
+                progress.advance(Stage::Sizing, done + sized, total, None)
+            })?;
+            done += batch_len;
+
+            for outcome in projects {
+                let project = match outcome {
+                    Ok(project) => project,
+                    Err(diagnostic) => {
+                        if let Some(index) = index.as_mut() {
+                            index.keep(&diagnostic.path);
+                        }
+                        events(EngineEvent::ProjectSkipped(diagnostic));
+                        continue;
+                    }
+                };
+                size += project.target_info.as_ref().map_or(0, |t| t.size_bytes);
+                if let Some(writer) = cache.as_mut()
+                    && let Err(e) = writer.append(&project)
+                {
+                    events(EngineEvent::Warning(format!(
+                        "Failed to write the project cache: {}",
+                        e
+                    )));
+                    cache = None;
+                }
+                if let Some(index) = index.as_mut() {
+                    index.record(&project);
+                }
+                found += usize::from(project.target_info.is_some());
+                events(EngineEvent::ProjectFound(Box::new(project)));
+            }
+
+            if let Some(stop_after) = self.config.stop_after
+                && size >= stop_after
+                && done < total
+            {
+                limited = Some(format!(
+                    "stopped after {} of targets; {} of {} projects were not sized (scan.stop_after)",
+                    format_bytes(size),
+                    total - done,
+                    total
+                ));
+                break;
+            }
+        }
+
+        if let Some(writer) = cache
+            && let Err(e) = writer.finish()
+        {
+            events(EngineEvent::Warning(format!(
+                "Failed to write the project cache: {}",
+                e
+            )));
+        }
+        if let Some(mut index) = index {
+            // A limited scan did not look everywhere, so it cannot tell what is gone
+            if limited.is_none() {
+                index.finish_scan(|path| self.scanner.covers(path));
+            }
+            if let Err(e) = index.save() {
+                events(EngineEvent::Warning(format!(
+                    "Failed to save the project index: {}",
+                    e
+                )));
+            }
+        }
+        Ok((found, limited))
+    }
+
+    fn clean(
+        &self,
+        projects: &[RustProject],
+        selected: &[bool],
+        options: CleanOptions,
+        events: &mut dyn FnMut(EngineEvent),
+    ) -> Result<(), Box<dyn Error>> {
+        let result = self.tasks.run_local(|cancel| {
+            let options = options.with_cancel(cancel);
+            let mut cleanup = TargetCleaner::clean(projects, selected, &options)?;
+            for event in cleanup.by_ref() {
+                EventProgress(&mut *events).advance(
+                    Stage::Cleaning,
+                    event.done,
+                    event.total,
+                    Some(&event.target),
+                );
+                for warning in &event.warnings {
+                    events(EngineEvent::Warning(warning.clone()));
+                }
+                events(EngineEvent::TargetCleaned(event));
+            }
+            Ok::<_, Box<dyn Error>>(cleanup.finish())
+        })?;
+
+        events(EngineEvent::CleanFinished(result));
+        Ok(())
+    }
+}
+
+/// Reports progress to a frontend as engine events
+struct EventProgress<'a>(&'a mut dyn FnMut(EngineEvent));
+
+impl ProgressSink for EventProgress<'_> {
+    fn advance(&mut self, stage: Stage, done: usize, total: usize, item: Option<&Path>) {
+        (self.0)(EngineEvent::Progress {
+            stage,
+            done,
+            total,
+            item: item.map(Path::to_path_buf),
+        });
+    }
+}
@@ -1,20 +1,216 @@
 use std::error::Error;
+use std::process::ExitCode;
+
+use clap::{CommandFactory, Parser};
 
 mod app;
+mod cache;
 mod cleaner;
+mod cli;
 mod config;
+mod engine;
+mod notify;
+mod progress;
+mod query;
+mod remote;
+mod report;
 mod scanner;
+mod size;
+mod summary;
+mod tasks;
+mod timing;
 mod ui;
+mod vfs;
 use app::App;
+use cache::lock::InstanceLock;
+use cli::{Cli, Command};
 use config::Config;
+use query::Query;
+use remote::RemoteHost;
+use scanner::rust_project::RustProject;
+use scanner::users;
+use summary::{ExitStatus, RunSummary};
+use tasks::Cancelled;
+
+/// Set by the `cargo-clear-target` binary when cargo runs this as `cargo clear-target`
+const VIA_CARGO_VAR: &str = "CLEAR_TARGET_VIA_CARGO";
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    if cli.timing {
+        timing::enable();
+    }
+    ui::cli::init_color(cli.no_color);
+
+    let result = run(&cli);
+    if cli.simulate && result.is_ok() {
+        eprintln!("Simulated: nothing on disk was changed");
+    }
+    if let Some(report) = timing::report() {
+        eprint!("{}", report);
+    }
+
+    match result {
+        Ok(None) => ExitStatus::Success.into(),
+        Ok(Some(summary)) => {
+            if cli.summary_json {
+                match summary.to_json_line() {
+                    Ok(line) => println!("{}", line),
+                    Err(e) => eprintln!("Error: failed to serialize summary: {}", e),
+                }
+            }
+            summary.exit_status().into()
+        }
+        Err(e) if e.is::<Cancelled>() => {
+            eprintln!("Cancelled");
+            ExitStatus::Cancelled.into()
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitStatus::Error.into()
+        }
+    }
+}
+
+/// Runs the selected command; returns `None` for commands that do not clean anything
+fn run(cli: &Cli) -> Result<Option<RunSummary>, Box<dyn Error>> {
+    // Needs neither the configuration nor the lock, and must print nothing else
+    if let Some(Command::Completions { shell }) = cli.command {
+        clap_complete::generate(
+            shell,
+            &mut Cli::command(),
+            "clear-target",
+            &mut std::io::stdout(),
+        );
+        return Ok(None);
+    }
+
+    // Called from build wrappers, possibly while the UI is open, so it skips the lock
+    if let Some(Command::Mark { build, path }) = &cli.command {
+        App::run_mark(*build, path.as_deref())?;
+        return Ok(None);
+    }
+
+    // Held for the whole run of a command that deletes, so concurrent instances never
+    // delete the same targets. Commands that only read, and simulations, which delete
+    // nothing on disk, run alongside an open UI; the deleting housekeeping below is
+    // skipped for them
+    let deletes = !cli.simulate
+        && match &cli.command {
+            None
+            | Some(
+                Command::Clean { .. }
+                | Command::Apply { .. }
+                | Command::Here
+                | Command::Pick
+                | Command::Restore,
+            ) => true,
+            Some(Command::Index { purge, .. }) => *purge,
+            Some(_) => false,
+        };
+    let lock = if deletes {
+        Some(InstanceLock::acquire()?)
+    } else {
+        None
+    };
+
+    if cli.simulate {
+        if cli.host.is_some()
+            || matches!(
+                cli.command,
+                Some(Command::Restore | Command::Watch | Command::Index { .. })
+            )
+        {
+            return Err(
+                "--simulate cannot be combined with --host or the restore, watch or index commands"
+                    .into(),
+            );
+        }
+        vfs::install(Box::leak(Box::new(vfs::MemoryFs::overlay())))?;
+    }
 
-fn main() -> Result<(), Box<dyn Error>> {
     // toml config not working
-    let config = Config::new();
-    println!("{:?}", config);
+    let profile = cli
+        .profile
+        .clone()
+        .or_else(|| std::env::var("CLEAR_TARGET_PROFILE").ok())
+        .filter(|profile| !profile.is_empty());
+    let mut config = Config::new().with_profile(profile);
+    if cli.orphans {
+        config.scan_orphans = true;
+    }
+    if cli.all_users {
+        if cli.host.is_some() {
+            return Err("--all-users cannot be combined with --host".into());
+        }
+        config.search_paths = users::home_directories()?;
+        config.all_users = true;
+    }
+    if cli.simulate {
+        // Removals only happen in memory, so they cannot be undone from the trash
+        config.dry_run = false;
+        config.trash = false;
+    }
     let mut app = App::new(config)?;
+    if lock.is_some() {
+        app.prune_own_records();
+    }
 
-    app.run()?;
+    if let Some(host) = &cli.host {
+        if matches!(
+            cli.command,
+            Some(Command::Here | Command::Restore | Command::Watch)
+        ) {
+            return Err(
+                "--host cannot be combined with the here, restore or watch commands".into(),
+            );
+        }
+        app = app.with_remote(RemoteHost::new(host));
+    }
+    if cli.cached {
+        app = app.with_cached_projects();
+    }
+    if let Some(selection) = &cli.select {
+        app = app.with_selection(Query::parse(selection)?);
+    }
 
-    Ok(())
+    match &cli.command {
+        Some(Command::Clean {
+            paths_from,
+            only,
+            stream,
+        }) => app
+            .run_clean(paths_from.as_deref(), *only, *stream)
+            .map(Some),
+        Some(Command::Apply { plan }) => app.run_apply(plan).map(Some),
+        Some(Command::Here) => app.run_here().map(Some),
+        Some(Command::Pick) => app.run_pick().map(Some),
+        Some(Command::Restore) => app.run_restore().map(Some),
+        Some(Command::List { json, stream }) => app.run_list(*json, *stream).map(|_| None),
+        Some(Command::Watch) => app.run_watch().map(|_| None),
+        Some(Command::Stats) => app.run_stats().map(|_| None),
+        Some(Command::Index { purge, all }) => App::run_index(*purge, *all).map(|_| None),
+        Some(Command::SkipPatterns) => {
+            app.print_skip_patterns();
+            Ok(None)
+        }
+        Some(Command::Completions { .. } | Command::Mark { .. }) => Ok(None),
+        Some(Command::Report {
+            report_format,
+            output,
+        }) => app
+            .run_report(*report_format, output.as_deref())
+            .map(|_| None),
+        #[cfg(feature = "gui")]
+        None if cli.gui => app.run_gui().map(Some),
+        // `cargo clear-target` inside a crate is about that crate, like other cargo commands
+        None if std::env::var_os(VIA_CARGO_VAR).is_some()
+            && cli.host.is_none()
+            && !cli.all_users
+            && RustProject::find_root(&std::env::current_dir()?).is_some() =>
+        {
+            app.run_here().map(Some)
+        }
+        None => app.run().map(Some),
+    }
 }
@@ -0,0 +1,115 @@
+use std::error::Error;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::config::Config;
+use crate::size::format_bytes;
+use crate::summary::RunSummary;
+
+/// Sends the summary of a headless cleanup to the configured webhook and mail address
+///
+/// Failures are reported as warnings only; the cleanup itself already happened.
+pub fn send(config: &Config, summary: &RunSummary) {
+    if config.webhook.is_none() && config.notify_email.is_none() {
+        return;
+    }
+    let host = hostname();
+
+    if let Some(url) = &config.webhook
+        && let Err(e) = post(url, &payload(&host, summary))
+    {
+        eprintln!("Warning: Failed to post the summary to {}: {}", url, e);
+    }
+    if let Some(address) = &config.notify_email
+        && let Err(e) = mail(&config.sendmail_command, address, &host, summary)
+    {
+        eprintln!("Warning: Failed to mail the summary to {}: {}", address, e);
+    }
+}
+
+/// The JSON summary, with the machine and time it ran at
+fn payload(host: &str, summary: &RunSummary) -> String {
+    let mut value = serde_json::to_value(summary).unwrap_or_default();
+    value["status"] = summary.exit_status().as_str().into();
+    value["host"] = host.into();
+    value["finished"] = chrono::Local::now().to_rfc3339().into();
+    value.to_string()
+}
+
+/// POSTs the payload with curl, which also handles TLS and proxies
+fn post(url: &str, payload: &str) -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--max-time", "30"])
+        .args(["--header", "Content-Type: application/json"])
+        .args(["--data-binary", "@-", "--", url])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()?;
+    if let Some(stdin) = child.stdin.as_mut() {
+        stdin.write_all(payload.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(format!("curl exited with {}", status).into());
+    }
+    Ok(())
+}
+
+/// Pipes a plain-text mail to the sendmail command
+fn mail(
+    command: &[String],
+    address: &str,
+    host: &str,
+    summary: &RunSummary,
+) -> Result<(), Box<dyn Error>> {
+    let (program, args) = command.split_first().ok_or("no sendmail command")?;
+    let verb = if summary.dry_run {
+        "would free"
+    } else {
+        "freed"
+    };
+    let mut body = format!(
+        "To: {}\nSubject: clear-target on {}: {} {}\n\n{} {} {} in {} target directories ({}).\n",
+        address,
+        host,
+        verb,
+        format_bytes(summary.bytes_freed),
+        host,
+        verb,
+        format_bytes(summary.bytes_freed),
+        summary.projects_cleaned,
+        summary.exit_status().as_str()
+    );
+    if !summary.errors.is_empty() {
+        body.push_str("\nErrors:\n");
+        for error in &summary.errors {
+            body.push_str(&format!("  {}\n", error));
+        }
+    }
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .spawn()?;
+    if let Some(stdin) = child.stdin.as_mut() {
+        stdin.write_all(body.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(format!("{} exited with {}", program, status).into());
+    }
+    Ok(())
+}
+
+/// Name of this machine, as reported by the environment or the `hostname` command
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .filter(|name| !name.is_empty())
+        .or_else(|| {
+            let output = Command::new("hostname").output().ok()?;
+            Some(String::from_utf8(output.stdout).ok()?.trim().to_string())
+        })
+        .unwrap_or_else(|| "unknown host".to_string())
+}
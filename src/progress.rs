@@ -0,0 +1,36 @@
+use std::path::Path;
+
+/// Long-running operation whose progress is reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Measuring the target directories of the projects found
+    Sizing,
+    /// Removing the selected target directories
+    Cleaning,
+}
+
+impl Stage {
+    /// Label shown next to a progress bar, e.g. "Sizing targets"
+    pub fn label(self) -> &'static str {
+        match self {
+            Stage::Sizing => "Sizing targets",
+            Stage::Cleaning => "Cleaning",
+        }
+    }
+}
+
+/// Receives the progress of sizing and cleaning, whoever runs them
+///
+/// Each frontend renders it its own way (the TUI status line, the CLI progress bar);
+/// code with nobody to show progress to passes [`NoProgress`].
+pub trait ProgressSink {
+    /// `done` of `total` items of `stage` are finished, the last of them being `item`
+    fn advance(&mut self, stage: Stage, done: usize, total: usize, item: Option<&Path>);
+}
+
+/// Progress sink that discards everything, for callers showing no progress
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn advance(&mut self, _stage: Stage, _done: usize, _total: usize, _item: Option<&Path>) {}
+}
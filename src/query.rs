@@ -0,0 +1,462 @@
+use std::time::SystemTime;
+
+use crate::scanner::rust_project::{BuildHealth, RustProject};
+use crate::size::parse_size;
+
+/// A selection expression such as `size > 1GB && age > 30d && path ~ "~/oss"`
+///
+/// Comparisons on `size`, `age` (days since last use), `path`, `name`, `tag`, `remote`
+/// (git host and owner), `build` (`passing`, `broken` or `unknown`) and `stale` combine
+/// with `&&`, `||`, `!` and parentheses. `~` tests whether a text field contains a value;
+/// a leading `~/` in paths stands for the home directory.
+#[derive(Debug, Clone)]
+pub enum Query {
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    Compare(Field, Op, Value),
+}
+
+/// Project properties a query can test
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Size,
+    Age,
+    Path,
+    Name,
+    Tag,
+    Remote,
+    Build,
+    Stale,
+}
+
+/// Comparison operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+    Contains,
+}
+
+/// Right-hand side of a comparison, already converted for its field
+#[derive(Debug, Clone)]
+pub enum Value {
+    Number(u64),
+    Text(String),
+    Bool(bool),
+}
+
+impl Query {
+    /// Parses a selection expression
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let query = parser.or()?;
+        match parser.peek() {
+            None => Ok(query),
+            Some(token) => Err(format!("Unexpected {:?} in selection", token)),
+        }
+    }
+
+    /// Whether a project matches; projects without a target only match on name, path and tags
+    pub fn matches(&self, project: &RustProject) -> bool {
+        match self {
+            Query::And(a, b) => a.matches(project) && b.matches(project),
+            Query::Or(a, b) => a.matches(project) || b.matches(project),
+            Query::Not(query) => !query.matches(project),
+            Query::Compare(field, op, value) => compare(project, *field, *op, value),
+        }
+    }
+}
+
+fn compare(project: &RustProject, field: Field, op: Op, value: &Value) -> bool {
+    let target_info = project.target_info.as_ref();
+    match (field, value) {
+        (Field::Size, Value::Number(n)) => {
+            target_info.is_some_and(|t| compare_numbers(t.size_bytes, op, *n))
+        }
+        (Field::Age, Value::Number(n)) => target_info.is_some_and(|t| {
+            let days = SystemTime::now()
+                .duration_since(t.last_accessed)
+                .map_or(0, |age| age.as_secs() / (24 * 60 * 60));
+            compare_numbers(days, op, *n)
+        }),
+        (Field::Stale, Value::Bool(b)) => {
+            let stale = target_info.is_some_and(|t| t.is_stale);
+            if op == Op::Ne {
+                stale != *b
+            } else {
+                stale == *b
+            }
+        }
+        (Field::Path, Value::Text(text)) => compare_text(&project.display_path(), op, text),
+        (Field::Name, Value::Text(text)) => compare_text(&project.name, op, text),
+        (Field::Remote, Value::Text(text)) => {
+            compare_text(project.remote.as_deref().unwrap_or(""), op, text)
+        }
+        (Field::Build, Value::Text(text)) => {
+            let health = project.build_health.map_or("unknown", BuildHealth::label);
+            compare_text(health, op, text)
+        }
+        (Field::Tag, Value::Text(text)) => {
+            let any = project
+                .tags
+                .iter()
+                .any(|tag| compare_text(tag, Op::Eq, text));
+            if op == Op::Ne { !any } else { any }
+        }
+        _ => false,
+    }
+}
+
+fn compare_numbers(left: u64, op: Op, right: u64) -> bool {
+    match op {
+        Op::Gt => left > right,
+        Op::Ge => left >= right,
+        Op::Lt => left < right,
+        Op::Le => left <= right,
+        Op::Eq => left == right,
+        Op::Ne => left != right,
+        Op::Contains => false,
+    }
+}
+
+fn compare_text(left: &str, op: Op, right: &str) -> bool {
+    match op {
+        Op::Eq => left == right,
+        Op::Ne => left != right,
+        Op::Contains => left.contains(right),
+        _ => false,
+    }
+}
+
+/// Lexical tokens of a selection expression
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Op(Op),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        chars.next();
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '~' => Token::Op(Op::Contains),
+            '&' | '|' => {
+                if chars.next_if_eq(&c).is_none() {
+                    return Err(format!("Expected '{}{}' in selection", c, c));
+                }
+                if c == '&' { Token::And } else { Token::Or }
+            }
+            '!' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Ne),
+            '!' => Token::Not,
+            '=' => {
+                chars.next_if_eq(&'=');
+                Token::Op(Op::Eq)
+            }
+            '>' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Ge),
+            '>' => Token::Op(Op::Gt),
+            '<' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Le),
+            '<' => Token::Op(Op::Lt),
+            '"' | '\'' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some(end) if end == c => break,
+                        Some(ch) => text.push(ch),
+                        None => return Err("Unterminated string in selection".to_string()),
+                    }
+                }
+                Token::Text(text)
+            }
+            _ => {
+                let mut word = c.to_string();
+                while let Some(ch) =
+                    chars.next_if(|ch| ch.is_alphanumeric() || matches!(ch, '.' | '_' | '-' | '/'))
+                {
+                    word.push(ch);
+                }
+                Token::Word(word)
+            }
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser over the tokens, `||` binding loosest
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Query, String> {
+        let mut query = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            query = Query::Or(Box::new(query), Box::new(self.and()?));
+        }
+        Ok(query)
+    }
+
+    fn and(&mut self) -> Result<Query, String> {
+        let mut query = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            query = Query::And(Box::new(query), Box::new(self.unary()?));
+        }
+        Ok(query)
+    }
+
+    fn unary(&mut self) -> Result<Query, String> {
+        match self.next() {
+            Some(Token::Not) => Ok(Query::Not(Box::new(self.unary()?))),
+            Some(Token::Open) => {
+                let query = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(query),
+                    _ => Err("Missing ')' in selection".to_string()),
+                }
+            }
+            Some(Token::Word(word)) => self.comparison(&word),
+            Some(token) => Err(format!("Unexpected {:?} in selection", token)),
+            None => Err("Selection ends unexpectedly".to_string()),
+        }
+    }
+
+    fn comparison(&mut self, name: &str) -> Result<Query, String> {
+        let field = match name {
+            "size" => Field::Size,
+            "age" => Field::Age,
+            "path" => Field::Path,
+            "name" => Field::Name,
+            "tag" => Field::Tag,
+            "remote" => Field::Remote,
+            "build" => Field::Build,
+            "stale" => Field::Stale,
+            other => return Err(format!("Unknown field {:?} in selection", other)),
+        };
+
+        // A bare `stale` means `stale == true`
+        let op = match self.peek() {
+            Some(Token::Op(op)) => *op,
+            _ if field == Field::Stale => {
+                return Ok(Query::Compare(field, Op::Eq, Value::Bool(true)));
+            }
+            _ => return Err(format!("Expected an operator after {:?}", name)),
+        };
+        self.pos += 1;
+
+        let raw = match self.next() {
+            Some(Token::Word(word)) | Some(Token::Text(word)) => word,
+            _ => return Err(format!("Expected a value after {:?}", name)),
+        };
+        let value = match field {
+            Field::Size => Value::Number(parse_size(&raw)?),
+            Field::Age => Value::Number(parse_days(&raw)?),
+            Field::Stale => Value::Bool(
+                raw.parse()
+                    .map_err(|_| format!("Expected true or false, got {:?}", raw))?,
+            ),
+            Field::Path => Value::Text(expand_home(&raw)),
+            Field::Name | Field::Tag => Value::Text(raw),
+            Field::Remote => Value::Text(raw.to_lowercase()),
+            Field::Build => match raw.as_str() {
+                "passing" | "broken" | "unknown" => Value::Text(raw),
+                _ => {
+                    return Err(format!(
+                        "Expected passing, broken or unknown, got {:?}",
+                        raw
+                    ));
+                }
+            },
+        };
+
+        let valid = match field {
+            Field::Size | Field::Age => op != Op::Contains,
+            Field::Stale | Field::Tag | Field::Build => matches!(op, Op::Eq | Op::Ne),
+            Field::Path | Field::Name | Field::Remote => {
+                matches!(op, Op::Eq | Op::Ne | Op::Contains)
+            }
+        };
+        if !valid {
+            return Err(format!("This operator cannot be used with {:?}", name));
+        }
+        Ok(Query::Compare(field, op, value))
+    }
+}
+
+/// Parses an age like `30d`, `2w` or `1y` into days; a bare number is days
+fn parse_days(input: &str) -> Result<u64, String> {
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("Invalid age: {:?}", input))?;
+    let days_per_unit = match unit {
+        "" | "d" => 1,
+        "w" => 7,
+        "y" => 365,
+        other => return Err(format!("Unknown age unit {:?} in {:?}", other, input)),
+    };
+    number
+        .checked_mul(days_per_unit)
+        .ok_or_else(|| format!("Age too large: {:?}", input))
+}
+
+/// Replaces a leading `~` with the home directory
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) => format!("{}{}", home.display(), rest),
+        _ => path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes a parsed query back out with full parentheses, to check how it grouped
+    fn shape(query: &Query) -> String {
+        match query {
+            Query::And(a, b) => format!("({} && {})", shape(a), shape(b)),
+            Query::Or(a, b) => format!("({} || {})", shape(a), shape(b)),
+            Query::Not(query) => format!("!{}", shape(query)),
+            Query::Compare(field, _, Value::Number(n)) => format!("{:?}:{}", field, n),
+            Query::Compare(field, _, Value::Text(text)) => format!("{:?}:{}", field, text),
+            Query::Compare(field, _, Value::Bool(b)) => format!("{:?}:{}", field, b),
+        }
+    }
+
+    fn parsed(input: &str) -> String {
+        shape(&Query::parse(input).unwrap())
+    }
+
+    fn compared(input: &str) -> (Field, Op, Value) {
+        match Query::parse(input).unwrap() {
+            Query::Compare(field, op, value) => (field, op, value),
+            other => panic!("{:?} is not a single comparison", other),
+        }
+    }
+
+    #[test]
+    fn binds_and_tighter_than_or() {
+        assert_eq!(
+            parsed("name == a || name == b && name == c"),
+            "(Name:a || (Name:b && Name:c))"
+        );
+        assert_eq!(
+            parsed("(name == a || name == b) && name == c"),
+            "((Name:a || Name:b) && Name:c)"
+        );
+        assert_eq!(parsed("!name == a && name == b"), "(!Name:a && Name:b)");
+        assert_eq!(parsed("!(name == a && name == b)"), "!(Name:a && Name:b)");
+    }
+
+    #[test]
+    fn reads_bare_stale_as_true() {
+        assert_eq!(parsed("stale"), "Stale:true");
+        assert_eq!(parsed("stale && !stale"), "(Stale:true && !Stale:true)");
+        assert_eq!(parsed("stale == false"), "Stale:false");
+    }
+
+    #[test]
+    fn converts_size_and_age_units() {
+        for (input, bytes) in [
+            ("size > 512", 512),
+            ("size > 2KB", 2 * 1024),
+            ("size > 3mb", 3 * 1024 * 1024),
+            ("size >= 1.5Gb", 3 * 1024 * 1024 * 1024 / 2),
+        ] {
+            assert!(
+                matches!(compared(input).2, Value::Number(n) if n == bytes),
+                "{}",
+                input
+            );
+        }
+        for (input, days) in [
+            ("age > 30", 30),
+            ("age > 30d", 30),
+            ("age < 2w", 14),
+            ("age >= 1y", 365),
+        ] {
+            assert!(
+                matches!(compared(input).2, Value::Number(n) if n == days),
+                "{}",
+                input
+            );
+        }
+        assert!(Query::parse("size > 1XB").is_err());
+        assert!(Query::parse("age > 3h").is_err());
+        assert!(
+            Query::parse("age > 99999999999999999y")
+                .unwrap_err()
+                .contains("too large")
+        );
+    }
+
+    #[test]
+    fn expands_home_in_paths() {
+        let home = dirs::home_dir().unwrap().display().to_string();
+        let (field, op, value) = compared("path ~ \"~/oss\"");
+        assert_eq!((field, op), (Field::Path, Op::Contains));
+        assert!(matches!(value, Value::Text(text) if text == format!("{}/oss", home)));
+        assert!(matches!(compared("name == \"~/oss\"").2, Value::Text(text) if text == "~/oss"));
+    }
+
+    #[test]
+    fn rejects_malformed_selections() {
+        for input in [
+            "stale stale",
+            "name == a name == b",
+            "(name == a))",
+            "(name == a",
+            "owner == me",
+            "size ~ 1GB",
+            "tag > a",
+            "name ==",
+            "name == a &",
+            "",
+        ] {
+            assert!(Query::parse(input).is_err(), "{:?} was accepted", input);
+        }
+        assert!(
+            Query::parse("owner == me")
+                .unwrap_err()
+                .contains("Unknown field")
+        );
+        assert!(
+            Query::parse("stale stale")
+                .unwrap_err()
+                .contains("Unexpected")
+        );
+    }
+}
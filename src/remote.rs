@@ -0,0 +1,159 @@
+use std::error::Error;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::scanner::rust_project::RustProject;
+
+/// Name of the helper binary expected on remote hosts
+const DEFAULT_REMOTE_PROGRAM: &str = "clear-target";
+
+/// Removes the path read from stdin after the checks only the remote host can make:
+/// it must not be or contain `$HOME`, and a directory not named `target` must carry the
+/// markers cargo puts in the target directories it creates under a configured name
+const REMOVE_SCRIPT: &str = r#"read -r p || exit 1
+case "$HOME/" in "$p"/*) echo "refused: $p is or contains the home directory" >&2; exit 1;; esac
+if [ "${p##*/}" != target ] && [ ! -f "$p/.rustc_info.json" ] && ! grep -qs "created by cargo" "$p/CACHEDIR.TAG"; then
+  echo "refused: $p does not look like a cargo target directory" >&2; exit 1
+fi
+rm -rf -- "$p""#;
+
+/// A build server reached over SSH
+///
+/// Scanning runs `clear-target list --json` on the remote host; deletion runs `rm -rf`
+/// there. Authentication and host configuration are left to the user's `ssh` setup.
+#[derive(Debug, Clone)]
+pub struct RemoteHost {
+    /// SSH destination, e.g. `user@build-server`
+    destination: String,
+    /// Program invoked on the remote host to scan for projects
+    program: String,
+}
+
+impl RemoteHost {
+    /// Creates a remote host for the given SSH destination
+    pub fn new(destination: &str) -> Self {
+        Self {
+            destination: destination.to_string(),
+            program: DEFAULT_REMOTE_PROGRAM.to_string(),
+        }
+    }
+
+    /// Returns the SSH destination
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    /// Scans the remote host for Rust projects using the remote helper
+    pub fn list_projects(&self) -> Result<Vec<RustProject>, Box<dyn Error>> {
+        let output = Command::new("ssh")
+            .arg(&self.destination)
+            .arg("--")
+            .args([self.program.as_str(), "list", "--json"])
+            .stdin(Stdio::null())
+            .stderr(Stdio::inherit())
+            .output()?;
+
+        if !output.status.success() {
+            return Err(format!(
+                "Remote scan on {} failed ({})",
+                self.destination, output.status
+            )
+            .into());
+        }
+
+        // The JSON listing is the last line; anything before it is progress output
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let json = stdout
+            .lines()
+            .rev()
+            .find(|line| line.trim_start().starts_with('['))
+            .ok_or_else(|| format!("No project listing received from {}", self.destination))?;
+
+        let mut projects: Vec<RustProject> = serde_json::from_str(json)?;
+        for project in &mut projects {
+            project.host = Some(self.destination.clone());
+        }
+        Ok(projects)
+    }
+
+    /// Permanently removes a target directory on the remote host
+    ///
+    /// Remote targets cannot go through the local guard, so the path is checked by
+    /// `check_remote_target` here and against the remote `$HOME` before `rm` runs there.
+    pub fn remove_dir(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let path = path.to_string_lossy();
+        check_remote_target(&path).map_err(|reason| {
+            format!(
+                "Refusing to remove {} on {}: {}",
+                path, self.destination, reason
+            )
+        })?;
+
+        // The remote shell re-parses the command, so pass the path through stdin
+        let mut child = Command::new("ssh")
+            .arg(&self.destination)
+            .arg("--")
+            .arg(REMOVE_SCRIPT)
+            .stdin(Stdio::piped())
+            .spawn()?;
+
+        if let Some(stdin) = child.stdin.as_mut() {
+            writeln!(stdin, "{}", path)?;
+        }
+
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(format!("ssh {} exited with {}", self.destination, status).into());
+        }
+        Ok(())
+    }
+}
+
+/// Checks a target path reported by a remote host before anything is removed there
+///
+/// The path must be absolute, free of `.` and `..` parts and at least two levels below
+/// `/`. Its name and the remote home directory are checked on the host itself.
+fn check_remote_target(path: &str) -> Result<(), String> {
+    if !path.starts_with('/') {
+        return Err("the path is not absolute".into());
+    }
+    let parts: Vec<&str> = path.split('/').filter(|part| !part.is_empty()).collect();
+    if parts.iter().any(|part| *part == "." || *part == "..") {
+        return Err("the path is not normalized".into());
+    }
+    match parts.as_slice() {
+        [] => Err("it is the filesystem root".into()),
+        [_] => Err("it is a top-level directory".into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refuses_unsafe_remote_targets() {
+        for path in [
+            "",
+            "target",
+            "srv/app/target",
+            "/",
+            "//",
+            "/srv",
+            "/srv/",
+            "/srv/app/../target",
+            "/srv/./target",
+        ] {
+            assert!(
+                check_remote_target(path).is_err(),
+                "{:?} was accepted",
+                path
+            );
+        }
+        for path in ["/srv/app/target", "/home/dev/app/build-cache"] {
+            assert!(check_remote_target(path).is_ok(), "{:?} was refused", path);
+        }
+    }
+}
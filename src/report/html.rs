@@ -0,0 +1,203 @@
+use std::fmt::Write;
+
+use chrono::Local;
+
+use crate::report::usage::UserTotal;
+use crate::report::{Report, format_delta};
+use crate::size::format_bytes;
+use crate::ui::age_label;
+
+/// Width of the longest bar in the largest-targets chart, in pixels
+const BAR_WIDTH: f64 = 480.0;
+/// Height of one bar, including the gap below it
+const BAR_HEIGHT: usize = 24;
+/// Room left of the bars for the project names
+const LABEL_WIDTH: usize = 180;
+
+/// Renders a report as a standalone HTML page with an inline SVG bar chart
+pub fn render(report: &Report) -> String {
+    let current = &report.current;
+    let title = format!(
+        "Rust target report, {}",
+        current.generated.format("%Y-%m-%d %H:%M")
+    );
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>",
+        escape(&title)
+    )
+    .ok();
+    writeln!(
+        out,
+        "<style>body{{font-family:sans-serif;margin:2em}}table{{border-collapse:collapse}}\
+         td,th{{border:1px solid #ccc;padding:4px 8px}}td.num{{text-align:right}}\
+         .stale{{color:#c0392b}}</style>\n</head>\n<body>"
+    )
+    .ok();
+    writeln!(out, "<h1>{}</h1>", escape(&title)).ok();
+    writeln!(
+        out,
+        "<p>{} targets, <b>{}</b> in total. {} stale targets hold <b>{}</b> ({:.0}%).</p>",
+        current.targets,
+        format_bytes(current.total_bytes),
+        report.stale_targets,
+        format_bytes(current.stale_bytes),
+        report.stale_percent()
+    )
+    .ok();
+    if let Some(previous) = &report.previous {
+        writeln!(
+            out,
+            "<p>Since {}: total {}, stale {}, {} targets (was {}).</p>",
+            previous.generated.format("%Y-%m-%d %H:%M"),
+            format_delta(previous.total_bytes, current.total_bytes),
+            format_delta(previous.stale_bytes, current.stale_bytes),
+            current.targets,
+            previous.targets
+        )
+        .ok();
+    }
+
+    writeln!(out, "<h2>Largest targets</h2>").ok();
+    out.push_str(&chart(report));
+    writeln!(
+        out,
+        "<table>\n<tr><th>#</th><th>Size</th><th>Last used</th><th>Path</th></tr>"
+    )
+    .ok();
+    for (i, row) in report.largest.iter().enumerate() {
+        writeln!(
+            out,
+            "<tr{}><td class=\"num\">{}</td><td class=\"num\">{}</td><td>{}</td><td>{}</td></tr>",
+            if row.is_stale { " class=\"stale\"" } else { "" },
+            i + 1,
+            format_bytes(row.size_bytes),
+            age_label(row.last_accessed),
+            escape(&row.path)
+        )
+        .ok();
+    }
+    writeln!(out, "</table>").ok();
+
+    writeln!(out, "<h2>Per-directory totals</h2>").ok();
+    writeln!(
+        out,
+        "<table>\n<tr><th>Size</th><th>Targets</th><th>Directory</th></tr>"
+    )
+    .ok();
+    for directory in &report.directories {
+        writeln!(
+            out,
+            "<tr><td class=\"num\">{}</td><td class=\"num\">{}</td><td>{}</td></tr>",
+            format_bytes(directory.size_bytes),
+            directory.targets,
+            escape(&directory.path)
+        )
+        .ok();
+    }
+    writeln!(out, "</table>\n</body>\n</html>").ok();
+    out
+}
+
+/// Renders the target usage per user as a standalone HTML page, for `watch`'s report address
+pub fn render_usage(users: &[UserTotal]) -> String {
+    let title = format!(
+        "Rust target usage per user, {}",
+        Local::now().format("%Y-%m-%d %H:%M")
+    );
+    let total: u64 = users.iter().map(|user| user.size_bytes).sum();
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>",
+        escape(&title)
+    )
+    .ok();
+    writeln!(
+        out,
+        "<style>body{{font-family:sans-serif;margin:2em}}table{{border-collapse:collapse}}\
+         td,th{{border:1px solid #ccc;padding:4px 8px}}td.num{{text-align:right}}</style>\n\
+         </head>\n<body>"
+    )
+    .ok();
+    writeln!(out, "<h1>{}</h1>", escape(&title)).ok();
+    writeln!(
+        out,
+        "<p>{} users hold <b>{}</b> in target directories.</p>",
+        users.len(),
+        format_bytes(total)
+    )
+    .ok();
+    writeln!(
+        out,
+        "<table>\n<tr><th>User</th><th>Size</th><th>Stale</th><th>Targets</th><th>Largest</th></tr>"
+    )
+    .ok();
+    for user in users {
+        writeln!(
+            out,
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+             <td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+            escape(&user.user),
+            format_bytes(user.size_bytes),
+            format_bytes(user.stale_bytes),
+            user.targets,
+            format_bytes(user.largest_bytes)
+        )
+        .ok();
+    }
+    writeln!(out, "</table>\n</body>\n</html>").ok();
+    out
+}
+
+/// Horizontal bar chart of the largest targets, stale ones in red
+fn chart(report: &Report) -> String {
+    let largest = report
+        .largest
+        .first()
+        .map_or(0, |row| row.size_bytes)
+        .max(1);
+    let height = report.largest.len() * BAR_HEIGHT;
+    let width = LABEL_WIDTH + BAR_WIDTH as usize + 100;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-size=\"12\">\n",
+        width, height
+    );
+    for (i, row) in report.largest.iter().enumerate() {
+        let y = i * BAR_HEIGHT;
+        let bar = (row.size_bytes as f64 / largest as f64 * BAR_WIDTH).max(1.0);
+        let name = row.path.rsplit(['/', '\\']).next().unwrap_or(&row.path);
+        writeln!(
+            svg,
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>\
+             <rect x=\"{}\" y=\"{}\" width=\"{:.0}\" height=\"{}\" fill=\"{}\"/>\
+             <text x=\"{:.0}\" y=\"{}\">{}</text>",
+            LABEL_WIDTH - 6,
+            y + 15,
+            escape(name),
+            LABEL_WIDTH,
+            y + 2,
+            bar,
+            BAR_HEIGHT - 6,
+            if row.is_stale { "#e74c3c" } else { "#3498db" },
+            LABEL_WIDTH as f64 + bar + 6.0,
+            y + 15,
+            format_bytes(row.size_bytes)
+        )
+        .ok();
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Escapes text for use in HTML content and attribute values
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
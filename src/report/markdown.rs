@@ -0,0 +1,86 @@
+use std::fmt::Write;
+
+use crate::report::{Report, format_delta};
+use crate::size::format_bytes;
+use crate::ui::age_label;
+
+/// Width of the longest bar in the largest-targets chart, in characters
+const BAR_WIDTH: usize = 24;
+
+/// Renders a report as Markdown, with the chart drawn as text bars in a table column
+pub fn render(report: &Report) -> String {
+    let mut out = String::new();
+    let current = &report.current;
+
+    writeln!(
+        out,
+        "# Rust target report, {}\n",
+        current.generated.format("%Y-%m-%d %H:%M")
+    )
+    .ok();
+    writeln!(
+        out,
+        "{} targets, **{}** in total. {} stale targets hold **{}** ({:.0}%).\n",
+        current.targets,
+        format_bytes(current.total_bytes),
+        report.stale_targets,
+        format_bytes(current.stale_bytes),
+        report.stale_percent()
+    )
+    .ok();
+    if let Some(previous) = &report.previous {
+        writeln!(
+            out,
+            "Since {}: total {}, stale {}, {} targets (was {}).\n",
+            previous.generated.format("%Y-%m-%d %H:%M"),
+            format_delta(previous.total_bytes, current.total_bytes),
+            format_delta(previous.stale_bytes, current.stale_bytes),
+            current.targets,
+            previous.targets
+        )
+        .ok();
+    }
+
+    writeln!(out, "## Largest targets\n").ok();
+    writeln!(out, "| # | Size | Last used | Stale | Path | |").ok();
+    writeln!(out, "|--:|-----:|-----------|:-----:|------|--|").ok();
+    let largest = report.largest.first().map_or(0, |row| row.size_bytes);
+    for (i, row) in report.largest.iter().enumerate() {
+        writeln!(
+            out,
+            "| {} | {} | {} | {} | `{}` | {} |",
+            i + 1,
+            format_bytes(row.size_bytes),
+            age_label(row.last_accessed),
+            if row.is_stale { "yes" } else { "" },
+            row.path,
+            bar(row.size_bytes, largest)
+        )
+        .ok();
+    }
+
+    writeln!(out, "\n## Per-directory totals\n").ok();
+    writeln!(out, "| Size | Targets | Directory |").ok();
+    writeln!(out, "|-----:|--------:|-----------|").ok();
+    for directory in &report.directories {
+        writeln!(
+            out,
+            "| {} | {} | `{}` |",
+            format_bytes(directory.size_bytes),
+            directory.targets,
+            directory.path
+        )
+        .ok();
+    }
+
+    out
+}
+
+/// Text bar proportional to `value`, the largest value getting the full width
+fn bar(value: u64, largest: u64) -> String {
+    if largest == 0 {
+        return String::new();
+    }
+    let width = (value as f64 / largest as f64 * BAR_WIDTH as f64).round() as usize;
+    "█".repeat(width.max(1))
+}
@@ -0,0 +1,205 @@
+use chrono::Local;
+use std::cmp::{Ordering, Reverse};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::cache::snapshot::Snapshot;
+use crate::scanner::compilers;
+use crate::scanner::rust_project::RustProject;
+use crate::scanner::target_finder::TargetFinder;
+use crate::size::format_bytes;
+
+pub mod html;
+pub mod markdown;
+pub mod server;
+pub mod stats;
+pub mod terminal;
+pub mod usage;
+
+/// Number of rows in the largest-targets and per-directory sections
+const TOP_COUNT: usize = 10;
+
+/// File formats a report can be written in, besides the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+    /// Markdown, for tickets and wikis
+    Md,
+    /// A standalone HTML page
+    Html,
+}
+
+impl ReportFormat {
+    /// File extension used for the default output file name
+    pub fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Md => "md",
+            ReportFormat::Html => "html",
+        }
+    }
+
+    /// Renders the report in this format
+    pub fn render(self, report: &Report) -> String {
+        match self {
+            ReportFormat::Md => markdown::render(report),
+            ReportFormat::Html => html::render(report),
+        }
+    }
+}
+
+/// One target directory as shown in a report
+#[derive(Debug, Clone)]
+pub struct TargetRow {
+    pub path: String,
+    pub size_bytes: u64,
+    pub last_accessed: SystemTime,
+    pub is_stale: bool,
+    /// Whether the target can be cleaned at all
+    pub available: bool,
+}
+
+/// Combined size of the targets of projects sharing a parent directory
+#[derive(Debug, Clone)]
+pub struct DirectoryTotal {
+    pub path: String,
+    pub targets: usize,
+    pub size_bytes: u64,
+}
+
+/// Target holding artifacts from more than one compiler
+#[derive(Debug, Clone)]
+pub struct CompilerRow {
+    pub path: String,
+    /// Compiler cargo last ran in the target, e.g. `rustc 1.80.0 (051478957 2024-07-21)`
+    pub last_version: Option<String>,
+    /// Number of distinct compilers with artifacts in the target
+    pub compilers: usize,
+    /// Size of the artifacts of compilers older than the installed toolchain
+    pub outdated_bytes: u64,
+}
+
+/// Read-only overview of the target directories found by a scan
+#[derive(Debug)]
+pub struct Report {
+    /// Totals of this report
+    pub current: Snapshot,
+    /// Totals of the previous report, for the trend
+    pub previous: Option<Snapshot>,
+    /// Largest targets, biggest first
+    pub largest: Vec<TargetRow>,
+    /// Directories holding the most target data, biggest first
+    pub directories: Vec<DirectoryTotal>,
+    /// Number of stale targets that can be cleaned
+    pub stale_targets: usize,
+    /// Number of targets that cannot be cleaned (symlinks, unreadable, ...)
+    pub unavailable_targets: usize,
+    /// Targets built by several compilers, most outdated data first
+    pub compilers: Vec<CompilerRow>,
+}
+
+impl Report {
+    /// Builds a report from scanned projects whose staleness is already determined
+    pub fn new(projects: &[RustProject], previous: Option<Snapshot>) -> Self {
+        let mut rows: Vec<TargetRow> = projects
+            .iter()
+            .filter_map(|project| {
+                let target_info = project.target_info.as_ref()?;
+                Some(TargetRow {
+                    path: project.display_path(),
+                    size_bytes: target_info.size_bytes,
+                    last_accessed: target_info.last_accessed,
+                    is_stale: target_info.is_stale,
+                    available: target_info.availability.reason().is_none(),
+                })
+            })
+            .collect();
+        rows.sort_by_key(|row| Reverse(row.size_bytes));
+
+        let mut by_directory: HashMap<String, DirectoryTotal> = HashMap::new();
+        for row in &rows {
+            let parent = Path::new(&row.path)
+                .parent()
+                .map(|parent| parent.display().to_string())
+                .unwrap_or_default();
+            let total = by_directory
+                .entry(parent.clone())
+                .or_insert_with(|| DirectoryTotal {
+                    path: parent,
+                    targets: 0,
+                    size_bytes: 0,
+                });
+            total.targets += 1;
+            total.size_bytes += row.size_bytes;
+        }
+        let mut directories: Vec<DirectoryTotal> = by_directory.into_values().collect();
+        directories.sort_by_key(|directory| Reverse(directory.size_bytes));
+        directories.truncate(TOP_COUNT);
+
+        // Only targets that can actually be cleaned count as reclaimable
+        let stale: Vec<&TargetRow> = rows
+            .iter()
+            .filter(|row| row.is_stale && row.available)
+            .collect();
+        let current = Snapshot {
+            generated: Local::now(),
+            targets: rows.len(),
+            total_bytes: rows.iter().map(|row| row.size_bytes).sum(),
+            stale_bytes: stale.iter().map(|row| row.size_bytes).sum(),
+        };
+        let stale_targets = stale.len();
+        let unavailable_targets = rows.iter().filter(|row| !row.available).count();
+
+        rows.truncate(TOP_COUNT);
+        Self {
+            current,
+            previous,
+            largest: rows,
+            directories,
+            stale_targets,
+            unavailable_targets,
+            compilers: Vec::new(),
+        }
+    }
+
+    /// Adds which compilers built each local target, reading their fingerprints
+    pub fn with_compilers(mut self, projects: &[RustProject]) -> Self {
+        self.compilers = projects
+            .iter()
+            .filter(|project| project.host.is_none())
+            .filter_map(|project| {
+                let target_info = project.target_info.as_ref()?;
+                let usage = compilers::usage(&target_info.path);
+                (usage.compilers > 1).then(|| CompilerRow {
+                    path: project.display_path(),
+                    last_version: usage.last_version,
+                    compilers: usage.compilers,
+                    outdated_bytes: usage
+                        .outdated
+                        .iter()
+                        .map(|p| TargetFinder::path_size(p))
+                        .sum(),
+                })
+            })
+            .collect();
+        self.compilers
+            .sort_by_key(|row| Reverse(row.outdated_bytes));
+        self
+    }
+
+    /// Share of the total size taken by stale targets, in percent
+    pub fn stale_percent(&self) -> f64 {
+        if self.current.total_bytes == 0 {
+            return 0.0;
+        }
+        self.current.stale_bytes as f64 * 100.0 / self.current.total_bytes as f64
+    }
+}
+
+/// Formats a size difference with its sign, e.g. "+1.20 GB"
+pub fn format_delta(before: u64, after: u64) -> String {
+    match after.cmp(&before) {
+        Ordering::Greater => format!("+{}", format_bytes(after - before)),
+        Ordering::Less => format!("-{}", format_bytes(before - after)),
+        Ordering::Equal => "no change".to_string(),
+    }
+}
@@ -0,0 +1,67 @@
+use std::error::Error;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::time::Duration;
+
+use crate::cache::projects::ProjectCache;
+use crate::report::{html, usage};
+use crate::scanner::target_finder::TargetFinder;
+
+/// How long a client may take to send its request before the connection is dropped
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serves the target usage per user as a read-only HTML page in the background
+///
+/// The page is rendered from the project list `watch` keeps in the cache on every
+/// request, so it is as current as the watcher; targets unused for `stale_after`
+/// count as stale. Only `GET /` is answered; nothing can be changed through it.
+pub fn spawn(address: SocketAddr, stale_after: Duration) -> Result<(), Box<dyn Error>> {
+    let listener = TcpListener::bind(address)
+        .map_err(|e| format!("Failed to serve the report on {}: {}", address, e))?;
+    std::thread::spawn(move || {
+        for stream in listener.incoming().filter_map(Result::ok) {
+            if let Err(e) = respond(stream, stale_after) {
+                eprintln!("Warning: Failed to answer a report request: {}", e);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Answers one request, closing the connection afterwards
+fn respond(mut stream: TcpStream, stale_after: Duration) -> Result<(), Box<dyn Error>> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+
+    let (status, content_type, body) = match (method, path) {
+        ("GET", "/") => {
+            let mut projects = ProjectCache::load()?.unwrap_or_default();
+            for target_info in projects.iter_mut().filter_map(|p| p.target_info.as_mut()) {
+                TargetFinder::update_stale_status(target_info, stale_after)?;
+            }
+            (
+                "200 OK",
+                "text/html; charset=utf-8",
+                html::render_usage(&usage::by_user(&projects)),
+            )
+        }
+        ("GET", _) => ("404 Not Found", "text/plain", "Not found\n".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "Read-only\n".to_string(),
+        ),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    Ok(())
+}
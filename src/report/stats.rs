@@ -0,0 +1,199 @@
+use crossterm::style::Stylize;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use crate::cache::snapshot::Snapshot;
+use crate::report::usage::{self, UserTotal};
+use crate::report::{DirectoryTotal, Report, format_delta};
+use crate::scanner::rust_project::RustProject;
+use crate::size::format_bytes;
+
+/// Ecosystem-wide totals printed by `clear-target stats`
+#[derive(Debug)]
+pub struct Stats {
+    /// Projects found, with or without a target directory
+    pub projects: usize,
+    /// Totals, largest targets and trend, shared with the report
+    pub report: Report,
+    /// Target data under each search path, biggest first
+    pub roots: Vec<DirectoryTotal>,
+    /// Target data per git remote host and owner, biggest first
+    pub remotes: Vec<RemoteTotal>,
+    /// Target data per user, biggest first; empty unless all users were scanned
+    pub users: Vec<UserTotal>,
+}
+
+/// Combined targets of the projects cloned from one remote owner
+#[derive(Debug, Clone)]
+pub struct RemoteTotal {
+    /// Host and owner, e.g. `github.com/mycompany`; `None` for projects without a remote
+    pub remote: Option<String>,
+    pub targets: usize,
+    pub size_bytes: u64,
+    pub stale_bytes: u64,
+}
+
+impl Stats {
+    /// Builds the totals from scanned projects whose staleness is already determined
+    pub fn new(projects: &[RustProject], roots: &[PathBuf], previous: Option<Snapshot>) -> Self {
+        let mut root_totals: Vec<DirectoryTotal> = roots
+            .iter()
+            .map(|root| {
+                let targets: Vec<u64> = projects
+                    .iter()
+                    .filter(|project| project.host.is_none() && project.path.starts_with(root))
+                    .filter_map(|project| project.target_info.as_ref())
+                    .map(|target_info| target_info.size_bytes)
+                    .collect();
+                DirectoryTotal {
+                    path: root.display().to_string(),
+                    targets: targets.len(),
+                    size_bytes: targets.iter().sum(),
+                }
+            })
+            .collect();
+        root_totals.sort_by_key(|root| Reverse(root.size_bytes));
+
+        let mut remotes: BTreeMap<Option<&String>, RemoteTotal> = BTreeMap::new();
+        for project in projects {
+            let Some(target_info) = &project.target_info else {
+                continue;
+            };
+            let total = remotes
+                .entry(project.remote.as_ref())
+                .or_insert_with(|| RemoteTotal {
+                    remote: project.remote.clone(),
+                    targets: 0,
+                    size_bytes: 0,
+                    stale_bytes: 0,
+                });
+            total.targets += 1;
+            total.size_bytes += target_info.size_bytes;
+            if target_info.is_stale {
+                total.stale_bytes += target_info.size_bytes;
+            }
+        }
+        let mut remotes: Vec<RemoteTotal> = remotes.into_values().collect();
+        // Projects without a remote go last, whatever their size
+        remotes.sort_by_key(|total| (total.remote.is_none(), Reverse(total.size_bytes)));
+
+        Self {
+            projects: projects.len(),
+            report: Report::new(projects, previous),
+            roots: root_totals,
+            remotes,
+            users: Vec::new(),
+        }
+    }
+
+    /// Adds the totals per owner of the project directories
+    pub fn with_users(mut self, projects: &[RustProject]) -> Self {
+        self.users = usage::by_user(projects);
+        self
+    }
+}
+
+/// Renders the totals as a short plain-text summary, colored when `color` is set
+pub fn render(stats: &Stats, color: bool) -> String {
+    let heading = |out: &mut String, title: &str| {
+        if color {
+            writeln!(out, "\n{}", title.bold().cyan()).ok();
+        } else {
+            writeln!(out, "\n{}", title).ok();
+        }
+    };
+
+    let mut out = String::new();
+    let report = &stats.report;
+    let current = &report.current;
+    writeln!(
+        out,
+        "Projects:     {} ({} with a target directory)",
+        stats.projects, current.targets
+    )
+    .ok();
+    writeln!(out, "Target data:  {}", format_bytes(current.total_bytes)).ok();
+    writeln!(
+        out,
+        "Stale:        {} in {} targets ({:.0}%)",
+        format_bytes(current.stale_bytes),
+        report.stale_targets,
+        report.stale_percent()
+    )
+    .ok();
+    writeln!(
+        out,
+        "Fresh:        {}",
+        format_bytes(current.total_bytes - current.stale_bytes)
+    )
+    .ok();
+    match &report.previous {
+        Some(previous) => writeln!(
+            out,
+            "Change:       {} since {} (stale {})",
+            format_delta(previous.total_bytes, current.total_bytes),
+            previous.generated.format("%Y-%m-%d %H:%M"),
+            format_delta(previous.stale_bytes, current.stale_bytes)
+        ),
+        None => writeln!(out, "Change:       first run, nothing to compare with"),
+    }
+    .ok();
+
+    heading(&mut out, "Largest targets");
+    for (i, row) in report.largest.iter().enumerate() {
+        writeln!(
+            out,
+            "{:>2}. {:>10}  {}",
+            i + 1,
+            format_bytes(row.size_bytes),
+            row.path
+        )
+        .ok();
+    }
+
+    heading(&mut out, "By search path");
+    for root in &stats.roots {
+        writeln!(
+            out,
+            "{:>10}  {:>3} targets  {}",
+            format_bytes(root.size_bytes),
+            root.targets,
+            root.path
+        )
+        .ok();
+    }
+
+    if !stats.users.is_empty() {
+        heading(&mut out, "By user");
+        for total in &stats.users {
+            writeln!(
+                out,
+                "{:>10}  {:>3} targets  {:>10} stale  {}",
+                format_bytes(total.size_bytes),
+                total.targets,
+                format_bytes(total.stale_bytes),
+                total.user
+            )
+            .ok();
+        }
+    }
+
+    if stats.remotes.iter().any(|total| total.remote.is_some()) {
+        heading(&mut out, "By git remote");
+        for total in &stats.remotes {
+            writeln!(
+                out,
+                "{:>10}  {:>3} targets  {:>10} stale  {}",
+                format_bytes(total.size_bytes),
+                total.targets,
+                format_bytes(total.stale_bytes),
+                total.remote.as_deref().unwrap_or("(no remote)")
+            )
+            .ok();
+        }
+    }
+
+    out
+}
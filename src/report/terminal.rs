@@ -0,0 +1,142 @@
+use crossterm::style::{Color, Stylize};
+use std::fmt::Write;
+
+use crate::report::{Report, format_delta};
+use crate::size::format_bytes;
+use crate::ui::age_label;
+
+/// Renders a report as sectioned plain text, colored when `color` is set
+pub fn render(report: &Report, color: bool) -> String {
+    let paint = |text: String, fg: Color| {
+        if color {
+            text.with(fg).to_string()
+        } else {
+            text
+        }
+    };
+    let heading = |out: &mut String, title: &str| {
+        if color {
+            writeln!(out, "\n{}", title.bold().cyan()).ok();
+        } else {
+            writeln!(out, "\n{}\n{}", title, "-".repeat(title.chars().count())).ok();
+        }
+    };
+
+    let mut out = String::new();
+    let current = &report.current;
+    let title = format!(
+        "Rust target report, {}",
+        current.generated.format("%Y-%m-%d %H:%M")
+    );
+    writeln!(
+        out,
+        "{}",
+        if color {
+            title.bold().to_string()
+        } else {
+            title
+        }
+    )
+    .ok();
+    writeln!(
+        out,
+        "{} targets, {} in total",
+        current.targets,
+        format_bytes(current.total_bytes)
+    )
+    .ok();
+
+    heading(&mut out, "Trend");
+    match &report.previous {
+        Some(previous) => {
+            writeln!(
+                out,
+                "Since {}: total {}, stale {}, {} targets (was {})",
+                previous.generated.format("%Y-%m-%d %H:%M"),
+                format_delta(previous.total_bytes, current.total_bytes),
+                format_delta(previous.stale_bytes, current.stale_bytes),
+                current.targets,
+                previous.targets
+            )
+            .ok();
+        }
+        None => {
+            writeln!(out, "No previous report to compare with").ok();
+        }
+    }
+
+    heading(&mut out, "Largest targets");
+    for (i, row) in report.largest.iter().enumerate() {
+        let age = age_label(row.last_accessed);
+        writeln!(
+            out,
+            "{:>2}. {:>10}  {}  {}",
+            i + 1,
+            format_bytes(row.size_bytes),
+            // Pad before coloring; escape codes would throw off the width
+            paint(
+                format!("{:<14}", age),
+                if row.is_stale {
+                    Color::Red
+                } else {
+                    Color::Green
+                }
+            ),
+            row.path
+        )
+        .ok();
+    }
+
+    heading(&mut out, "Stale targets");
+    writeln!(
+        out,
+        "{} stale targets hold {} ({:.0}% of the total), reclaimable with `clear-target clean`",
+        report.stale_targets,
+        paint(format_bytes(current.stale_bytes), Color::Yellow),
+        report.stale_percent()
+    )
+    .ok();
+    if report.unavailable_targets > 0 {
+        writeln!(
+            out,
+            "{} targets cannot be cleaned (symlinks or unreadable)",
+            report.unavailable_targets
+        )
+        .ok();
+    }
+
+    if !report.compilers.is_empty() {
+        heading(&mut out, "Targets built by several compilers");
+        for row in &report.compilers {
+            writeln!(
+                out,
+                "{:>10}  {} compilers, last {}  {}",
+                format_bytes(row.outdated_bytes),
+                row.compilers,
+                row.last_version.as_deref().unwrap_or("unknown"),
+                row.path
+            )
+            .ok();
+        }
+        writeln!(
+            out,
+            "Sizes are artifacts of compilers older than the installed one, \
+             reclaimable with `clear-target clean --only old-compilers`"
+        )
+        .ok();
+    }
+
+    heading(&mut out, "Per-directory totals");
+    for directory in &report.directories {
+        writeln!(
+            out,
+            "{:>10}  {:>3} targets  {}",
+            format_bytes(directory.size_bytes),
+            directory.targets,
+            directory.path
+        )
+        .ok();
+    }
+
+    out
+}
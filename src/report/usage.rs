@@ -0,0 +1,47 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use crate::scanner::rust_project::RustProject;
+use crate::scanner::users::owner_name;
+
+/// Combined targets of the projects one user owns, on a machine shared by a team
+#[derive(Debug, Clone)]
+pub struct UserTotal {
+    /// Login name of the owner of the project directories, or their uid if unknown
+    pub user: String,
+    pub targets: usize,
+    pub size_bytes: u64,
+    pub stale_bytes: u64,
+    /// Size of that user's largest target
+    pub largest_bytes: u64,
+}
+
+/// Totals per owner of the project directories, biggest first
+///
+/// A project is counted for the owner of its directory rather than of its target,
+/// which may have been built by a service account or a container.
+pub fn by_user(projects: &[RustProject]) -> Vec<UserTotal> {
+    let mut users: BTreeMap<String, UserTotal> = BTreeMap::new();
+    for project in projects {
+        let Some(target_info) = &project.target_info else {
+            continue;
+        };
+        let user = owner_name(&project.path);
+        let total = users.entry(user.clone()).or_insert_with(|| UserTotal {
+            user,
+            targets: 0,
+            size_bytes: 0,
+            stale_bytes: 0,
+            largest_bytes: 0,
+        });
+        total.targets += 1;
+        total.size_bytes += target_info.size_bytes;
+        total.largest_bytes = total.largest_bytes.max(target_info.size_bytes);
+        if target_info.is_stale {
+            total.stale_bytes += target_info.size_bytes;
+        }
+    }
+    let mut users: Vec<UserTotal> = users.into_values().collect();
+    users.sort_by_key(|total| Reverse(total.size_bytes));
+    users
+}
@@ -0,0 +1,101 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Directory cargo writes `--timings` reports to, inside the target
+const TIMINGS_DIR: &str = "cargo-timings";
+/// File cargo creates in a unit's fingerprint directory when it starts building it
+const INVOKED_FILE: &str = "invoked.timestamp";
+
+/// Estimates how long rebuilding a target from scratch would take
+///
+/// A `cargo build --timings` report states the total time of that build and is used
+/// when present. Otherwise each unit's build time is taken from its fingerprint, from
+/// `invoked.timestamp` (written when the unit started) to the newest file written
+/// when it finished. Units build in parallel, so their sum is spread over the CPUs but
+/// never below the slowest unit. The profile that took longest stands for the target.
+pub fn estimate(target: &Path) -> Option<Duration> {
+    timings_total(target).or_else(|| {
+        profiles(target)
+            .iter()
+            .filter_map(|profile| fingerprint_estimate(profile))
+            .max()
+    })
+}
+
+/// Total time of the newest `--timings` report in a target
+fn timings_total(target: &Path) -> Option<Duration> {
+    let newest = fs::read_dir(target.join(TIMINGS_DIR))
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "html"))
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .max_by_key(|(modified, _)| *modified)?;
+    let report = fs::read_to_string(newest.1).ok()?;
+    // The summary table has a row like `<td>Total time:</td><td>84.2s (1m 24.2s)</td>`
+    let after = &report[report.find("Total time:")? + "Total time:".len()..];
+    let start = after.find(|c: char| c.is_ascii_digit())?;
+    let number: String = after[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    number.parse().ok().map(Duration::from_secs_f64)
+}
+
+/// Profile directories of a target holding fingerprints, including cross-compiled ones
+fn profiles(target: &Path) -> Vec<PathBuf> {
+    let subdirs = |dir: &Path| -> Vec<PathBuf> {
+        fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect()
+    };
+    subdirs(target)
+        .into_iter()
+        .flat_map(|dir| {
+            let mut dirs = subdirs(&dir);
+            dirs.push(dir);
+            dirs
+        })
+        .filter(|dir| dir.join(".fingerprint").is_dir())
+        .collect()
+}
+
+/// Build time of a profile estimated from the fingerprints of its units
+fn fingerprint_estimate(profile: &Path) -> Option<Duration> {
+    let units: Vec<Duration> = fs::read_dir(profile.join(".fingerprint"))
+        .ok()?
+        .filter_map(Result::ok)
+        .filter_map(|unit| unit_duration(&unit.path()))
+        .collect();
+    let longest = units.iter().max().copied()?;
+    let cpus = std::thread::available_parallelism().map_or(1, |n| n.get()) as u32;
+    Some((units.iter().sum::<Duration>() / cpus).max(longest))
+}
+
+/// Time from the start of a unit's build to the last file written for it
+fn unit_duration(unit: &Path) -> Option<Duration> {
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    let started = modified(&unit.join(INVOKED_FILE))?;
+    let finished: SystemTime = fs::read_dir(unit)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name() != INVOKED_FILE)
+        .filter_map(|entry| modified(&entry.path()))
+        .max()?;
+    finished.duration_since(started).ok()
+}
+
+/// Formats an estimate for warnings, e.g. "25 min" or "1 h 10 min"
+pub fn format_estimate(duration: Duration) -> String {
+    let minutes = duration.as_secs().div_ceil(60);
+    match minutes {
+        0..=1 => "1 min".to_string(),
+        2..=59 => format!("{} min", minutes),
+        _ if minutes.is_multiple_of(60) => format!("{} h", minutes / 60),
+        _ => format!("{} h {} min", minutes / 60, minutes % 60),
+    }
+}
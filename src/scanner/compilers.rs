@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+/// Which compilers built the artifacts in a target directory
+#[derive(Debug, Clone, Default)]
+pub struct CompilerUsage {
+    /// Version of the compiler cargo last ran in the target, from `.rustc_info.json`
+    pub last_version: Option<String>,
+    /// Number of distinct compilers with artifacts in the target
+    pub compilers: usize,
+    /// Artifacts of compilers older than the installed toolchain
+    pub outdated: Vec<PathBuf>,
+}
+
+/// One compiled unit, as recorded by its fingerprint directory
+struct Unit {
+    /// Profile directory holding `.fingerprint`, `deps` and `build`
+    profile: PathBuf,
+    /// Fingerprint directory name, `<package>-<hash>`
+    name: String,
+    /// Hash of the compiler version that built the unit
+    rustc: u64,
+    /// When the unit was last built
+    built: SystemTime,
+}
+
+/// Groups the artifacts of a target by the compiler that built them
+///
+/// Cargo records a hash of the compiler's version in every fingerprint, so units built
+/// by different compilers can be told apart even though only the last version is
+/// stored in readable form. A group counts as outdated when it is not the one built
+/// most recently and all of it predates the installation of the current toolchain;
+/// a toolchain used alongside it (e.g. nightly next to stable) keeps its artifacts.
+pub fn usage(target: &Path) -> CompilerUsage {
+    let units = units(target);
+    let mut groups: HashMap<u64, Vec<&Unit>> = HashMap::new();
+    for unit in &units {
+        groups.entry(unit.rustc).or_default().push(unit);
+    }
+    let newest = |units: &[&Unit]| units.iter().map(|unit| unit.built).max();
+    let current = groups
+        .iter()
+        .max_by_key(|(_, units)| newest(units))
+        .map(|(rustc, _)| *rustc);
+
+    let outdated_units: Vec<&Unit> = match installed_at() {
+        Some(installed) => groups
+            .iter()
+            .filter(|(rustc, units)| {
+                Some(**rustc) != current && newest(units).is_some_and(|built| built < installed)
+            })
+            .flat_map(|(_, units)| units.iter().copied())
+            .collect(),
+        None => Vec::new(),
+    };
+
+    CompilerUsage {
+        last_version: last_version(target),
+        compilers: groups.len(),
+        outdated: artifacts(&outdated_units),
+    }
+}
+
+/// Fingerprinted units in every profile of a target, including cross-compiled ones
+fn units(target: &Path) -> Vec<Unit> {
+    let mut units = Vec::new();
+    // Profiles sit at target/<profile>, or target/<triple>/<profile> when cross-compiling
+    let mut walker = walkdir::WalkDir::new(target)
+        .min_depth(1)
+        .max_depth(3)
+        .into_iter();
+    while let Some(entry) = walker.next() {
+        let Ok(entry) = entry else { continue };
+        if !entry.file_type().is_dir() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy();
+        if name == ".fingerprint" {
+            let profile = entry.path().parent().unwrap_or(target).to_path_buf();
+            units.extend(
+                fs::read_dir(entry.path())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Result::ok)
+                    .filter_map(|unit| read_unit(&profile, &unit.path())),
+            );
+            walker.skip_current_dir();
+        } else if matches!(
+            name.as_ref(),
+            "deps" | "build" | "incremental" | "examples" | "doc"
+        ) {
+            walker.skip_current_dir();
+        }
+    }
+    units
+}
+
+/// Reads the compiler hash from any of a unit's fingerprint files
+fn read_unit(profile: &Path, dir: &Path) -> Option<Unit> {
+    let rustc = fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|file| file.path().extension().is_some_and(|ext| ext == "json"))
+        .find_map(|file| {
+            let content = fs::read_to_string(file.path()).ok()?;
+            serde_json::from_str::<serde_json::Value>(&content)
+                .ok()?
+                .get("rustc")?
+                .as_u64()
+        })?;
+    Some(Unit {
+        profile: profile.to_path_buf(),
+        name: dir.file_name()?.to_string_lossy().into_owned(),
+        rustc,
+        built: fs::metadata(dir).and_then(|m| m.modified()).ok()?,
+    })
+}
+
+/// Fingerprints, build script outputs and compiled files of the given units
+fn artifacts(units: &[&Unit]) -> Vec<PathBuf> {
+    let mut by_profile: HashMap<&Path, Vec<&Unit>> = HashMap::new();
+    for unit in units {
+        by_profile.entry(&unit.profile).or_default().push(unit);
+    }
+
+    let mut paths = Vec::new();
+    for (profile, units) in by_profile {
+        let mut hashes = Vec::new();
+        for unit in units {
+            paths.push(profile.join(".fingerprint").join(&unit.name));
+            let build = profile.join("build").join(&unit.name);
+            if build.exists() {
+                paths.push(build);
+            }
+            if let Some((_, hash)) = unit.name.rsplit_once('-') {
+                hashes.push(format!("-{}", hash));
+            }
+        }
+        // Compiled files are named `<crate>-<hash>` plus an extension, e.g. `libfoo-1a2b.rlib`
+        for dir in ["deps", "examples"] {
+            for entry in fs::read_dir(profile.join(dir))
+                .into_iter()
+                .flatten()
+                .filter_map(Result::ok)
+            {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let stem = name.split('.').next().unwrap_or(&name);
+                if hashes.iter().any(|hash| stem.ends_with(hash.as_str())) {
+                    paths.push(entry.path());
+                }
+            }
+        }
+    }
+    paths
+}
+
+/// Version of the compiler cargo last ran in a target, e.g. `rustc 1.80.0 (051478957 2024-07-21)`
+fn last_version(target: &Path) -> Option<String> {
+    let content = fs::read_to_string(target.join(".rustc_info.json")).ok()?;
+    let info: serde_json::Value = serde_json::from_str(&content).ok()?;
+    info.get("outputs")?
+        .as_object()?
+        .values()
+        .filter_map(|output| output.get("stdout")?.as_str())
+        .find(|stdout| stdout.contains("\nrelease: "))
+        .and_then(|stdout| stdout.lines().next())
+        .map(str::to_string)
+}
+
+/// When the toolchain `rustc` resolves to was installed, looked up once per run
+fn installed_at() -> Option<SystemTime> {
+    static INSTALLED: OnceLock<Option<SystemTime>> = OnceLock::new();
+    *INSTALLED.get_or_init(|| {
+        let output = Command::new("rustc")
+            .args(["--print", "sysroot"])
+            .output()
+            .ok()?;
+        let sysroot = PathBuf::from(String::from_utf8(output.stdout).ok()?.trim());
+        // rustup writes a manifest per installed component; fall back to the binary itself
+        let manifest = fs::read_dir(sysroot.join("lib").join("rustlib"))
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .find(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with("manifest-rustc-")
+            })
+            .map(|entry| entry.path());
+        let binary = sysroot.join("bin").join("rustc");
+        fs::metadata(manifest.unwrap_or(binary))
+            .and_then(|m| m.modified())
+            .ok()
+    })
+}
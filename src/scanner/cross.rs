@@ -0,0 +1,112 @@
+use std::path::Path;
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+use crate::vfs;
+
+/// Dep-info and build script output files read per profile to tell which tool built it
+const SAMPLED_FILES: usize = 8;
+
+/// Tool that cross-compiled the builds of a target triple instead of plain cargo
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CrossTool {
+    /// cross-rs, building inside a container with the target mounted at `/target`
+    Cross,
+    /// cargo-zigbuild, linking through zig's C compiler
+    Zigbuild,
+}
+
+impl CrossTool {
+    /// Name of the tool as typed on the command line
+    pub fn label(self) -> &'static str {
+        match self {
+            CrossTool::Cross => "cross",
+            CrossTool::Zigbuild => "zigbuild",
+        }
+    }
+}
+
+/// Builds of one target triple made by a cross-compilation tool
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrossBuild {
+    /// Target triple, also the name of its directory in the target
+    pub triple: String,
+    /// Tool the builds were made with
+    pub tool: CrossTool,
+}
+
+/// Finds which tool built the given triples of a target, leaving out plain cargo builds
+///
+/// cross runs cargo in a container where the target is mounted at `/target`, which
+/// the dep-info files keep as the path of every output. cargo-zigbuild leaves no mark
+/// of its own, but the cc crate logs the zig wrapper it was given as the C compiler
+/// in the output of build scripts. Only a few files per profile are read.
+pub fn detect(target: &Path, triples: &[String]) -> Vec<CrossBuild> {
+    triples
+        .iter()
+        .filter_map(|triple| {
+            let dir = target.join(triple);
+            let tool = if sample(&dir, "deps", |content| content.starts_with("/target/")) {
+                CrossTool::Cross
+            } else if sample(&dir, "build", |content| content.contains("zigcc")) {
+                CrossTool::Zigbuild
+            } else {
+                return None;
+            };
+            Some(CrossBuild {
+                triple: triple.clone(),
+                tool,
+            })
+        })
+        .collect()
+}
+
+/// Whether any sampled file in `<profile>/<kind>` of a triple matches
+///
+/// Dep-info files (`.d`) are sampled in `deps`, build script `output` files in `build`.
+fn sample(triple_dir: &Path, kind: &str, matches: impl Fn(&str) -> bool) -> bool {
+    let fs = vfs::current();
+    fs.read_dir(triple_dir)
+        .into_iter()
+        .flatten()
+        .filter(|profile| profile.kind == vfs::EntryKind::Dir)
+        .any(|profile| {
+            let entries = fs.read_dir(&profile.path.join(kind)).unwrap_or_default();
+            let files = entries.into_iter().filter_map(|entry| match kind {
+                "deps" => entry
+                    .path
+                    .extension()
+                    .is_some_and(|ext| ext == "d")
+                    .then_some(entry.path),
+                _ => Some(entry.path.join("output")),
+            });
+            files
+                .take(SAMPLED_FILES)
+                .filter_map(|file| fs.read_to_string(&file).ok())
+                .any(|content| matches(&content))
+        })
+}
+
+/// Docker volumes that cross keeps projects and their targets in for remote builds
+///
+/// These are named `cross-<toolchain>-...` and are only found when `docker` is
+/// installed and its daemon reachable; otherwise there are none to report.
+pub fn volumes() -> Vec<String> {
+    let Ok(output) = Command::new("docker")
+        .args(["volume", "ls", "--quiet", "--filter", "name=cross-"])
+        .output()
+    else {
+        return Vec::new();
+    };
+    if !output.status.success() {
+        return Vec::new();
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|name| name.starts_with("cross-"))
+        .map(str::to_string)
+        .collect()
+}
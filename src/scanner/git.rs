@@ -0,0 +1,111 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Host and owner of the repository a project is checked out from, e.g. `github.com/mycompany`
+///
+/// Reads the `origin` remote (or the first remote) from the git config of the enclosing
+/// repository, following the `.git` files of worktrees and submodules. `None` when the
+/// project is not in a repository or its remote is a local path.
+pub fn remote_owner(project_path: &Path) -> Option<String> {
+    let config = fs::read_to_string(git_dir(project_path)?.join("config")).ok()?;
+    owner(&remote_url(&config)?)
+}
+
+/// Whether tracked files of the project have uncommitted changes
+///
+/// Runs `git status` only inside a repository; a project outside one, or one git
+/// cannot read, counts as clean.
+pub fn is_dirty(project_path: &Path) -> bool {
+    git_dir(project_path).is_some()
+        && Command::new("git")
+            .arg("-C")
+            .arg(project_path)
+            .args(["status", "--porcelain", "--untracked-files=no", "--", "."])
+            .output()
+            .is_ok_and(|output| output.status.success() && !output.stdout.is_empty())
+}
+
+/// When the commit checked out in the project was made
+pub fn last_commit(project_path: &Path) -> Option<SystemTime> {
+    git_dir(project_path)?;
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(project_path)
+        .args(["log", "-1", "--format=%ct"])
+        .output()
+        .ok()?;
+    let seconds = String::from_utf8(output.stdout).ok()?.trim().parse().ok()?;
+    Some(UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+/// Git directory holding the config of the repository containing `path`
+fn git_dir(path: &Path) -> Option<PathBuf> {
+    let dot_git = path
+        .ancestors()
+        .map(|dir| dir.join(".git"))
+        .find(|dot_git| dot_git.exists())?;
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+
+    // Worktrees and submodules have a `.git` file pointing to their git directory
+    let content = fs::read_to_string(&dot_git).ok()?;
+    let target = content.trim().strip_prefix("gitdir:")?.trim();
+    let git_dir = dot_git.parent()?.join(target);
+    // A worktree's own git directory shares the config of the main one
+    match fs::read_to_string(git_dir.join("commondir")) {
+        Ok(common) => Some(git_dir.join(common.trim())),
+        Err(_) => Some(git_dir),
+    }
+}
+
+/// URL of the `origin` remote, or of the first remote when there is no origin
+fn remote_url(config: &str) -> Option<String> {
+    let mut section = String::new();
+    let mut first = None;
+    for line in config.lines().map(str::trim) {
+        if line.starts_with('[') {
+            section = line.to_string();
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if key.trim() != "url" || !section.starts_with("[remote ") {
+            continue;
+        }
+        let url = value.trim().to_string();
+        if section == "[remote \"origin\"]" {
+            return Some(url);
+        }
+        first.get_or_insert(url);
+    }
+    first
+}
+
+/// Lowercased `host/owner` of a remote URL in URL or scp-like (`git@host:owner/repo`) form
+fn owner(url: &str) -> Option<String> {
+    let (host, path) = match url.split_once("://") {
+        Some(("file", _)) => return None,
+        Some((_, rest)) => {
+            let (authority, path) = rest.split_once('/')?;
+            let host = authority.rsplit('@').next()?;
+            (host.split(':').next()?, path)
+        }
+        None => {
+            let (authority, path) = url.split_once(':')?;
+            // A slash before the colon, or a drive letter, makes it a local path
+            if authority.contains('/') || authority.len() == 1 {
+                return None;
+            }
+            (authority.rsplit('@').next()?, path)
+        }
+    };
+    let owner = path.trim_start_matches('/').split('/').next()?;
+    if host.is_empty() || owner.is_empty() {
+        return None;
+    }
+    Some(format!("{}/{}", host, owner).to_lowercase())
+}
@@ -0,0 +1,63 @@
+use std::path::Path;
+
+/// Built-in patterns for directories whose Cargo.toml files are not projects anyone builds,
+/// each with the reason it is skipped
+pub const DEFAULT_SKIP_PATTERNS: &[(&str, &str)] = &[
+    (
+        "*/templates/*",
+        "project templates, e.g. for cargo-generate",
+    ),
+    ("*/.cargo/registry/*", "sources of downloaded crates"),
+    ("*/.cargo/git/*", "checkouts of git dependencies"),
+    ("*/vendor/*", "dependencies copied by cargo vendor"),
+    ("*/target/package/*", "crates unpacked by cargo package"),
+    (
+        "*/target/*/build/*/out/*",
+        "code generated by build scripts",
+    ),
+];
+
+/// Why a skip pattern is active: the built-in reason, or `None` for configured ones
+pub fn reason(pattern: &str) -> Option<&'static str> {
+    DEFAULT_SKIP_PATTERNS
+        .iter()
+        .find(|(default, _)| *default == pattern)
+        .map(|(_, reason)| *reason)
+}
+
+/// Whether a directory matches a skip pattern
+///
+/// `*` matches any run of characters, including separators, and `?` a single one. The
+/// directory is matched with a trailing `/`, so `*/templates/*` also skips `templates`
+/// itself and the walk never descends into it.
+pub fn matches(pattern: &str, dir: &Path) -> bool {
+    let path = format!("{}/", dir.to_string_lossy().replace('\\', "/"));
+    glob(pattern.as_bytes(), path.as_bytes())
+}
+
+fn glob(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text position it currently covers up to
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p + 1, t));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_t)) => {
+                    p = star_p;
+                    t = star_t + 1;
+                    backtrack = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
@@ -1,3 +1,12 @@
+pub mod build_time;
+pub mod compilers;
+pub mod cross;
+pub mod git;
+pub mod heuristics;
 pub mod rust_project;
 pub mod rust_project_scaner;
 pub mod target_finder;
+pub mod traversal;
+pub mod users;
+pub mod watcher;
+pub mod wsl;
@@ -1,8 +1,14 @@
-use crate::scanner::target_finder::TargetInfo;
+use crate::cache::audit::CleanupHistory;
+use crate::config::Config;
+use crate::scanner::git;
+use crate::scanner::target_finder::{Availability, TargetFinder, TargetInfo};
+use crate::vfs;
+use serde::{Deserialize, Serialize};
 use std::error::Error;
+use std::fs;
 use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RustProject {
     /// Path to the project directory (containing Cargo.toml)
     pub path: PathBuf,
@@ -10,38 +16,316 @@ pub struct RustProject {
     pub name: String,
     /// Information about the target directory
     pub target_info: Option<TargetInfo>,
+    /// SSH destination of the host the project lives on, for remote projects
+    #[serde(skip)]
+    pub host: Option<String>,
+    /// Directory with container configuration (devcontainer, compose) the project lives under
+    #[serde(default)]
+    pub container_root: Option<PathBuf>,
+    /// User-assigned tags, loaded from the tag store in the cache directory
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Previous cleanups of the project, loaded from the audit log
+    #[serde(default)]
+    pub history: Option<CleanupHistory>,
+    /// Crates of a virtual workspace, which all build into its target directory
+    #[serde(default)]
+    pub members: Vec<String>,
+    /// Host and owner of the project's git remote, e.g. `github.com/mycompany`
+    #[serde(default)]
+    pub remote: Option<String>,
+    /// Whether this is a target directory left behind without a Cargo.toml next to it
+    #[serde(default)]
+    pub orphan: bool,
+    /// Whether the last build succeeded, as marked by the user or a build hook
+    #[serde(default)]
+    pub build_health: Option<BuildHealth>,
 }
 
+/// Outcome of a project's last build, recorded with `clear-target mark`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BuildHealth {
+    Passing,
+    Broken,
+}
+
+impl BuildHealth {
+    /// Name used in the tag store, selections and listings
+    pub fn label(self) -> &'static str {
+        match self {
+            BuildHealth::Passing => "passing",
+            BuildHealth::Broken => "broken",
+        }
+    }
+}
+
+/// Files and directories indicating a directory is bind-mounted into containers
+const CONTAINER_MARKERS: &[&str] = &[
+    ".devcontainer",
+    "docker-compose.yml",
+    "docker-compose.yaml",
+    "compose.yml",
+    "compose.yaml",
+    "Dockerfile",
+];
+
+/// Files in a project opting its target out of cleaning; the first is created by the UI
+pub const KEEP_MARKERS: &[&str] = &[".keep-target", ".no-clean"];
+
 impl RustProject {
     /// Creates a RustProject from a directory path containing Cargo.toml
     pub fn from_path(path: &Path) -> Result<Self, Box<dyn Error>> {
-        if !path.exists() {
+        let fs = vfs::current();
+        if !fs.exists(path) {
             return Err(format!("Project path does not exist: {:?}", path).into());
         }
 
         let cargo_toml = path.join("Cargo.toml");
-        if !cargo_toml.exists() {
+        if !fs.exists(&cargo_toml) {
             return Err(format!("Cargo.toml not found in: {:?}", path).into());
         }
 
-        let name = Self::extract_project_name(&cargo_toml)?;
+        let manifest: Option<toml::Table> = fs
+            .read_to_string(&cargo_toml)
+            .ok()
+            .and_then(|content| content.parse().ok());
+        let virtual_workspace = manifest
+            .as_ref()
+            .is_some_and(|m| !m.contains_key("package") && m.contains_key("workspace"));
+
+        let (name, members) = if virtual_workspace {
+            let dir_name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.display().to_string());
+            (
+                format!("workspace: {}", dir_name),
+                manifest
+                    .as_ref()
+                    .map(|m| Self::workspace_member_names(path, m))
+                    .unwrap_or_default(),
+            )
+        } else {
+            (Self::extract_project_name(&cargo_toml)?, Vec::new())
+        };
 
         Ok(Self {
             path: path.to_path_buf(),
             name,
             target_info: None,
+            host: None,
+            container_root: Self::find_container_root(path),
+            tags: Vec::new(),
+            history: None,
+            members,
+            remote: git::remote_owner(path),
+            orphan: false,
+            build_health: None,
         })
     }
 
+    /// Creates a project for a target directory whose project is gone
+    ///
+    /// The project path is the directory the target was left in.
+    pub fn orphan(target_path: &Path) -> Self {
+        let path = target_path.parent().unwrap_or(target_path).to_path_buf();
+        let dir_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        Self {
+            name: format!("orphan: {}", dir_name),
+            target_info: None,
+            host: None,
+            container_root: None,
+            tags: Vec::new(),
+            history: None,
+            members: Vec::new(),
+            remote: git::remote_owner(&path),
+            orphan: true,
+            build_health: None,
+            path,
+        }
+    }
+
+    /// Finds the root of the project (or enclosing workspace) containing `start`
+    pub fn find_root(start: &Path) -> Option<PathBuf> {
+        let mut nearest = None;
+
+        for dir in start.ancestors() {
+            let cargo_toml = dir.join("Cargo.toml");
+            if !cargo_toml.is_file() {
+                continue;
+            }
+
+            if nearest.is_none() {
+                nearest = Some(dir.to_path_buf());
+            }
+
+            // A workspace root owns the shared target directory of its members
+            let is_workspace = std::fs::read_to_string(&cargo_toml)
+                .map(|content| content.lines().any(|line| line.trim() == "[workspace]"))
+                .unwrap_or(false);
+            if is_workspace {
+                return Some(dir.to_path_buf());
+            }
+        }
+
+        nearest
+    }
+
+    /// Canonical paths of the project this binary was built from and the one it runs in
+    pub fn own_projects() -> Vec<PathBuf> {
+        let from_cwd = std::env::current_dir()
+            .ok()
+            .and_then(|cwd| Self::find_root(&cwd))
+            .and_then(|project| fs::canonicalize(project).ok());
+        let mut projects = Self::source_projects();
+        if let Some(project) = from_cwd
+            && !projects.contains(&project)
+        {
+            projects.push(project);
+        }
+        projects
+    }
+
+    /// Canonical paths of the project this binary was built from
+    ///
+    /// A binary run from `<project>/target/<profile>/` belongs to that project; the
+    /// manifest directory recorded at build time covers `cargo install --path`.
+    pub fn source_projects() -> Vec<PathBuf> {
+        let from_exe = std::env::current_exe()
+            .ok()
+            .and_then(|exe| fs::canonicalize(exe).ok())
+            .and_then(|exe| {
+                exe.ancestors()
+                    .find(|dir| dir.file_name().is_some_and(|name| name == "target"))
+                    .and_then(Path::parent)
+                    .filter(|project| project.join("Cargo.toml").is_file())
+                    .map(Path::to_path_buf)
+            });
+        let from_build = Self::find_root(Path::new(env!("CARGO_MANIFEST_DIR")));
+
+        let mut projects: Vec<PathBuf> = [from_exe, from_build]
+            .into_iter()
+            .flatten()
+            .filter_map(|project| fs::canonicalize(project).ok())
+            .collect();
+        projects.dedup();
+        projects
+    }
+
+    /// Finds the nearest directory at or above the project that has container configuration
+    fn find_container_root(path: &Path) -> Option<PathBuf> {
+        let home = dirs::home_dir();
+
+        for dir in path.ancestors() {
+            if CONTAINER_MARKERS
+                .iter()
+                .any(|marker| dir.join(marker).exists())
+            {
+                return Some(dir.to_path_buf());
+            }
+
+            // Container configuration above the home directory is not about this project
+            if home.as_deref() == Some(dir) {
+                break;
+            }
+        }
+
+        None
+    }
+
+    /// Package names of a workspace's members, sorted
+    ///
+    /// Member entries may end in `*` to include every crate directory below a prefix,
+    /// which covers the usual `crates/*` layout.
+    fn workspace_member_names(root: &Path, manifest: &toml::Table) -> Vec<String> {
+        let patterns = manifest
+            .get("workspace")
+            .and_then(|workspace| workspace.get("members"))
+            .and_then(|members| members.as_array())
+            .map(|members| {
+                members
+                    .iter()
+                    .filter_map(|m| m.as_str())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        let mut member_dirs = Vec::new();
+        for pattern in patterns {
+            match pattern.strip_suffix('*') {
+                Some(prefix) => {
+                    let Ok(entries) = vfs::current().read_dir(&root.join(prefix)) else {
+                        continue;
+                    };
+                    member_dirs.extend(entries.into_iter().map(|entry| entry.path));
+                }
+                None => member_dirs.push(root.join(pattern)),
+            }
+        }
+
+        let mut names: Vec<String> = member_dirs
+            .iter()
+            .map(|dir| dir.join("Cargo.toml"))
+            .filter(|cargo_toml| vfs::current().is_file(cargo_toml))
+            .filter_map(|cargo_toml| Self::extract_project_name(&cargo_toml).ok())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Returns the project path, prefixed with its host for remote projects
+    pub fn display_path(&self) -> String {
+        match &self.host {
+            Some(host) => format!("{}:{}", host, self.path.display()),
+            None => self.path.display().to_string(),
+        }
+    }
+
     /// Adds target information to the project
     pub fn with_target_info(mut self, target_info: TargetInfo) -> Self {
         self.target_info = Some(target_info);
         self
     }
 
+    /// Marker file through which a local project opted out of cleaning, if it has one
+    pub fn keep_marker(&self) -> Option<PathBuf> {
+        if self.host.is_some() {
+            return None;
+        }
+        KEEP_MARKERS
+            .iter()
+            .map(|marker| self.path.join(marker))
+            .find(|path| vfs::current().exists(path))
+    }
+
+    /// Fills in whether the project's target is stale and whether it can be cleaned
+    pub fn update_status(&mut self, config: &Config) -> Result<(), Box<dyn Error>> {
+        let threshold = config.stale_threshold_for(self);
+        let protected = config.is_protected(self);
+        let kept = self.keep_marker().is_some();
+        if let Some(target_info) = self.target_info.as_mut() {
+            TargetFinder::apply_metric(target_info, config.staleness_metric);
+            TargetFinder::update_stale_status(target_info, threshold)?;
+            if self.host.is_none() {
+                TargetFinder::update_availability(target_info, config.symlinked_targets);
+            }
+            if protected && target_info.availability == Availability::Available {
+                target_info.availability = Availability::Protected;
+            }
+            if kept && target_info.availability == Availability::Available {
+                target_info.availability = Availability::Kept;
+            }
+        }
+        Ok(())
+    }
+
     /// Extracts the project name from Cargo.toml
     fn extract_project_name(cargo_toml: &Path) -> Result<String, Box<dyn Error>> {
-        let content = std::fs::read_to_string(cargo_toml)?;
+        let content = vfs::current().read_to_string(cargo_toml)?;
 
         // Simple parsing to extract the name from [package] section
         // This is a basic implementation - in a real scenario, you'd use toml crate
@@ -61,21 +345,21 @@ impl RustProject {
                 continue;
             }
 
-            if in_package && trimmed.starts_with("name") {
-                if let Some(name_part) = trimmed.split('=').nth(1) {
-                    let name = name_part.trim().trim_matches('"').trim_matches('\'');
-                    return Ok(name.to_string());
-                }
+            if in_package
+                && trimmed.starts_with("name")
+                && let Some(name_part) = trimmed.split('=').nth(1)
+            {
+                let name = name_part.trim().trim_matches('"').trim_matches('\'');
+                return Ok(name.to_string());
             }
         }
 
         // Fallback to directory name if name not found
-        if let Some(parent) = cargo_toml.parent() {
-            if let Some(dir_name) = parent.file_name() {
-                if let Some(name_str) = dir_name.to_str() {
-                    return Ok(name_str.to_string());
-                }
-            }
+        if let Some(parent) = cargo_toml.parent()
+            && let Some(dir_name) = parent.file_name()
+            && let Some(name_str) = dir_name.to_str()
+        {
+            return Ok(name_str.to_string());
         }
 
         Err("Could not determine project name".into())
@@ -84,6 +368,6 @@ impl RustProject {
     /// Returns the path to the project's target directory
     #[allow(dead_code)]
     pub fn target_path(&self) -> Option<PathBuf> {
-        self.path.join("target").into()
+        Some(TargetFinder::resolve_target_dir(&self.path))
     }
 }
@@ -1,15 +1,43 @@
 use std::{
+    collections::HashSet,
     error::Error,
-    io::Write,
     path::{Path, PathBuf},
+    sync::{Arc, Mutex},
 };
 
+use crate::progress::{ProgressSink, Stage};
+use crate::scanner::heuristics;
+use crate::scanner::traversal::{self, TraversalRecorder, TraversalStat};
 use crate::scanner::{rust_project::RustProject, target_finder::TargetFinder};
+use crate::tasks::{CancelToken, Cancelled};
+use crate::vfs::{self, EntryKind, Walk, WalkEntry, WalkError};
 
+/// Why a directory holding a Cargo.toml is missing from the scan results
+#[derive(Debug, Clone)]
+pub struct ScanDiagnostic {
+    /// Directory holding the Cargo.toml
+    pub path: PathBuf,
+    /// What went wrong reading the project or its target
+    pub reason: String,
+}
+
+#[derive(Clone)]
 pub struct RustProjectScanner {
     search_paths: Vec<PathBuf>,
     exclude_patterns: Vec<String>,
     ignore_paths: Vec<PathBuf>,
+    /// Glob patterns of directories whose projects are not worth listing
+    skip_patterns: Vec<String>,
+    /// Whether to descend into symlinked directories
+    follow_links: bool,
+    /// Whether to look for target directories left behind without a Cargo.toml
+    orphans: bool,
+    /// Time spent in each top-level directory during the last scan, shared between clones
+    traversal: Arc<Mutex<Vec<TraversalStat>>>,
+    /// Directories the last scan could not read for lack of permission, shared between clones
+    denied: Arc<Mutex<Vec<PathBuf>>>,
+    /// What the last scan has to tell the user, shared between clones
+    notes: Arc<Mutex<Vec<String>>>,
 }
 
 impl RustProjectScanner {
@@ -30,7 +58,7 @@ impl RustProjectScanner {
     ) -> Result<Self, Box<dyn Error>> {
         // Validate search paths exist
         for path in search_paths {
-            if !path.exists() {
+            if !vfs::current().exists(path) {
                 return Err(format!("Search path does not exist: {:?}", path).into());
             }
         }
@@ -39,94 +67,267 @@ impl RustProjectScanner {
             search_paths: search_paths.to_vec(),
             exclude_patterns: exclude_patterns.to_vec(),
             ignore_paths: ignore_paths.to_vec(),
+            skip_patterns: Vec::new(),
+            follow_links: false,
+            orphans: false,
+            traversal: Arc::default(),
+            denied: Arc::default(),
+            notes: Arc::default(),
         })
     }
 
+    /// Sets whether symlinked directories are followed while scanning
+    pub fn with_follow_links(mut self, follow_links: bool) -> Self {
+        self.follow_links = follow_links;
+        self
+    }
+
+    /// Sets whether target directories without a Cargo.toml next to them are reported
+    pub fn with_orphans(mut self, orphans: bool) -> Self {
+        self.orphans = orphans;
+        self
+    }
+
+    /// Sets the glob patterns of directories to skip, such as project templates
+    pub fn with_skip_patterns(mut self, patterns: &[String]) -> Self {
+        self.skip_patterns = patterns.to_vec();
+        self
+    }
+
     /// Scans all configured paths for Rust projects with target directories
-    pub fn find_projects(&self) -> Result<Vec<RustProject>, Box<dyn Error>> {
+    pub fn find_projects(
+        &self,
+        progress: &mut dyn ProgressSink,
+    ) -> Result<Vec<RustProject>, Box<dyn Error>> {
+        let project_dirs = self
+            .find_project_dirs(&CancelToken::default())
+            .map_err(|e| e as Box<dyn Error>)?;
+        let total = project_dirs.len();
         let mut projects = Vec::new();
+        for (done, dir) in project_dirs.into_iter().enumerate() {
+            let project = Self::analyze(dir).ok();
+            let item = project.as_ref().map(|project| project.path.as_path());
+            progress.advance(Stage::Sizing, done + 1, total, item);
+            projects.extend(project);
+        }
+        Ok(projects)
+    }
+
+    /// Whether a scan walks into a directory: it is under a search path and no
+    /// exclude, ignore or skip rule applies to it
+    pub fn covers(&self, path: &Path) -> bool {
+        let under_root = self.search_paths.iter().any(|root| {
+            path.starts_with(
+                vfs::current()
+                    .canonicalize(root)
+                    .unwrap_or_else(|_| root.clone()),
+            )
+        });
+        under_root
+            && !is_excluded(path, &self.exclude_patterns)
+            && !self.is_ignored_path(path)
+            && !self
+                .skip_patterns
+                .iter()
+                .any(|pattern| heuristics::matches(pattern, path))
+    }
+
+    /// Directories from the last scan that were slow to walk and held no projects
+    pub fn exclude_suggestions(&self) -> Vec<TraversalStat> {
+        traversal::exclude_suggestions(&self.traversal.lock().unwrap())
+    }
+
+    /// Directories skipped during the last scan because reading them was not permitted
+    pub fn denied_paths(&self) -> Vec<PathBuf> {
+        self.denied.lock().unwrap().clone()
+    }
+
+    /// What the last scan found worth telling the user, leaving none behind
+    pub fn take_notes(&self) -> Vec<String> {
+        std::mem::take(&mut self.notes.lock().unwrap())
+    }
+
+    fn note(&self, message: String) {
+        self.notes.lock().unwrap().push(message);
+    }
 
-        // Filter out paths that should be ignored
-        let filtered_paths: Vec<&PathBuf> = self
+    /// Analyzes a directory containing a Cargo.toml, with its target directory if it has one
+    ///
+    /// An orphaned target directory found by the scan is analyzed as a project of its own.
+    /// Fails with a diagnostic when the manifest or the target cannot be read.
+    pub fn analyze(project_path: PathBuf) -> Result<RustProject, ScanDiagnostic> {
+        if !vfs::current().exists(&project_path.join("Cargo.toml"))
+            && TargetFinder::is_cargo_target(&project_path)
+        {
+            let project = RustProject::orphan(&project_path);
+            return match TargetFinder::target_info_at(&project.path, project_path.clone()) {
+                Ok(Some(target_info)) => Ok(project.with_target_info(target_info)),
+                Ok(None) | Err(_) => Err(ScanDiagnostic {
+                    path: project_path,
+                    reason: "cannot read orphaned target directory".to_string(),
+                }),
+            };
+        }
+        let diagnostic = |reason: String| ScanDiagnostic {
+            path: project_path.clone(),
+            reason,
+        };
+        let project = RustProject::from_path(&project_path)
+            .map_err(|e| diagnostic(format!("cannot read Cargo.toml: {}", e)))?;
+        match TargetFinder::find_target_info(&project_path) {
+            Ok(Some(target_info)) => Ok(project.with_target_info(target_info)),
+            Ok(None) => Ok(project),
+            Err(e) => Err(diagnostic(format!("cannot read target directory: {}", e))),
+        }
+    }
+
+    /// Walks all configured paths for directories containing a Cargo.toml
+    ///
+    /// With orphans enabled, target directories no project builds into follow them.
+    /// Sizing is left to [`Self::analyze`], so callers can run it concurrently.
+    pub fn find_project_dirs(
+        &self,
+        cancel: &CancelToken,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error + Send + Sync>> {
+        let mut project_dirs = Vec::new();
+        self.traversal.lock().unwrap().clear();
+        self.denied.lock().unwrap().clear();
+        self.notes.lock().unwrap().clear();
+
+        // Search paths that are themselves ignored are not walked at all
+        for path in self
             .search_paths
             .iter()
             .filter(|path| !self.is_ignored_path(path))
-            .collect();
-
-        println!(
-            "Searching in {} directories ({} ignored)...",
-            filtered_paths.len(),
-            self.search_paths.len() - filtered_paths.len()
-        );
+        {
+            project_dirs.extend(self.scan_path(path, cancel)?);
+        }
 
-        for (i, path) in filtered_paths.iter().enumerate() {
-            // Check if this search path should be ignored
-            if self.is_ignored_path(path) {
-                println!("Skipping ignored path: {}", path.display());
-                continue;
-            }
+        // Overlapping search paths and symlinks can lead to the same project twice;
+        // keep the real path over an alias, and otherwise the first one found
+        let fs = vfs::current();
+        let found = project_dirs.len();
+        let canonical: Vec<PathBuf> = project_dirs
+            .iter()
+            .map(|dir| fs.canonicalize(dir).unwrap_or_else(|_| dir.clone()))
+            .collect();
+        let real: HashSet<&PathBuf> = project_dirs
+            .iter()
+            .zip(&canonical)
+            .filter(|(dir, canonical)| dir == canonical)
+            .map(|(_, canonical)| canonical)
+            .collect();
+        let mut seen = HashSet::new();
+        let mut canonical = canonical.iter();
+        project_dirs.retain(|dir| {
+            let canonical = canonical.next().unwrap_or(dir);
+            (dir == canonical || !real.contains(canonical)) && seen.insert(canonical.clone())
+        });
+        if project_dirs.len() < found {
+            self.note(format!(
+                "Skipped {} projects reached through more than one path",
+                found - project_dirs.len()
+            ));
+        }
 
-            println!(
-                "Scanning {}/{}: {}",
-                i + 1,
-                filtered_paths.len(),
-                path.display()
-            );
-            let found_projects = self.scan_path(path)?;
-            println!(
-                "Found {} Rust projects in {}",
-                found_projects.len(),
-                path.display()
-            );
-            projects.extend(found_projects);
+        if self.orphans {
+            // Targets of projects configured with build.target-dir elsewhere are not orphans
+            let (mut projects, orphans): (Vec<PathBuf>, Vec<PathBuf>) = project_dirs
+                .into_iter()
+                .partition(|dir| fs.exists(&dir.join("Cargo.toml")));
+            let claimed: HashSet<PathBuf> = projects
+                .iter()
+                .map(|dir| TargetFinder::resolve_target_dir(dir))
+                .map(|target| fs.canonicalize(&target).unwrap_or(target))
+                .collect();
+            let orphans: Vec<PathBuf> = orphans
+                .into_iter()
+                .filter(|dir| !claimed.contains(&fs.canonicalize(dir).unwrap_or(dir.clone())))
+                .collect();
+            self.note(format!(
+                "Found {} orphaned target directories",
+                orphans.len()
+            ));
+            projects.extend(orphans);
+            project_dirs = projects;
         }
 
-        Ok(projects)
+        Ok(project_dirs)
     }
 
-    /// Scans a single path for Rust projects
-    fn scan_path(&self, path: &Path) -> Result<Vec<RustProject>, Box<dyn Error>> {
-        let mut projects = Vec::new();
+    /// Scans a single path for directories containing a Cargo.toml
+    fn scan_path(
+        &self,
+        path: &Path,
+        cancel: &CancelToken,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error + Send + Sync>> {
+        let mut project_dirs = Vec::new();
         let mut directories_scanned = 0;
         let mut cargo_files_found = 0;
+        let mut recorder = TraversalRecorder::new();
 
-        // Use walkdir to traverse the directory tree
-        for entry in walkdir::WalkDir::new(path)
-            .into_iter()
-            .filter_entry(|e| {
-                !is_excluded(e.path(), &self.exclude_patterns) && !self.is_ignored_path(e.path())
-            })
-            .filter_map(Result::ok)
-        {
+        let fs = vfs::current();
+        let mut walker = Walk::new(fs, path).follow_links(self.follow_links);
+        while let Some(entry) = walker.next() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                // Only reachable when following links; the walk refuses to descend again
+                Err(WalkError::Loop { path, ancestor }) => {
+                    self.note(format!(
+                        "Skipped symlink loop: {} points back to {}",
+                        path.display(),
+                        ancestor.display()
+                    ));
+                    continue;
+                }
+                Err(WalkError::Io { path, error }) => {
+                    if error.kind() == std::io::ErrorKind::PermissionDenied {
+                        self.denied.lock().unwrap().push(path);
+                    }
+                    continue;
+                }
+            };
+            if is_excluded(&entry.path, &self.exclude_patterns)
+                || self.is_ignored_path(&entry.path)
+                || self.is_skipped(&entry)
+            {
+                walker.skip_current_dir();
+                continue;
+            }
+            if cancel.is_cancelled() {
+                return Err(Cancelled.into());
+            }
             directories_scanned += 1;
 
-            // Show progress for every 1000 directories scanned
-            if directories_scanned % 1000 == 0 {
-                print!(".");
-                std::io::stdout().flush().unwrap();
+            let is_manifest = entry.path.file_name() == Some("Cargo.toml".as_ref());
+            recorder.visit(&entry, is_manifest);
+            if self.orphans
+                && entry.kind == EntryKind::Dir
+                && entry
+                    .path
+                    .parent()
+                    .is_some_and(|parent| !fs.exists(&parent.join("Cargo.toml")))
+                && TargetFinder::is_cargo_target(&entry.path)
+            {
+                project_dirs.push(entry.path.clone());
             }
-
-            if entry.file_name() == "Cargo.toml" {
+            if is_manifest {
                 cargo_files_found += 1;
-                let cargo_path = entry.path();
+                let cargo_path = entry.path.as_path();
                 let project_path = cargo_path.parent().unwrap_or(cargo_path);
-
-                if let Ok(project) = RustProject::from_path(project_path) {
-                    if let Ok(target_info) = TargetFinder::find_target_info(project_path) {
-                        let project_with_target = project.with_target_info(target_info);
-                        projects.push(project_with_target);
-                    }
-                }
+                project_dirs.push(project_path.to_path_buf());
             }
         }
 
-        println!();
-        println!(
-            "Scanned {} directories, found {} Cargo.toml files",
-            directories_scanned, cargo_files_found
-        );
+        self.note(format!(
+            "Scanned {} directories in {}, found {} Cargo.toml files",
+            directories_scanned,
+            path.display(),
+            cargo_files_found
+        ));
+        self.traversal.lock().unwrap().extend(recorder.finish());
 
-        Ok(projects)
+        Ok(project_dirs)
     }
 }
 
@@ -144,6 +345,15 @@ fn is_excluded(path: &Path, patterns: &[String]) -> bool {
 }
 
 impl RustProjectScanner {
+    /// Checks if a directory matches one of the skip patterns
+    fn is_skipped(&self, entry: &WalkEntry) -> bool {
+        entry.kind == EntryKind::Dir
+            && self
+                .skip_patterns
+                .iter()
+                .any(|pattern| heuristics::matches(pattern, &entry.path))
+    }
+
     /// Checks if a path should be ignored based on the ignore_paths list
     fn is_ignored_path(&self, path: &Path) -> bool {
         // Check if path is exactly in the ignore list
@@ -170,7 +380,7 @@ impl RustProjectScanner {
 
             // Also check if normalized path starts with normalized ignore
             // and either they are equal or next character is a separator
-            if normalized_path.starts_with(&normalized_ignore.as_ref()) {
+            if normalized_path.starts_with(normalized_ignore.as_ref()) {
                 if normalized_path.len() == normalized_ignore.len() {
                     return true; // Exact match
                 }
@@ -1,19 +1,334 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::error::Error;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, SystemTime};
 
+use crate::cache::sizes;
+use crate::config::{StalenessMetric, SymlinkPolicy};
+use crate::scanner::cross::{self, CrossBuild};
+use crate::size::format_bytes_compact;
+use crate::timing::{self, Phase};
+use crate::vfs::{self, EntryKind, Walk};
+
 /// Information about a target directory
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TargetInfo {
     /// Path to the target directory
     pub path: PathBuf,
     /// Total size in bytes
     pub size_bytes: u64,
-    /// Last modification time (more reliable than access time)
+    /// When the target was last used: `last_built`, or the newest artifact access time
+    /// under the atime metric. Drives the age shown and staleness
     pub last_accessed: SystemTime,
+    /// Newest modification of the target's build outputs, i.e. when it was last built
+    #[serde(default)]
+    pub last_built: Option<SystemTime>,
+    /// Oldest modification among a sample of the target's files
+    #[serde(default)]
+    pub oldest_artifact: Option<SystemTime>,
     /// Whether the directory is considered stale (not accessed for a while)
     pub is_stale: bool,
+    /// Owner uid of files not owned by the project's owner (e.g. root from a container build)
+    #[serde(default)]
+    pub foreign_owner: Option<u32>,
+    /// Resolved location when the target directory is a symlink
+    #[serde(default)]
+    pub link_target: Option<PathBuf>,
+    /// Device of the target when it lives on a different one than its project
+    /// (a symlink to another drive, or a bind mount)
+    #[serde(default)]
+    pub device: Option<u64>,
+    /// Whether the directory can currently be cleaned
+    #[serde(skip)]
+    pub availability: Availability,
+    /// How the size splits between profiles and generated docs
+    #[serde(default)]
+    pub breakdown: SizeBreakdown,
+    /// Directory inside the target that rust-analyzer builds into, if it has one
+    #[serde(default)]
+    pub rust_analyzer: Option<PathBuf>,
+    /// Newest modification of the project's sources or Cargo.lock, if it has any
+    #[serde(default)]
+    pub last_edited: Option<SystemTime>,
+    /// Which timestamps `last_accessed` was taken from
+    #[serde(default)]
+    pub metric: StalenessMetric,
+    /// Cross-compilation targets built into the directory, e.g. `wasm32-unknown-unknown`
+    #[serde(default)]
+    pub triples: Vec<String>,
+    /// Triples among `triples` built with cross or cargo-zigbuild rather than cargo
+    #[serde(default)]
+    pub cross_builds: Vec<CrossBuild>,
+    /// Web build outputs next to the target, from trunk (`dist`) or wasm-pack (`pkg`)
+    #[serde(default)]
+    pub web_outputs: Vec<PathBuf>,
+    /// Combined size of `web_outputs`, not included in `size_bytes`
+    #[serde(default)]
+    pub web_size: u64,
+    /// Embedded toolchain directories next to the target: `.embuild` and ESP-IDF's `build`
+    #[serde(default)]
+    pub embedded_dirs: Vec<PathBuf>,
+    /// Combined size of `embedded_dirs`, not included in `size_bytes`
+    #[serde(default)]
+    pub embedded_size: u64,
+    /// Profiler output left in the project by cargo-flamegraph or perf
+    #[serde(default)]
+    pub profiling_files: Vec<PathBuf>,
+    /// Combined size of `profiling_files`, not included in `size_bytes`
+    #[serde(default)]
+    pub profiling_size: u64,
+}
+
+/// Size of a target directory split by what produced it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SizeBreakdown {
+    /// Debug profile builds (`debug`, also under target triples)
+    pub debug: u64,
+    /// Release profile builds (`release`, also under target triples)
+    pub release: u64,
+    /// Generated rustdoc output (`doc`)
+    pub doc: u64,
+    /// rust-analyzer's own build directory, often a duplicate of a debug build
+    #[serde(default)]
+    pub rust_analyzer: u64,
+    /// Benchmark results and reports of criterion (`criterion`)
+    #[serde(default)]
+    pub criterion: u64,
+    /// Everything else: custom profiles, package builds, tool caches
+    pub other: u64,
+    /// Compiled dependencies (`deps`) over all profiles, included in the profile sizes
+    #[serde(default)]
+    pub deps: u64,
+    /// Build script outputs (`build`) over all profiles, included in the profile sizes
+    #[serde(default)]
+    pub build: u64,
+    /// Cargo's freshness records (`.fingerprint`) over all profiles, included in the profile sizes
+    #[serde(default)]
+    pub fingerprint: u64,
+    /// Files in the target, including those only counted while sampling
+    #[serde(default)]
+    pub files: u64,
+    /// Files whose size was read; fewer than `files` when the size is an estimate
+    #[serde(default)]
+    pub files_measured: u64,
+}
+
+impl SizeBreakdown {
+    /// Total size over all parts
+    pub fn total(&self) -> u64 {
+        self.debug + self.release + self.doc + self.rust_analyzer + self.criterion + self.other
+    }
+
+    /// Whether the size was extrapolated from a sample of the files instead of measured
+    pub fn is_estimated(&self) -> bool {
+        self.files_measured < self.files
+    }
+
+    /// How the size was estimated, e.g. "estimated from 10000 of 84210 files (12%)"
+    pub fn estimate_note(&self) -> Option<String> {
+        self.is_estimated().then(|| {
+            format!(
+                "estimated from {} of {} files ({}%)",
+                self.files_measured,
+                self.files,
+                self.files_measured * 100 / self.files
+            )
+        })
+    }
+
+    /// Adds the size of a directory entry to the part its name belongs to
+    fn add(&mut self, name: &str, size: Measured) {
+        match name {
+            "debug" => self.debug += size.bytes,
+            "release" => self.release += size.bytes,
+            "doc" => self.doc += size.bytes,
+            CRITERION_DIR => self.criterion += size.bytes,
+            _ => self.other += size.bytes,
+        }
+        self.count(size);
+    }
+
+    /// Counts the files behind a size added to any part
+    fn count(&mut self, size: Measured) {
+        self.files += size.files;
+        self.files_measured += size.measured;
+    }
+
+    /// Adds a profile directory's entries to the profile and to the artifact kinds inside it
+    fn add_profile(
+        &mut self,
+        profile: &str,
+        path: &Path,
+        exact: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        for entry in vfs::current().read_dir(path)? {
+            let size = TargetFinder::measure_path(&entry.path, exact);
+            self.add(profile, size);
+            match entry.name().as_str() {
+                "deps" => self.deps += size.bytes,
+                "build" => self.build += size.bytes,
+                ".fingerprint" => self.fingerprint += size.bytes,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Summary of the artifact kinds inside the profiles, e.g. "deps 1.9G · build scripts 40M"
+    pub fn artifacts(&self) -> String {
+        let parts = [
+            (self.deps, "deps"),
+            (self.build, "build scripts"),
+            (self.fingerprint, "fingerprints"),
+        ];
+        parts
+            .iter()
+            .filter(|(size, _)| *size > 0)
+            .map(|(size, label)| format!("{} {}", label, format_bytes_compact(*size)))
+            .collect::<Vec<_>>()
+            .join(" · ")
+    }
+
+    /// Compact summary of the main parts, e.g. "2.1G dbg / 800M rel"
+    pub fn compact(&self) -> String {
+        let parts = [
+            (self.debug, "dbg"),
+            (self.release, "rel"),
+            (self.doc, "doc"),
+            (self.rust_analyzer, "ra"),
+            (self.criterion, "bench"),
+        ];
+        parts
+            .iter()
+            .filter(|(size, _)| *size > 0)
+            .map(|(size, label)| format!("{} {}", format_bytes_compact(*size), label))
+            .collect::<Vec<_>>()
+            .join(" / ")
+    }
+}
+
+/// Whether a previously scanned target directory can currently be cleaned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Availability {
+    /// The directory exists on a writable filesystem
+    #[default]
+    Available,
+    /// The directory is gone, e.g. because its filesystem is no longer mounted
+    Missing,
+    /// The directory lives on a read-only filesystem
+    ReadOnly,
+    /// The directory is a symlink and the symlink policy says to leave it alone
+    Symlinked,
+    /// The project is the one this tool was built from or is running in
+    Protected,
+    /// The project opted out of cleaning with a marker file
+    Kept,
+}
+
+impl Availability {
+    /// Human-readable reason why the directory cannot be cleaned
+    pub fn reason(self) -> Option<&'static str> {
+        match self {
+            Availability::Available => None,
+            Availability::Missing => Some("not found (unmounted or removed)"),
+            Availability::ReadOnly => Some("read-only filesystem"),
+            Availability::Symlinked => Some("a symlink (see scan.symlinked_targets)"),
+            Availability::Protected => {
+                Some("in use by this tool (see settings.protect_own_projects)")
+            }
+            Availability::Kept => Some("kept by a .keep-target or .no-clean file in the project"),
+        }
+    }
+}
+
+/// A top-level entry inside a target directory (e.g. `debug`, `release`, `doc`)
+#[derive(Debug, Clone)]
+pub struct TargetEntry {
+    /// Path to the entry
+    pub path: PathBuf,
+    /// File or directory name of the entry
+    pub name: String,
+    /// Total size in bytes
+    pub size_bytes: u64,
+}
+
+/// Most files looked at under `src/` when finding a project's last edit
+const MAX_SOURCE_FILES: usize = 10_000;
+/// Same, on a network filesystem where each file's metadata is a round trip to the server
+const NETWORK_SOURCE_FILES: usize = 1_000;
+
+/// Files of a directory whose sizes are read before the rest is only counted
+const SAMPLE_FILES: u64 = 10_000;
+/// Same, on a network filesystem
+const NETWORK_SAMPLE_FILES: u64 = 1_000;
+
+/// Most files whose times are read to find a target's oldest artifact
+const MAX_TIMED_FILES: usize = 100;
+/// Same, on a network filesystem
+const NETWORK_TIMED_FILES: usize = 20;
+
+/// Levels of a target read to find when it was last built, enough for `<triple>/<profile>`
+const BUILT_DEPTH: usize = 3;
+/// Directories holding one file per unit; rebuilding a unit changes their own
+/// modification time, which is read instead of their contents
+pub const UNIT_DIRS: &[&str] = &["deps", "build", ".fingerprint", "incremental", "doc"];
+
+/// Directories of a project that Rust web builds write to: trunk's `dist`, wasm-pack's `pkg`
+const WEB_OUTPUT_DIRS: &[&str] = &["dist", "pkg"];
+
+/// Where esp-idf-sys and embuild install ESP-IDF and its tools, inside the project
+const EMBUILD_DIR: &str = ".embuild";
+/// Build directory of ESP-IDF's CMake project, inside the project
+const ESP_IDF_BUILD_DIR: &str = "build";
+
+/// Directory of a target that criterion writes benchmark results and reports to
+pub const CRITERION_DIR: &str = "criterion";
+/// Files that cargo-flamegraph and perf write to the directory they are run in
+const PROFILING_FILES: &[&str] = &["flamegraph.svg", "perf.data", "perf.data.old"];
+
+/// Whether the search paths are on a network filesystem, where metadata calls are slow
+static NETWORK_FS: AtomicBool = AtomicBool::new(false);
+
+/// Whether every file is measured instead of estimating large directories from a sample
+static EXACT_SIZES: AtomicBool = AtomicBool::new(false);
+
+/// Measures every file from now on instead of sampling large directories (`scan.exact_sizes`)
+pub fn set_exact_sizes(exact: bool) {
+    EXACT_SIZES.store(exact, Ordering::Relaxed);
+}
+
+/// Whether sizes are measured file by file rather than estimated from a sample
+pub fn exact_sizes() -> bool {
+    EXACT_SIZES.load(Ordering::Relaxed)
+}
+
+/// Reads as little file metadata as possible from now on (`scan.network_fs`)
+///
+/// Sizes are sampled from fewer files, fewer files are timed, and targets are not
+/// checked for files owned by other users: on NFS and SMB each of these is a round trip.
+pub fn set_network_fs(network: bool) {
+    NETWORK_FS.store(network, Ordering::Relaxed);
+}
+
+/// Whether scanning is tuned for a network filesystem
+pub fn network_fs() -> bool {
+    NETWORK_FS.load(Ordering::Relaxed)
+}
+
+/// `local`, or `network` when scanning is tuned for a network filesystem
+fn limit<T>(local: T, network: T) -> T {
+    if network_fs() { network } else { local }
+}
+
+/// Size of a file or directory, with how many of its files were measured
+#[derive(Debug, Clone, Copy, Default)]
+struct Measured {
+    bytes: u64,
+    files: u64,
+    measured: u64,
 }
 
 /// Utility for finding and analyzing target directories
@@ -21,150 +336,769 @@ pub struct TargetFinder;
 
 impl TargetFinder {
     /// Finds and analyzes the target directory for a Rust project
-    pub fn find_target_info(project_path: &Path) -> Result<TargetInfo, Box<dyn Error>> {
-        let target_path = project_path.join("target");
+    ///
+    /// A project that has not been built has no target directory, which is not an error.
+    pub fn find_target_info(project_path: &Path) -> Result<Option<TargetInfo>, Box<dyn Error>> {
+        Self::target_info_at(project_path, Self::resolve_target_dir(project_path))
+    }
 
-        if !target_path.exists() || !target_path.is_dir() {
-            return Err(format!("Target directory not found: {:?}", target_path).into());
+    /// Analyzes the given target directory of a project, or of the directory it was left in
+    pub fn target_info_at(
+        project_path: &Path,
+        target_path: PathBuf,
+    ) -> Result<Option<TargetInfo>, Box<dyn Error>> {
+        let fs = vfs::current();
+        if !fs.is_dir(&target_path) {
+            if let Ok(link) = fs::read_link(&target_path) {
+                return Err(format!(
+                    "{} is a broken symlink to {}",
+                    target_path.display(),
+                    link.display()
+                )
+                .into());
+            }
+            return Ok(None);
         }
 
-        let size_bytes = Self::calculate_directory_size(&target_path)?;
-        let last_accessed = Self::get_last_accessed_time(&target_path)?;
+        let rust_analyzer = Self::find_rust_analyzer_dir(project_path, &target_path);
+        let breakdown = timing::measure(Phase::Sizing, || {
+            sizes::cached_size(&target_path, || {
+                Self::calculate_breakdown(&target_path, rust_analyzer.as_deref(), exact_sizes())
+            })
+        })?;
+        let (last_built, oldest_artifact) = timing::measure(Phase::Staleness, || {
+            Ok::<_, Box<dyn Error>>((
+                Self::find_last_built(&target_path)?,
+                Self::find_oldest_artifact(&target_path),
+            ))
+        })?;
 
         // Default to considering it stale (will be updated by analyzer)
         let is_stale = false;
+        let foreign_owner = Self::find_foreign_owner(project_path, &target_path);
+        let link_target = (fs.symlink_metadata(&target_path)?.kind == EntryKind::Symlink)
+            .then(|| fs.canonicalize(&target_path))
+            .transpose()?;
+        let device = Self::find_separate_device(project_path, &target_path);
+        let last_edited =
+            timing::measure(Phase::Staleness, || Self::find_last_edited(project_path));
+        let triples = Self::find_triples(&target_path, rust_analyzer.as_deref());
+        let cross_builds = cross::detect(&target_path, &triples);
+        let web_outputs = Self::find_web_outputs(project_path);
+        let web_size = web_outputs.iter().map(|path| Self::path_size(path)).sum();
+        let embedded_dirs = Self::find_embedded_dirs(project_path);
+        let embedded_size = embedded_dirs.iter().map(|path| Self::path_size(path)).sum();
+        let profiling_files = Self::find_profiling_files(project_path);
+        let profiling_size = profiling_files
+            .iter()
+            .map(|path| Self::path_size(path))
+            .sum();
 
-        Ok(TargetInfo {
+        Ok(Some(TargetInfo {
             path: target_path,
-            size_bytes,
-            last_accessed,
+            size_bytes: breakdown.total(),
+            last_accessed: last_built,
+            last_built: Some(last_built),
+            oldest_artifact,
             is_stale,
-        })
+            foreign_owner,
+            link_target,
+            device,
+            availability: Availability::Available,
+            breakdown,
+            rust_analyzer,
+            last_edited,
+            metric: StalenessMetric::Mtime,
+            triples,
+            cross_builds,
+            web_outputs,
+            web_size,
+            embedded_dirs,
+            embedded_size,
+            profiling_files,
+            profiling_size,
+        }))
     }
 
-    /// Calculates the total size of a directory recursively with optimizations for large directories
-    fn calculate_directory_size(dir_path: &Path) -> Result<u64, Box<dyn Error>> {
-        let mut total_size = 0u64;
-        let mut file_count = 0;
-
-        // Optimized walkdir configuration
-        for entry in walkdir::WalkDir::new(dir_path)
-            .follow_links(false) // Don't follow symlinks
-            .max_open(128) // Limit file descriptors
-            .into_iter()
+    /// Newest modification time of `Cargo.lock` and the files under `src/`
+    ///
+    /// Editing sources without building leaves the target untouched, so this tells
+    /// an actively worked-on project apart from an abandoned one.
+    fn find_last_edited(project_path: &Path) -> Option<SystemTime> {
+        let fs = vfs::current();
+        let lock = fs
+            .metadata(&project_path.join("Cargo.lock"))
+            .map(|m| m.modified)
+            .ok();
+        let sources = Walk::new(fs, &project_path.join("src"))
             .filter_map(Result::ok)
-        {
-            if entry.file_type().is_file() {
-                if let Ok(metadata) = entry.metadata() {
-                    total_size += metadata.len();
-                    file_count += 1;
-
-                    // For directories with many files, avoid scanning everything
-                    // Estimate size based on sample for very large directories
-                    if file_count > 10000 {
-                        // Calculate average file size so far and estimate
-                        let avg_size = if file_count > 0 {
-                            total_size / file_count as u64
-                        } else {
-                            0
-                        };
-
-                        // Estimate total based on directory entry count (which is faster)
-                        if let Ok(dir_entry_count) = Self::count_directory_entries(dir_path) {
-                            return Ok(avg_size * dir_entry_count);
-                        }
-                    }
+            .filter(|entry| entry.kind == EntryKind::File)
+            .take(limit(MAX_SOURCE_FILES, NETWORK_SOURCE_FILES))
+            .filter_map(|entry| Some(fs.symlink_metadata(&entry.path).ok()?.modified))
+            .max();
+        lock.max(sources)
+    }
+
+    /// Whether a directory looks like a target directory cargo created
+    ///
+    /// Cargo tags its target directories with a `CACHEDIR.TAG` naming it and records
+    /// the compiler it ran in `.rustc_info.json`.
+    pub fn is_cargo_target(path: &Path) -> bool {
+        let fs = vfs::current();
+        fs.is_file(&path.join(".rustc_info.json"))
+            || fs
+                .read_to_string(&path.join("CACHEDIR.TAG"))
+                .is_ok_and(|tag| tag.contains("created by cargo"))
+    }
+
+    /// Target directory cargo builds a project into
+    ///
+    /// Honors `build.target-dir` from `.cargo/config.toml` (or the legacy `.cargo/config`)
+    /// in the project and its parent directories, nearest first, the way cargo merges
+    /// them. Relative values are relative to the directory holding `.cargo`. The global
+    /// `CARGO_TARGET_DIR` and `$CARGO_HOME` settings are not applied: a target shared by
+    /// every project is not any one project's to clean.
+    pub fn resolve_target_dir(project_path: &Path) -> PathBuf {
+        for dir in project_path.ancestors() {
+            for name in ["config.toml", "config"] {
+                let Ok(content) = vfs::current().read_to_string(&dir.join(".cargo").join(name))
+                else {
+                    continue;
+                };
+                let configured = content.parse::<toml::Table>().ok().and_then(|config| {
+                    config
+                        .get("build")?
+                        .get("target-dir")?
+                        .as_str()
+                        .map(PathBuf::from)
+                });
+                if let Some(target_dir) = configured {
+                    return dir.join(target_dir);
                 }
             }
         }
+        project_path.join("target")
+    }
 
-        Ok(total_size)
+    /// Finds the directory inside the target that rust-analyzer builds into
+    ///
+    /// With `rust-analyzer.cargo.targetDir` set to `true` that is `target/rust-analyzer`;
+    /// a path setting is honored when it points inside the target. Directories elsewhere
+    /// are not part of the target and are left alone.
+    fn find_rust_analyzer_dir(project_path: &Path, target_path: &Path) -> Option<PathBuf> {
+        let configured = vfs::current()
+            .read_to_string(&project_path.join(".vscode/settings.json"))
+            .ok()
+            .and_then(|settings| serde_json::from_str::<serde_json::Value>(&settings).ok())
+            .and_then(|settings| settings.get("rust-analyzer.cargo.targetDir").cloned());
+
+        let dir = match configured {
+            Some(serde_json::Value::String(dir)) => project_path.join(dir),
+            _ => target_path.join("rust-analyzer"),
+        };
+        (dir.parent() == Some(target_path) && vfs::current().is_dir(&dir)).then_some(dir)
     }
 
-    /// Gets the last accessed time for a directory or its most recent file
-    fn get_last_accessed_time(dir_path: &Path) -> Result<SystemTime, Box<dyn Error>> {
-        // First try to get modification time, which is more reliably updated than access time
-        let mut last_modified = fs::metadata(dir_path)?.modified()?;
-        let mut files_checked = 0;
-        let mut found_older_file = false;
+    /// Samples the target for files owned by someone other than the project's owner
+    ///
+    /// Containerized builds usually run as root, leaving files the user cannot delete.
+    #[cfg(unix)]
+    fn find_foreign_owner(project_path: &Path, target_path: &Path) -> Option<u32> {
+        use std::os::unix::fs::MetadataExt;
 
-        // Optimized walkdir configuration
-        for entry in walkdir::WalkDir::new(dir_path)
-            .follow_links(false) // Don't follow symlinks
-            .max_open(128) // Limit file descriptors
+        // Servers commonly map root to nobody anyway, so this would mostly cost time
+        if network_fs() {
+            return None;
+        }
+        let owner = fs::metadata(project_path).ok()?.uid();
+        walkdir::WalkDir::new(target_path)
+            .follow_links(false)
             .into_iter()
             .filter_map(Result::ok)
-        {
-            if entry.file_type().is_file() {
-                if let Ok(metadata) = entry.metadata() {
-                    // Try to get modification time first, as it's more reliable
-                    if let Ok(modified) = metadata.modified() {
-                        // Update if we found an older file (more representative of last use)
-                        if modified < last_modified && !found_older_file {
-                            last_modified = modified;
-                            found_older_file = true;
-                        }
-
-                        // Keep track of the oldest file we've found
-                        if modified < last_modified {
-                            last_modified = modified;
-                        }
-                    }
-                }
+            .take(500)
+            .filter_map(|entry| entry.metadata().ok())
+            .map(|metadata| metadata.uid())
+            .find(|&uid| uid != owner)
+    }
+
+    /// Returns the target's device when it differs from the project's (a mount or another drive)
+    #[cfg(unix)]
+    fn find_separate_device(project_path: &Path, target_path: &Path) -> Option<u64> {
+        use std::os::unix::fs::MetadataExt;
+
+        let project_device = fs::metadata(project_path).ok()?.dev();
+        let target_device = fs::metadata(target_path).ok()?.dev();
+        (target_device != project_device).then_some(target_device)
+    }
+
+    /// Returns the target's device when it differs from the project's (a mount or another drive)
+    #[cfg(not(unix))]
+    fn find_separate_device(_project_path: &Path, _target_path: &Path) -> Option<u64> {
+        None
+    }
+
+    /// Samples the target for files owned by someone other than the project's owner
+    #[cfg(not(unix))]
+    fn find_foreign_owner(_project_path: &Path, _target_path: &Path) -> Option<u32> {
+        None
+    }
+
+    /// Lists the top-level entries of a target directory with their sizes, largest first
+    pub fn list_target_entries(target_path: &Path) -> Result<Vec<TargetEntry>, Box<dyn Error>> {
+        let mut entries = Vec::new();
+
+        let fs = vfs::current();
+        for entry in fs.read_dir(target_path)? {
+            let size_bytes = if entry.kind == EntryKind::Dir {
+                timing::measure(Phase::Sizing, || {
+                    Self::calculate_directory_size(&entry.path, exact_sizes())
+                })?
+                .bytes
+            } else {
+                fs.symlink_metadata(&entry.path).map_or(0, |m| m.size)
+            };
+
+            entries.push(TargetEntry {
+                name: entry.name(),
+                path: entry.path,
+                size_bytes,
+            });
+        }
+
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.size_bytes));
+        Ok(entries)
+    }
+
+    /// Current size of a target directory, measured without the size cache
+    pub fn measure_size(target_path: &Path) -> u64 {
+        timing::measure(Phase::Sizing, || {
+            Self::calculate_directory_size(target_path, exact_sizes())
+        })
+        .map_or(0, |size| size.bytes)
+    }
+
+    /// Measures every file of a target, replacing a size estimated from a sample
+    pub fn measure_exact(target_info: &mut TargetInfo) -> Result<(), Box<dyn Error>> {
+        Self::measure_again(target_info, true)
+    }
+
+    /// Measures a target again after part of it was removed, the way a scan would
+    pub fn remeasure(target_info: &mut TargetInfo) -> Result<(), Box<dyn Error>> {
+        Self::measure_again(target_info, exact_sizes())
+    }
+
+    fn measure_again(target_info: &mut TargetInfo, exact: bool) -> Result<(), Box<dyn Error>> {
+        let breakdown = timing::measure(Phase::Sizing, || {
+            sizes::refresh(&target_info.path, || {
+                Self::calculate_breakdown(
+                    &target_info.path,
+                    target_info.rust_analyzer.as_deref(),
+                    exact,
+                )
+            })
+        })?;
+        target_info.size_bytes = breakdown.total();
+        target_info.breakdown = breakdown;
+        Ok(())
+    }
+
+    /// Size of a file, or of a directory and everything in it
+    pub fn path_size(path: &Path) -> u64 {
+        Self::measure_path(path, exact_sizes()).bytes
+    }
+
+    /// Size of a file, or of a directory and everything in it, sampled unless `exact`
+    fn measure_path(path: &Path, exact: bool) -> Measured {
+        match vfs::current().symlink_metadata(path) {
+            Ok(metadata) if metadata.is_dir() => timing::measure(Phase::Sizing, || {
+                Self::calculate_directory_size(path, exact)
+            })
+            .unwrap_or_default(),
+            Ok(metadata) => Measured {
+                bytes: metadata.size,
+                files: 1,
+                measured: 1,
+            },
+            Err(_) => Measured::default(),
+        }
+    }
 
-                files_checked += 1;
+    /// Sizes a target directory, split into debug, release, doc and everything else
+    ///
+    /// Directories for a target triple (`target/x86_64-unknown-linux-gnu/debug`) are
+    /// split by the profiles inside them.
+    fn calculate_breakdown(
+        target_path: &Path,
+        rust_analyzer: Option<&Path>,
+        exact: bool,
+    ) -> Result<SizeBreakdown, Box<dyn Error>> {
+        let mut breakdown = SizeBreakdown::default();
 
-                // Limit the number of files we check for performance
-                // After checking 100 files, we should have a reasonable sample
-                if files_checked > 100 {
-                    break;
+        let fs = vfs::current();
+        for entry in fs.read_dir(target_path)? {
+            let path = entry.path.as_path();
+            let name = entry.name();
+            if Some(path) == rust_analyzer {
+                let size = Self::measure_path(path, exact);
+                breakdown.rust_analyzer += size.bytes;
+                breakdown.count(size);
+            } else if entry.kind != EntryKind::Dir {
+                let size = Self::measure_path(path, exact);
+                breakdown.other += size.bytes;
+                breakdown.count(size);
+            } else if Self::is_triple_dir(&name, path) {
+                for child in fs.read_dir(path)? {
+                    if child.kind == EntryKind::Dir {
+                        breakdown.add_profile(&child.name(), &child.path, exact)?;
+                    } else {
+                        breakdown.add(&child.name(), Self::measure_path(&child.path, exact));
+                    }
                 }
+            } else if name == "doc" || name == CRITERION_DIR {
+                breakdown.add(&name, Self::calculate_directory_size(path, exact)?);
+            } else {
+                breakdown.add_profile(&name, path, exact)?;
             }
         }
 
-        // If we couldn't find a reliable timestamp, use a default (30 days ago)
-        if !found_older_file {
-            let default_age = SystemTime::now() - Duration::from_secs(30 * 24 * 60 * 60);
-            return Ok(default_age);
+        Ok(breakdown)
+    }
+
+    /// Cross-compilation targets with builds in a target directory, sorted by name
+    fn find_triples(target_path: &Path, rust_analyzer: Option<&Path>) -> Vec<String> {
+        let mut triples: Vec<String> = vfs::current()
+            .read_dir(target_path)
+            .into_iter()
+            .flatten()
+            .filter(|entry| {
+                entry.kind == EntryKind::Dir && Some(entry.path.as_path()) != rust_analyzer
+            })
+            .map(|entry| (entry.name(), entry.path))
+            .filter(|(name, path)| Self::is_triple_dir(name, path))
+            .map(|(name, _)| name)
+            .collect();
+        triples.sort();
+        triples
+    }
+
+    /// Output directories of Rust web builds in a project, e.g. `dist` from trunk
+    ///
+    /// Only directories with a `.wasm` file at their top level count, so a `dist` or
+    /// `pkg` holding anything else (a Python package, release tarballs) is never offered.
+    fn find_web_outputs(project_path: &Path) -> Vec<PathBuf> {
+        let fs = vfs::current();
+        WEB_OUTPUT_DIRS
+            .iter()
+            .map(|name| project_path.join(name))
+            .filter(|dir| {
+                fs.symlink_metadata(dir)
+                    .is_ok_and(|metadata| metadata.is_dir())
+                    && fs.read_dir(dir).into_iter().flatten().any(|entry| {
+                        entry.kind == EntryKind::File
+                            && entry.path.extension().is_some_and(|ext| ext == "wasm")
+                    })
+            })
+            .collect()
+    }
+
+    /// Embedded toolchain directories in a project, downloaded or generated again by a build
+    ///
+    /// A `build` directory only counts when it is ESP-IDF's, known from the
+    /// `project_description.json` it writes or the target chip in its CMake cache.
+    fn find_embedded_dirs(project_path: &Path) -> Vec<PathBuf> {
+        let fs = vfs::current();
+        let is_dir = |path: &Path| {
+            fs.symlink_metadata(path)
+                .is_ok_and(|metadata| metadata.is_dir())
+        };
+        let mut dirs = Vec::new();
+        let embuild = project_path.join(EMBUILD_DIR);
+        if is_dir(&embuild) {
+            dirs.push(embuild);
+        }
+        let build = project_path.join(ESP_IDF_BUILD_DIR);
+        if is_dir(&build)
+            && (fs.is_file(&build.join("project_description.json"))
+                || fs
+                    .read_to_string(&build.join("CMakeCache.txt"))
+                    .is_ok_and(|cache| cache.contains("IDF_TARGET")))
+        {
+            dirs.push(build);
         }
+        dirs
+    }
 
-        Ok(last_modified)
+    /// Profiler output files in a project's directory
+    fn find_profiling_files(project_path: &Path) -> Vec<PathBuf> {
+        PROFILING_FILES
+            .iter()
+            .map(|name| project_path.join(name))
+            .filter(|file| vfs::current().is_file(file))
+            .collect()
     }
 
-    /// Counts the number of entries in a directory (faster than walking all files)
-    fn count_directory_entries(dir_path: &Path) -> Result<u64, Box<dyn Error>> {
-        let mut count = 0;
+    /// Whether a top-level target entry holds the builds for a cross-compilation target
+    fn is_triple_dir(name: &str, path: &Path) -> bool {
+        !matches!(name, "debug" | "release" | "doc" | CRITERION_DIR)
+            && (vfs::current().is_dir(&path.join("debug"))
+                || vfs::current().is_dir(&path.join("release")))
+    }
 
-        if let Ok(entries) = fs::read_dir(dir_path) {
-            for _ in entries.filter_map(Result::ok) {
-                count += 1;
+    /// Calculates the total size of a directory recursively
+    ///
+    /// Unless `exact`, only the first [`SAMPLE_FILES`] files ([`NETWORK_SAMPLE_FILES`] on a
+    /// network filesystem) are measured; the rest are counted, which needs no metadata,
+    /// and sized like the average of the sample.
+    fn calculate_directory_size(dir_path: &Path, exact: bool) -> Result<Measured, Box<dyn Error>> {
+        let mut size = Measured::default();
 
-                // Cap at a reasonable limit
-                if count > 100000 {
-                    break;
-                }
+        let fs = vfs::current();
+        // Symlinks are not followed
+        for entry in Walk::new(fs, dir_path).filter_map(Result::ok) {
+            if entry.kind != EntryKind::File {
+                continue;
+            }
+            if !exact && size.measured >= limit(SAMPLE_FILES, NETWORK_SAMPLE_FILES) {
+                size.files += 1;
+            } else if let Ok(metadata) = fs.symlink_metadata(&entry.path) {
+                size.bytes += metadata.size;
+                size.files += 1;
+                size.measured += 1;
             }
         }
 
-        Ok(count)
+        if size.measured < size.files {
+            size.bytes = (u128::from(size.bytes) * u128::from(size.files)
+                / u128::from(size.measured)) as u64;
+        }
+        Ok(size)
+    }
+
+    /// Newest modification time of the build outputs of a target
+    ///
+    /// Reads the top levels of each profile: the final binaries and libraries, and the
+    /// per-unit directories themselves, whose time changes whenever a unit is rebuilt.
+    /// An empty target counts as built when it was created.
+    fn find_last_built(dir_path: &Path) -> Result<SystemTime, Box<dyn Error>> {
+        let fs = vfs::current();
+        let created = fs.metadata(dir_path)?.modified;
+        let mut newest = None;
+
+        // Symlinks are not followed
+        let mut walk = Walk::new(fs, dir_path).max_depth(BUILT_DEPTH);
+        while let Some(entry) = walk.next() {
+            let Ok(entry) = entry else { continue };
+            if entry.depth == 0 {
+                continue;
+            }
+            if entry.kind == EntryKind::Dir
+                && entry
+                    .path
+                    .file_name()
+                    .is_some_and(|name| UNIT_DIRS.iter().any(|dir| name == *dir))
+            {
+                walk.skip_current_dir();
+            }
+            if let Ok(metadata) = fs.symlink_metadata(&entry.path) {
+                newest = newest.max(Some(metadata.modified));
+            }
+        }
+
+        Ok(newest.unwrap_or(created))
+    }
+
+    /// Oldest modification time among the first files of a target
+    fn find_oldest_artifact(dir_path: &Path) -> Option<SystemTime> {
+        let fs = vfs::current();
+        Walk::new(fs, dir_path)
+            .filter_map(Result::ok)
+            .filter(|entry| entry.kind == EntryKind::File)
+            .take(limit(MAX_TIMED_FILES, NETWORK_TIMED_FILES))
+            .filter_map(|entry| Some(fs.symlink_metadata(&entry.path).ok()?.modified))
+            .min()
     }
 
     /// Checks if a target directory is considered stale based on the given threshold
+    ///
+    /// A project whose sources were edited within the threshold is never stale, even
+    /// when it has not been built lately.
     pub fn is_stale(target_info: &TargetInfo, threshold: Duration) -> Result<bool, Box<dyn Error>> {
         let now = SystemTime::now();
+        let last_active = target_info
+            .last_edited
+            .map_or(target_info.last_accessed, |edited| {
+                edited.max(target_info.last_accessed)
+            });
         let time_diff = now
-            .duration_since(target_info.last_accessed)
+            .duration_since(last_active)
             .unwrap_or_else(|_| Duration::from_secs(0));
 
         Ok(time_diff >= threshold)
     }
 
+    /// Re-checks whether a target directory can still be cleaned
+    pub fn update_availability(target_info: &mut TargetInfo, symlinks: SymlinkPolicy) {
+        target_info.availability = if !vfs::current().exists(&target_info.path) {
+            Availability::Missing
+        } else if is_read_only_filesystem(&target_info.path) {
+            Availability::ReadOnly
+        } else if target_info.link_target.is_some() && symlinks == SymlinkPolicy::Skip {
+            Availability::Symlinked
+        } else {
+            Availability::Available
+        };
+    }
+
+    /// Takes a target's last use from the access times of its artifacts when configured
+    ///
+    /// Keeps the modification times where the filesystem does not record access times.
+    pub fn apply_metric(target_info: &mut TargetInfo, metric: StalenessMetric) {
+        if metric != StalenessMetric::Atime || !records_access_times(&target_info.path) {
+            return;
+        }
+        if let Some(accessed) = timing::measure(Phase::Staleness, || {
+            Self::artifact_access_time(&target_info.path)
+        }) {
+            target_info.last_accessed = accessed;
+            target_info.metric = StalenessMetric::Atime;
+        }
+    }
+
+    /// Newest access time of the binaries and libraries in any profile of a target
+    ///
+    /// Running a binary or linking against a library reads it, so these tell when the
+    /// build was last used rather than when it was last rebuilt.
+    fn artifact_access_time(target: &Path) -> Option<SystemTime> {
+        let subdirs = |dir: &Path| -> Vec<PathBuf> {
+            fs::read_dir(dir)
+                .into_iter()
+                .flatten()
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect()
+        };
+        // Profiles sit at target/<profile>, or target/<triple>/<profile> when cross-compiling
+        let profiles = subdirs(target)
+            .into_iter()
+            .flat_map(|dir| {
+                let mut dirs = subdirs(&dir);
+                dirs.push(dir);
+                dirs
+            })
+            .filter(|dir| dir.join("deps").is_dir());
+
+        profiles
+            .flat_map(|profile| [profile.join("deps"), profile])
+            .flat_map(|dir| {
+                fs::read_dir(dir)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Result::ok)
+                    .take(1000)
+            })
+            .filter_map(|entry| entry.metadata().ok())
+            .filter(|metadata| metadata.is_file())
+            .filter_map(|metadata| metadata.accessed().ok())
+            .max()
+    }
+
     /// Updates a TargetInfo to determine if it's stale based on the threshold
     pub fn update_stale_status(
         target_info: &mut TargetInfo,
         threshold: Duration,
     ) -> Result<(), Box<dyn Error>> {
-        target_info.is_stale = Self::is_stale(target_info, threshold)?;
+        target_info.is_stale =
+            timing::measure(Phase::Staleness, || Self::is_stale(target_info, threshold))?;
         Ok(())
     }
 }
+
+/// Formats a device id the way `stat` and `/proc/self/mountinfo` show it
+pub fn device_label(device: u64) -> String {
+    #[cfg(target_os = "linux")]
+    {
+        format!("{}:{}", libc::major(device), libc::minor(device))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        format!("{:#x}", device)
+    }
+}
+
+/// Statistics of the filesystem containing a path, from statvfs(3)
+#[cfg(unix)]
+fn statvfs(path: &Path) -> Option<libc::statvfs> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    // SAFETY: c_path is a valid NUL-terminated string and stat is a properly sized out-parameter
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    let result = unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) };
+    (result == 0).then_some(stat)
+}
+
+/// Space available to unprivileged users on the filesystem containing a path
+#[cfg(unix)]
+pub fn free_space(path: &Path) -> Option<u64> {
+    let stat = statvfs(path)?;
+    #[allow(clippy::unnecessary_cast)] // the field types differ between platforms
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+/// Space available to the current user on the volume containing a path
+#[cfg(windows)]
+pub fn free_space(path: &Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut available = 0u64;
+    // SAFETY: wide is NUL-terminated and available is a valid out-parameter; the others may be null
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut available,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    (ok != 0).then_some(available)
+}
+
+/// Space available on the filesystem containing a path
+#[cfg(not(any(unix, windows)))]
+pub fn free_space(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Whether a path is on a network filesystem (NFS, SMB, AFS, Ceph, WSL's 9p, ...)
+#[cfg(target_os = "linux")]
+pub fn is_network_filesystem(path: &Path) -> bool {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    /// `f_type` magic numbers of network filesystems, from statfs(2)
+    const NETWORK_MAGICS: &[i64] = &[
+        0x6969,     // NFS
+        0x517b,     // SMB
+        0xff534d42, // CIFS
+        0xfe534d42, // SMB2
+        0x5346414f, // AFS
+        0x73757245, // Coda
+        0x564c,     // NCP
+        0x01021997, // 9p
+        0x00c36400, // Ceph
+    ];
+
+    let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: c_path is a valid NUL-terminated string and stat is a properly sized out-parameter
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    let result = unsafe { libc::statfs(c_path.as_ptr(), &mut stat) };
+    #[allow(clippy::unnecessary_cast)] // the field type differs between architectures
+    let magic = stat.f_type as i64;
+    result == 0 && NETWORK_MAGICS.contains(&magic)
+}
+
+/// Whether a path is on a network share: a UNC path or a mapped network drive
+#[cfg(windows)]
+pub fn is_network_filesystem(path: &Path) -> bool {
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::{Component, Prefix};
+    use windows_sys::Win32::Storage::FileSystem::GetDriveTypeW;
+
+    /// `GetDriveTypeW` result for a remote drive
+    const DRIVE_REMOTE: u32 = 4;
+
+    let drive = match path.components().next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::UNC(..) | Prefix::VerbatimUNC(..) => return true,
+            Prefix::Disk(letter) | Prefix::VerbatimDisk(letter) => letter,
+            _ => return false,
+        },
+        _ => return false,
+    };
+    let root: Vec<u16> = std::ffi::OsStr::new(&format!("{}:\\", drive as char))
+        .encode_wide()
+        .chain(Some(0))
+        .collect();
+    // SAFETY: root is a NUL-terminated drive root such as "C:\"
+    unsafe { GetDriveTypeW(root.as_ptr()) == DRIVE_REMOTE }
+}
+
+/// Whether a path is on a network filesystem; not detected on other platforms
+#[cfg(not(any(target_os = "linux", windows)))]
+pub fn is_network_filesystem(_path: &Path) -> bool {
+    false
+}
+
+/// Free space on the filesystems holding the given directories, sampled once per filesystem
+pub fn free_space_by_filesystem<'a>(
+    dirs: impl IntoIterator<Item = &'a Path>,
+) -> Vec<(PathBuf, u64)> {
+    let mut seen = HashSet::new();
+    dirs.into_iter()
+        .filter(|dir| seen.insert(filesystem_id(dir)))
+        .filter_map(|dir| Some((dir.to_path_buf(), free_space(dir)?)))
+        .collect()
+}
+
+/// Identifies the filesystem a path is on
+#[cfg(unix)]
+fn filesystem_id(path: &Path) -> Option<String> {
+    use std::os::unix::fs::MetadataExt;
+
+    fs::metadata(path).ok().map(|m| m.dev().to_string())
+}
+
+/// Identifies the volume a path is on by its drive or share
+#[cfg(not(unix))]
+fn filesystem_id(path: &Path) -> Option<String> {
+    path.components()
+        .next()
+        .map(|prefix| prefix.as_os_str().to_string_lossy().into_owned())
+}
+
+/// Paths whose filesystem has less than `threshold` bytes free, with the space free
+pub fn low_free_space(paths: &[PathBuf], threshold: u64) -> Vec<(PathBuf, u64)> {
+    paths
+        .iter()
+        .filter_map(|path| Some((path.clone(), free_space(path)?)))
+        .filter(|(_, free)| *free < threshold)
+        .collect()
+}
+
+/// Checks whether the filesystem containing a path is mounted read-only
+#[cfg(unix)]
+fn is_read_only_filesystem(path: &Path) -> bool {
+    statvfs(path).is_some_and(|stat| (stat.f_flag & libc::ST_RDONLY) != 0)
+}
+
+/// Whether the filesystem containing a path updates access times (not mounted `noatime`)
+#[cfg(target_os = "linux")]
+fn records_access_times(path: &Path) -> bool {
+    statvfs(path).is_some_and(|stat| (stat.f_flag & libc::ST_NOATIME) == 0)
+}
+
+/// Whether the filesystem containing a path updates access times; assumed on other Unixes
+#[cfg(all(unix, not(target_os = "linux")))]
+fn records_access_times(_path: &Path) -> bool {
+    true
+}
+
+/// Windows often has last-access updates disabled, so access times are not trusted there
+#[cfg(not(unix))]
+fn records_access_times(_path: &Path) -> bool {
+    false
+}
+
+/// Checks whether the filesystem containing a path is mounted read-only
+#[cfg(not(unix))]
+fn is_read_only_filesystem(path: &Path) -> bool {
+    fs::metadata(path)
+        .map(|metadata| metadata.permissions().readonly())
+        .unwrap_or(false)
+}
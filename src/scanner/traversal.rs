@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::scanner::target_finder;
+use crate::vfs::{EntryKind, WalkEntry};
+
+/// Directories taking less time than this to walk are never suggested for exclusion
+const SUGGEST_AFTER: Duration = Duration::from_secs(1);
+/// Same, on a network filesystem where every directory is slow to walk
+const NETWORK_SUGGEST_AFTER: Duration = Duration::from_secs(10);
+/// Maximum number of exclusion suggestions offered after a scan
+const MAX_SUGGESTIONS: usize = 5;
+
+/// Time spent walking one top-level directory of a search path, and what it yielded
+#[derive(Debug, Clone)]
+pub struct TraversalStat {
+    /// Directory directly below the search path
+    pub path: PathBuf,
+    /// Time spent walking it
+    pub elapsed: Duration,
+    /// Number of entries visited inside it
+    pub entries: usize,
+    /// Number of Cargo.toml files found inside it
+    pub projects: usize,
+}
+
+/// Records traversal statistics per top-level directory while a search path is walked
+///
+/// The walk visits a directory's contents before moving on to its next sibling, so each
+/// top-level directory is timed from its first entry until the next one starts.
+pub struct TraversalRecorder {
+    current: Option<(TraversalStat, Instant)>,
+    finished: Vec<TraversalStat>,
+}
+
+impl TraversalRecorder {
+    pub fn new() -> Self {
+        Self {
+            current: None,
+            finished: Vec::new(),
+        }
+    }
+
+    /// Accounts for a walked entry; `is_manifest` marks a Cargo.toml
+    pub fn visit(&mut self, entry: &WalkEntry, is_manifest: bool) {
+        if entry.depth == 1 && entry.kind == EntryKind::Dir {
+            self.finish_current();
+            let stat = TraversalStat {
+                path: entry.path.clone(),
+                elapsed: Duration::ZERO,
+                entries: 0,
+                projects: 0,
+            };
+            self.current = Some((stat, Instant::now()));
+        } else if entry.depth > 1
+            && let Some((stat, _)) = self.current.as_mut()
+        {
+            stat.entries += 1;
+            stat.projects += usize::from(is_manifest);
+        }
+    }
+
+    /// Returns the statistics of all top-level directories walked
+    pub fn finish(mut self) -> Vec<TraversalStat> {
+        self.finish_current();
+        self.finished
+    }
+
+    fn finish_current(&mut self) {
+        if let Some((mut stat, started)) = self.current.take() {
+            stat.elapsed = started.elapsed();
+            self.finished.push(stat);
+        }
+    }
+}
+
+/// Slow directories that yielded no projects, slowest first
+pub fn exclude_suggestions(stats: &[TraversalStat]) -> Vec<TraversalStat> {
+    let suggest_after = if target_finder::network_fs() {
+        NETWORK_SUGGEST_AFTER
+    } else {
+        SUGGEST_AFTER
+    };
+    let mut suggestions: Vec<TraversalStat> = stats
+        .iter()
+        .filter(|stat| stat.projects == 0 && stat.elapsed >= suggest_after)
+        .cloned()
+        .collect();
+    suggestions.sort_by_key(|stat| std::cmp::Reverse(stat.elapsed));
+    suggestions.truncate(MAX_SUGGESTIONS);
+    suggestions
+}
@@ -0,0 +1,84 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// Directory holding the home directories of the users of the machine
+#[cfg(target_os = "macos")]
+const HOMES_ROOT: &str = "/Users";
+#[cfg(not(target_os = "macos"))]
+const HOMES_ROOT: &str = "/home";
+
+/// Home directories of every user, for scanning them all with `--all-users`
+///
+/// Reading other users' projects and removing their targets needs root, so this
+/// refuses to run without it rather than silently skipping what it cannot read.
+pub fn home_directories() -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    if !is_privileged() {
+        return Err("--all-users needs root privileges (run it with sudo)".into());
+    }
+    let mut homes: Vec<PathBuf> = std::fs::read_dir(HOMES_ROOT)
+        .map_err(|e| format!("Failed to list {}: {}", HOMES_ROOT, e))?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+        // macOS keeps a directory for files shared by all users next to the homes
+        .filter(|entry| entry.file_name() != "Shared")
+        .map(|entry| entry.path())
+        .collect();
+    homes.sort();
+    Ok(homes)
+}
+
+/// Whether the process runs as root
+#[cfg(unix)]
+fn is_privileged() -> bool {
+    // SAFETY: geteuid takes no arguments, cannot fail and only reads process state
+    unsafe { libc::geteuid() == 0 }
+}
+
+/// Whether the process runs as root
+///
+/// Telling an elevated Windows process apart is not supported, so it never is.
+#[cfg(not(unix))]
+fn is_privileged() -> bool {
+    false
+}
+
+/// Login name of the owner of a path
+#[cfg(unix)]
+pub fn owner_name(path: &Path) -> String {
+    use std::os::unix::fs::MetadataExt;
+
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return "unknown".to_string();
+    };
+    let uid = metadata.uid();
+    // SAFETY: passwd is plain C data, for which all zero bytes are a valid value
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buffer = vec![0 as libc::c_char; 4096];
+    let mut result = std::ptr::null_mut();
+    // SAFETY: every pointer refers to a live local, and the buffer length passed is its own
+    let status = unsafe {
+        libc::getpwuid_r(
+            uid,
+            &mut passwd,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        )
+    };
+    if status != 0 || result.is_null() {
+        return uid.to_string();
+    }
+    // SAFETY: on success pw_name points to a NUL-terminated string inside `buffer`, which is
+    // still alive here
+    unsafe { std::ffi::CStr::from_ptr(passwd.pw_name) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Login name of the owner of a path
+///
+/// Ownership is not read on this platform; every project counts for the current user.
+#[cfg(not(unix))]
+pub fn owner_name(_path: &Path) -> String {
+    std::env::var("USERNAME").unwrap_or_else(|_| "unknown".to_string())
+}
@@ -0,0 +1,171 @@
+use notify::{RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::cache::projects::ProjectCache;
+use crate::cache::sizes;
+use crate::scanner::rust_project::RustProject;
+use crate::scanner::target_finder::{TargetFinder, low_free_space};
+use crate::size::format_bytes;
+
+/// How long the filesystem must stay quiet before a batch of changes is applied
+const SETTLE_TIME: Duration = Duration::from_secs(2);
+
+/// Keeps the cached project list in sync with the filesystem
+///
+/// Instead of rescanning everything periodically, filesystem events under the search
+/// paths are mapped to the projects they affect, and only those are re-analyzed.
+pub struct ProjectWatcher {
+    /// Directories watched recursively
+    roots: Vec<PathBuf>,
+    /// Patterns whose events are ignored, like the scanner's exclusions
+    exclude_patterns: Vec<String>,
+    /// Current project list, mirrored to the cache after every change
+    projects: Vec<RustProject>,
+    /// Free space below which a cleanup is proposed
+    free_below: Option<u64>,
+    /// Whether free space was low at the last check, so the proposal is made once per drop
+    space_low: bool,
+}
+
+impl ProjectWatcher {
+    /// Creates a watcher starting from the result of a full scan
+    pub fn new(roots: &[PathBuf], exclude_patterns: &[String], projects: Vec<RustProject>) -> Self {
+        Self {
+            roots: roots.to_vec(),
+            exclude_patterns: exclude_patterns.to_vec(),
+            projects,
+            free_below: None,
+            space_low: false,
+        }
+    }
+
+    /// Proposes a cleanup whenever free space on a watched filesystem drops below `threshold`
+    pub fn with_free_space_trigger(mut self, threshold: Option<u64>) -> Self {
+        self.free_below = threshold;
+        self
+    }
+
+    /// Watches the roots until the process is stopped, telling `report` what changed
+    pub fn run(&mut self, report: &mut dyn FnMut(&str)) -> Result<(), Box<dyn Error>> {
+        ProjectCache::save(&self.projects)?;
+
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        for root in &self.roots {
+            watcher.watch(root, RecursiveMode::Recursive)?;
+            report(&format!("Watching {}", root.display()));
+        }
+
+        self.check_free_space(report);
+        while let Ok(first) = rx.recv() {
+            // Builds produce bursts of events; collect them until things settle down
+            let mut affected = BTreeSet::new();
+            let mut next = Some(first);
+            while let Some(event) = next {
+                match event {
+                    // Sizing a target opens its files; reacting to that would loop forever
+                    Ok(event) if event.kind.is_access() => {}
+                    Ok(event) => {
+                        for path in &event.paths {
+                            affected.extend(self.affected_projects(path));
+                        }
+                    }
+                    Err(e) => report(&format!("Watch error: {}", e)),
+                }
+                next = rx.recv_timeout(SETTLE_TIME).ok();
+            }
+
+            if affected.is_empty() {
+                continue;
+            }
+            for project_dir in &affected {
+                self.refresh(project_dir);
+            }
+            ProjectCache::save(&self.projects)?;
+            if let Err(e) = sizes::save() {
+                report(&format!("Warning: Failed to save the size cache: {}", e));
+            }
+            report(&format!(
+                "Updated {} project(s); {} tracked",
+                affected.len(),
+                self.projects.len()
+            ));
+            self.check_free_space(report);
+        }
+
+        Ok(())
+    }
+
+    /// Proposes a cleanup when free space has just dropped below the trigger
+    fn check_free_space(&mut self, report: &mut dyn FnMut(&str)) {
+        let Some(threshold) = self.free_below else {
+            return;
+        };
+        let low = low_free_space(&self.roots, threshold);
+        if !low.is_empty() && !self.space_low {
+            for (root, free) in &low {
+                report(&format!(
+                    "Free space is low on {}: {} left, below {}",
+                    root.display(),
+                    format_bytes(*free),
+                    format_bytes(threshold)
+                ));
+            }
+            report("Run `clear-target clean` to remove stale targets");
+        }
+        self.space_low = !low.is_empty();
+    }
+
+    /// Project directories whose state may have changed because of an event on `path`
+    fn affected_projects(&self, path: &Path) -> Vec<PathBuf> {
+        let path_str = path.to_string_lossy();
+        if self
+            .exclude_patterns
+            .iter()
+            .any(|pattern| path_str.contains(pattern.as_str()))
+        {
+            return Vec::new();
+        }
+
+        // A removed or renamed directory takes every project below it along
+        let mut affected: Vec<PathBuf> = self
+            .projects
+            .iter()
+            .filter(|project| project.path.starts_with(path))
+            .map(|project| project.path.clone())
+            .collect();
+
+        if path.file_name().is_some_and(|name| name == "Cargo.toml")
+            && let Some(dir) = path.parent()
+        {
+            affected.push(dir.to_path_buf());
+        }
+
+        // Anything inside target/ changes that project's size and last use
+        if let Some(dir) = path
+            .ancestors()
+            .find(|dir| dir.file_name().is_some_and(|name| name == "target"))
+            .and_then(Path::parent)
+            .filter(|dir| dir.join("Cargo.toml").is_file())
+        {
+            affected.push(dir.to_path_buf());
+        }
+
+        affected
+    }
+
+    /// Re-analyzes one project directory, adding, updating or dropping it
+    fn refresh(&mut self, project_dir: &Path) {
+        self.projects.retain(|project| project.path != project_dir);
+
+        if let Ok(project) = RustProject::from_path(project_dir)
+            && let Ok(Some(target_info)) = TargetFinder::find_target_info(project_dir)
+        {
+            self.projects.push(project.with_target_info(target_info));
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use std::path::PathBuf;
+
+/// Home directories of the installed WSL distributions, as seen from Windows
+///
+/// Each is a UNC path like `\\wsl.localhost\Ubuntu\home` (`\\wsl$\...` on older
+/// Windows builds). Distributions that are not running are started by accessing them.
+#[cfg(windows)]
+pub fn home_roots() -> Result<Vec<PathBuf>, String> {
+    use std::process::Command;
+
+    let output = match Command::new("wsl.exe").args(["--list", "--quiet"]).output() {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => return Err(format!("wsl.exe --list exited with {}", output.status)),
+        Err(e) => return Err(format!("Cannot list WSL distributions: {}", e)),
+    };
+
+    Ok(distros(&output.stdout)
+        .into_iter()
+        .filter_map(|distro| {
+            [r"\\wsl.localhost", r"\\wsl$"]
+                .iter()
+                .map(|prefix| PathBuf::from(format!(r"{}\{}\home", prefix, distro)))
+                .find(|home| home.is_dir())
+        })
+        .collect())
+}
+
+/// WSL is only reachable this way from Windows
+#[cfg(not(windows))]
+pub fn home_roots() -> Result<Vec<PathBuf>, String> {
+    Err("scan.wsl only applies on Windows; scan WSL projects from inside WSL".to_string())
+}
+
+/// Distribution names from `wsl.exe --list --quiet`, which writes UTF-16 by default
+#[cfg(windows)]
+fn distros(stdout: &[u8]) -> Vec<String> {
+    let text = if stdout.len() >= 2 && stdout[1] == 0 {
+        let units: Vec<u16> = stdout
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        String::from_utf8_lossy(stdout).into_owned()
+    };
+    text.lines()
+        .map(|line| line.trim_matches(|c: char| c.is_whitespace() || c == '\0'))
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
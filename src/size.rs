@@ -0,0 +1,66 @@
+/// Units used when formatting and parsing sizes (binary multiples)
+const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
+const THRESHOLD: f64 = 1024.0;
+
+/// Formats bytes into a human-readable string
+pub fn format_bytes(bytes: u64) -> String {
+    if bytes == 0 {
+        return "0 B".to_string();
+    }
+
+    let bytes_f = bytes as f64;
+    let unit_index = (bytes_f.log10() / THRESHOLD.log10()).floor() as usize;
+    let unit_index = unit_index.min(UNITS.len() - 1);
+    let scaled = bytes_f / THRESHOLD.powi(unit_index as i32);
+
+    if unit_index == 0 {
+        format!("{} {}", bytes, UNITS[unit_index])
+    } else {
+        format!("{:.2} {}", scaled, UNITS[unit_index])
+    }
+}
+
+/// Formats bytes compactly with one decimal and a single-letter unit, e.g. "2.1G" or "800M"
+pub fn format_bytes_compact(bytes: u64) -> String {
+    if bytes < 1024 {
+        return format!("{}B", bytes);
+    }
+
+    let bytes_f = bytes as f64;
+    let unit_index = ((bytes_f.log10() / THRESHOLD.log10()).floor() as usize).min(UNITS.len() - 1);
+    let scaled = bytes_f / THRESHOLD.powi(unit_index as i32);
+    let unit = &UNITS[unit_index][..1];
+
+    if scaled >= 100.0 {
+        format!("{:.0}{}", scaled, unit)
+    } else {
+        format!("{:.1}{}", scaled, unit)
+    }
+}
+
+/// Parses a human-readable size such as `20GB`, `1.5 G` or `512MiB` into bytes
+///
+/// Units are case-insensitive binary multiples, matching `format_bytes`;
+/// a bare number is taken as bytes.
+pub fn parse_size(input: &str) -> Result<u64, String> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+
+    let value: f64 = number
+        .parse()
+        .map_err(|_| format!("Invalid size: {:?}", input))?;
+
+    let exponent = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 0,
+        "K" | "KB" | "KIB" => 1,
+        "M" | "MB" | "MIB" => 2,
+        "G" | "GB" | "GIB" => 3,
+        "T" | "TB" | "TIB" => 4,
+        other => return Err(format!("Unknown size unit {:?} in {:?}", other, input)),
+    };
+
+    Ok((value * THRESHOLD.powi(exponent)) as u64)
+}
@@ -0,0 +1,132 @@
+use serde::Serialize;
+use std::process::ExitCode;
+
+use crate::cleaner::targer_cleaner::CleanupResult;
+
+/// Process exit statuses, so wrappers can branch on the result of a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// Everything selected was cleaned (or would have been, in dry-run mode)
+    Success = 0,
+    /// A fatal error stopped the run
+    Error = 1,
+    /// Some target directories could not be deleted
+    PartialFailure = 2,
+    /// No target directories matched for cleaning
+    NothingToClean = 3,
+    /// The user quit without cleaning anything
+    Cancelled = 4,
+}
+
+impl ExitStatus {
+    /// Short machine-readable name used in the JSON summary
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExitStatus::Success => "success",
+            ExitStatus::Error => "error",
+            ExitStatus::PartialFailure => "partial_failure",
+            ExitStatus::NothingToClean => "nothing_to_clean",
+            ExitStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl From<ExitStatus> for ExitCode {
+    fn from(status: ExitStatus) -> Self {
+        ExitCode::from(status as u8)
+    }
+}
+
+/// Summary of a complete run
+#[derive(Debug, Default, Serialize)]
+pub struct RunSummary {
+    /// Total bytes freed (or that would be freed in dry-run mode)
+    pub bytes_freed: u64,
+    /// Number of target directories cleaned
+    pub projects_cleaned: usize,
+    /// Errors that occurred while cleaning
+    pub errors: Vec<String>,
+    /// Whether the run was a dry run
+    pub dry_run: bool,
+    /// Growth of free space on the affected filesystems, measured around a live cleanup
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub space_reclaimed: Option<u64>,
+    /// Whether the user quit before cleaning anything
+    #[serde(skip)]
+    pub cancelled: bool,
+}
+
+impl RunSummary {
+    /// Builds a summary from the result of a cleanup operation
+    pub fn from_cleanup(result: CleanupResult, dry_run: bool) -> Self {
+        Self {
+            bytes_freed: result.total_freed,
+            projects_cleaned: result.projects_cleaned,
+            errors: result.errors,
+            dry_run,
+            space_reclaimed: result.space_reclaimed,
+            cancelled: false,
+        }
+    }
+
+    /// Summary for a run that ended without any cleanup taking place
+    pub fn cancelled(dry_run: bool) -> Self {
+        Self {
+            dry_run,
+            cancelled: true,
+            ..Self::default()
+        }
+    }
+
+    /// Determines the exit status for this run
+    pub fn exit_status(&self) -> ExitStatus {
+        if self.cancelled {
+            ExitStatus::Cancelled
+        } else if !self.errors.is_empty() {
+            ExitStatus::PartialFailure
+        } else if self.projects_cleaned == 0 {
+            ExitStatus::NothingToClean
+        } else {
+            ExitStatus::Success
+        }
+    }
+
+    /// Serializes the summary as a single line of JSON
+    pub fn to_json_line(&self) -> Result<String, serde_json::Error> {
+        let mut value = serde_json::to_value(self)?;
+        value["status"] = self.exit_status().as_str().into();
+        serde_json::to_string(&value)
+    }
+}
+
+/// Cleanups performed in an interactive session, where dry-run mode can be switched
+///
+/// Results of one mode are combined; once a live cleanup happened, dry runs no longer
+/// count, so real and simulated bytes are never added up.
+#[derive(Debug, Default)]
+pub struct SessionCleanups {
+    result: Option<CleanupResult>,
+    dry_run: bool,
+}
+
+impl SessionCleanups {
+    /// Records the result of a cleanup run in the given mode
+    pub fn record(&mut self, result: CleanupResult, dry_run: bool) {
+        match &mut self.result {
+            Some(last) if self.dry_run == dry_run => last.merge(result),
+            Some(_) if dry_run => {}
+            _ => {
+                self.result = Some(result);
+                self.dry_run = dry_run;
+            }
+        }
+    }
+
+    /// Summary of the session; `dry_run` is the mode it ended in, reported if nothing was cleaned
+    pub fn into_summary(self, dry_run: bool) -> RunSummary {
+        match self.result {
+            Some(result) => RunSummary::from_cleanup(result, self.dry_run),
+            None => RunSummary::cancelled(dry_run),
+        }
+    }
+}
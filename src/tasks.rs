@@ -0,0 +1,271 @@
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use tokio::runtime::Runtime;
+use tokio::sync::{Semaphore, watch};
+use tokio::task::{JoinHandle, JoinSet};
+
+use crate::summary::ExitStatus;
+
+/// Error returned by work that stopped because it was cancelled
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cancelled")
+    }
+}
+
+impl Error for Cancelled {}
+
+/// Shared flag telling running tasks to stop at the next opportunity
+#[derive(Debug, Clone)]
+pub struct CancelToken {
+    sender: Arc<watch::Sender<bool>>,
+}
+
+impl Default for CancelToken {
+    fn default() -> Self {
+        let (sender, _) = watch::channel(false);
+        Self {
+            sender: Arc::new(sender),
+        }
+    }
+}
+
+impl CancelToken {
+    /// Asks every holder of the token to stop
+    pub fn cancel(&self) {
+        self.sender.send_replace(true);
+    }
+
+    /// Whether cancellation has been requested
+    pub fn is_cancelled(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    /// Completes once cancellation is requested
+    pub async fn cancelled(&self) {
+        let mut receiver = self.sender.subscribe();
+        receiver.wait_for(|&cancelled| cancelled).await.ok();
+    }
+}
+
+/// Runtime that scanning, sizing and cleaning run on as cancellable tasks
+///
+/// Blocking filesystem work runs on the runtime's blocking pool, limited to `jobs`
+/// concurrent jobs. Ctrl+C cancels whatever is running; with nothing running, the next
+/// task or prompt returns [`Cancelled`] so the run unwinds. A second Ctrl+C exits at once.
+pub struct Tasks {
+    runtime: Option<Runtime>,
+    cancel: CancelToken,
+    /// Limits how many blocking jobs run at once
+    limit: Arc<Semaphore>,
+    /// How many blocking jobs run at once
+    jobs: usize,
+    /// Number of task groups in progress
+    active: Arc<AtomicUsize>,
+}
+
+impl Tasks {
+    /// Starts the runtime, running at most `jobs` blocking jobs at once
+    pub fn new(jobs: usize) -> Result<Self, Box<dyn Error>> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+        let cancel = CancelToken::default();
+        let active = Arc::new(AtomicUsize::new(0));
+
+        runtime.spawn({
+            let cancel = cancel.clone();
+            let active = Arc::clone(&active);
+            async move {
+                while tokio::signal::ctrl_c().await.is_ok() {
+                    if cancel.is_cancelled() {
+                        std::process::exit(ExitStatus::Cancelled as i32);
+                    }
+                    if active.load(Ordering::SeqCst) > 0 {
+                        eprintln!("\nCancelling (press Ctrl+C again to quit immediately)...");
+                    }
+                    cancel.cancel();
+                }
+            }
+        });
+
+        Ok(Self {
+            runtime: Some(runtime),
+            cancel,
+            limit: Arc::new(Semaphore::new(jobs.max(1))),
+            jobs: jobs.max(1),
+            active,
+        })
+    }
+
+    /// How many blocking jobs run at once
+    pub fn jobs(&self) -> usize {
+        self.jobs
+    }
+
+    /// Spawns a future, e.g. a progress display, onto the runtime
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.runtime().spawn(future)
+    }
+
+    /// Waits for a spawned future to finish
+    pub fn wait<T>(&self, handle: JoinHandle<T>) -> Result<T, Box<dyn Error>> {
+        Ok(self.runtime().block_on(handle)?)
+    }
+
+    /// Runs a blocking job on the pool and waits for it
+    ///
+    /// Cancellation is cooperative: the job receives the token and is expected to
+    /// return early, so partial results (like half a cleanup) are still reported.
+    pub fn run_blocking<T>(
+        &self,
+        job: impl FnOnce(CancelToken) -> T + Send + 'static,
+    ) -> Result<T, Box<dyn Error>>
+    where
+        T: Send + 'static,
+    {
+        let _active = ActiveGuard::new(&self.active);
+        let cancel = self.cancel.clone();
+        self.runtime().block_on(async {
+            if cancel.is_cancelled() {
+                return Err(Cancelled.into());
+            }
+            let _permit = self.limit.acquire().await?;
+            Ok(tokio::task::spawn_blocking(move || job(cancel)).await?)
+        })
+    }
+
+    /// Waits for an answer read by `ask`, returning [`Cancelled`] once Ctrl+C is pressed
+    ///
+    /// The read itself cannot be interrupted, so it is left behind on the blocking pool.
+    pub fn prompt<T>(
+        &self,
+        ask: impl FnOnce() -> io::Result<T> + Send + 'static,
+    ) -> Result<T, Box<dyn Error>>
+    where
+        T: Send + 'static,
+    {
+        self.runtime().block_on(async {
+            if self.cancel.is_cancelled() {
+                return Err(Cancelled.into());
+            }
+            tokio::select! {
+                answer = tokio::task::spawn_blocking(ask) => Ok(answer??),
+                _ = self.cancel.cancelled() => Err(Cancelled.into()),
+            }
+        })
+    }
+
+    /// Runs a job on the current thread, letting Ctrl+C cancel it through the token
+    pub fn run_local<T>(&self, job: impl FnOnce(CancelToken) -> T) -> T {
+        let _active = ActiveGuard::new(&self.active);
+        job(self.cancel.clone())
+    }
+
+    /// Runs `job` on every item, at most `jobs` at a time, keeping the input order
+    ///
+    /// `progress` is called with `(done, total)` as items complete. Once cancelled, no new
+    /// items start and the call returns [`Cancelled`] without waiting for running ones.
+    pub fn map_blocking<I, T>(
+        &self,
+        items: Vec<I>,
+        job: impl Fn(I) -> T + Send + Sync + 'static,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<Vec<T>, Box<dyn Error>>
+    where
+        I: Send + 'static,
+        T: Send + 'static,
+    {
+        let _active = ActiveGuard::new(&self.active);
+        let total = items.len();
+        let job = Arc::new(job);
+
+        self.runtime().block_on(async {
+            if self.cancel.is_cancelled() {
+                return Err(Cancelled.into());
+            }
+            let mut set = JoinSet::new();
+            for (index, item) in items.into_iter().enumerate() {
+                let (job, limit, cancel) = (
+                    Arc::clone(&job),
+                    Arc::clone(&self.limit),
+                    self.cancel.clone(),
+                );
+                set.spawn(async move {
+                    let _permit = limit.acquire_owned().await.ok()?;
+                    if cancel.is_cancelled() {
+                        return None;
+                    }
+                    let value = tokio::task::spawn_blocking(move || job(item)).await;
+                    Some(value.map(|value| (index, value)))
+                });
+            }
+
+            let mut results = Vec::with_capacity(total);
+            progress(0, total);
+            loop {
+                tokio::select! {
+                    next = set.join_next() => match next {
+                        Some(Ok(Some(result))) => {
+                            results.push(result?);
+                            progress(results.len(), total);
+                        }
+                        Some(Ok(None)) => {}
+                        Some(Err(e)) => return Err(e.into()),
+                        None => break,
+                    },
+                    _ = self.cancel.cancelled() => {
+                        set.abort_all();
+                        return Err(Cancelled.into());
+                    }
+                }
+            }
+
+            results.sort_by_key(|(index, _)| *index);
+            Ok(results.into_iter().map(|(_, value)| value).collect())
+        })
+    }
+
+    fn runtime(&self) -> &Runtime {
+        self.runtime
+            .as_ref()
+            .expect("runtime is only taken on drop")
+    }
+}
+
+impl Drop for Tasks {
+    fn drop(&mut self) {
+        // Don't wait for jobs abandoned by a cancellation
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+/// Marks a task group as in progress while alive
+struct ActiveGuard<'a>(&'a AtomicUsize);
+
+impl<'a> ActiveGuard<'a> {
+    fn new(active: &'a AtomicUsize) -> Self {
+        active.fetch_add(1, Ordering::SeqCst);
+        Self(active)
+    }
+}
+
+impl Drop for ActiveGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,124 @@
+use std::cell::Cell;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Parts of a run whose duration is reported by `--timing`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Walking the filesystem for projects
+    Discovery,
+    /// Measuring target directory sizes
+    Sizing,
+    /// Determining when targets were last used
+    Staleness,
+    /// Drawing the terminal UI
+    Rendering,
+    /// Removing target directories
+    Cleaning,
+}
+
+const PHASES: [Phase; 5] = [
+    Phase::Discovery,
+    Phase::Sizing,
+    Phase::Staleness,
+    Phase::Rendering,
+    Phase::Cleaning,
+];
+
+impl Phase {
+    fn name(self) -> &'static str {
+        match self {
+            Phase::Discovery => "discovery",
+            Phase::Sizing => "sizing",
+            Phase::Staleness => "staleness",
+            Phase::Rendering => "rendering",
+            Phase::Cleaning => "cleaning",
+        }
+    }
+}
+
+/// Accumulated time and number of measurements per phase
+#[derive(Debug, Clone, Copy, Default)]
+struct Totals {
+    elapsed: Duration,
+    count: u64,
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static TOTALS: Mutex<[Totals; PHASES.len()]> = Mutex::new(
+    [Totals {
+        elapsed: Duration::ZERO,
+        count: 0,
+    }; PHASES.len()],
+);
+static STARTED: Mutex<Option<Instant>> = Mutex::new(None);
+
+thread_local! {
+    /// Phase being measured on this thread and when it last resumed
+    static CURRENT: Cell<Option<(Phase, Instant)>> = const { Cell::new(None) };
+}
+
+/// Turns on timing collection for the rest of the run
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+    *STARTED.lock().unwrap() = Some(Instant::now());
+}
+
+/// Runs `f`, attributing its duration to `phase`
+///
+/// Time spent in nested measurements counts only towards the innermost phase,
+/// so discovery excludes the sizing and staleness work it triggers.
+pub fn measure<T>(phase: Phase, f: impl FnOnce() -> T) -> T {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return f();
+    }
+
+    let parent = CURRENT.with(|current| current.replace(Some((phase, Instant::now()))));
+    if let Some((parent_phase, resumed)) = parent {
+        record(parent_phase, resumed.elapsed(), 0);
+    }
+
+    let result = f();
+
+    if let Some((_, resumed)) = CURRENT.with(|current| current.get()) {
+        record(phase, resumed.elapsed(), 1);
+    }
+    CURRENT.with(|current| {
+        current.set(parent.map(|(parent_phase, _)| (parent_phase, Instant::now())));
+    });
+    result
+}
+
+fn record(phase: Phase, elapsed: Duration, count: u64) {
+    let index = PHASES.iter().position(|&p| p == phase).unwrap_or(0);
+    let mut totals = TOTALS.lock().unwrap();
+    totals[index].elapsed += elapsed;
+    totals[index].count += count;
+}
+
+/// Formats the collected timings, or returns `None` when timing is off
+pub fn report() -> Option<String> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    let totals = *TOTALS.lock().unwrap();
+    let mut report = String::from("Timing:\n");
+    for (phase, totals) in PHASES.iter().zip(totals) {
+        report.push_str(&format!(
+            "  {:<10} {:>9.3}s  ({} calls)\n",
+            phase.name(),
+            totals.elapsed.as_secs_f64(),
+            totals.count
+        ));
+    }
+    if let Some(started) = *STARTED.lock().unwrap() {
+        report.push_str(&format!(
+            "  {:<10} {:>9.3}s\n",
+            "total",
+            started.elapsed().as_secs_f64()
+        ));
+    }
+    Some(report)
+}
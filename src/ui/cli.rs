@@ -0,0 +1,248 @@
+use crossterm::style::{Color, Stylize};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::error::Error;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::cleaner::targer_cleaner::{CleanEvent, CleanupResult};
+use crate::engine::EngineEvent;
+use crate::progress::{ProgressSink, Stage};
+use crate::scanner::rust_project::RustProject;
+use crate::scanner::rust_project_scaner::ScanDiagnostic;
+use crate::size::format_bytes;
+use crate::ui::UI;
+
+/// Whether colors may be used at all; cleared by `--no-color` or `NO_COLOR`
+static COLOR: AtomicBool = AtomicBool::new(true);
+
+/// Targets at least this large are highlighted as big
+const LARGE_BYTES: u64 = 1024 * 1024 * 1024;
+/// Targets at least this large are highlighted as notable
+const NOTABLE_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Disables colored output when requested by flag or by a non-empty `NO_COLOR`
+pub fn init_color(no_color: bool) {
+    let no_color_env = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    COLOR.store(!no_color && !no_color_env, Ordering::Relaxed);
+}
+
+/// Whether output to a stream should be colored, given whether it is a terminal
+pub fn use_color(is_terminal: bool) -> bool {
+    is_terminal && COLOR.load(Ordering::Relaxed)
+}
+
+/// Formats a size, colored by how large it is when `color` is set
+pub fn paint_size(bytes: u64, color: bool) -> String {
+    let text = format_bytes(bytes);
+    if !color {
+        return text;
+    }
+    let fg = if bytes >= LARGE_BYTES {
+        Color::Red
+    } else if bytes >= NOTABLE_BYTES {
+        Color::Yellow
+    } else {
+        Color::Green
+    };
+    text.with(fg).to_string()
+}
+
+/// Progress and summaries of the headless commands, written to stderr
+///
+/// Shows a progress bar while targets are sized or cleaned and collects what the
+/// engine reports, so stdout stays free for listings and JSON.
+pub struct CliUI {
+    /// Whether stderr output is colored
+    color: bool,
+    /// Whether cleanups only report what they would delete
+    dry_run: bool,
+    /// Bar of the scan or cleanup in progress
+    bar: Option<ProgressBar>,
+    /// Projects reported by the scan
+    projects: Vec<RustProject>,
+    /// Why the scan left projects out, when it hit a scan limit
+    scan_limited: Option<String>,
+    /// Projects the scan could not read, reported once it finishes
+    skipped: Vec<ScanDiagnostic>,
+    /// Result of the finished cleanup
+    cleanup: Option<CleanupResult>,
+}
+
+impl CliUI {
+    /// Creates the headless output for a run
+    pub fn new(dry_run: bool) -> Self {
+        Self {
+            color: use_color(std::io::IsTerminal::is_terminal(&std::io::stderr())),
+            dry_run,
+            bar: None,
+            projects: Vec::new(),
+            scan_limited: None,
+            skipped: Vec::new(),
+            cleanup: None,
+        }
+    }
+
+    /// Projects reported by the scan, leaving none behind
+    pub fn take_projects(&mut self) -> Vec<RustProject> {
+        std::mem::take(&mut self.projects)
+    }
+
+    /// Why the scan left projects out, if it hit a limit
+    pub fn scan_limited(&self) -> Option<&str> {
+        self.scan_limited.as_deref()
+    }
+
+    /// Result of the cleanup, once it has finished
+    pub fn take_cleanup_result(&mut self) -> Option<CleanupResult> {
+        self.cleanup.take()
+    }
+
+    /// Progress bar for the current operation, created on first use
+    fn bar(&mut self, action: &str, total: usize) -> &ProgressBar {
+        let color = self.color;
+        let bar = self.bar.get_or_insert_with(|| {
+            let template = if color {
+                "{msg} [{bar:30.cyan/blue}] {pos}/{len} {elapsed}"
+            } else {
+                "{msg} [{bar:30}] {pos}/{len} {elapsed}"
+            };
+            let bar = ProgressBar::new(total as u64);
+            if let Ok(style) = ProgressStyle::with_template(template) {
+                bar.set_style(style.progress_chars("=> "));
+            }
+            bar.set_message(action.to_string());
+            bar
+        });
+        bar.set_length(total as u64);
+        bar
+    }
+
+    fn finish_bar(&mut self) {
+        if let Some(bar) = self.bar.take() {
+            bar.finish_and_clear();
+        }
+    }
+
+    fn warn(&self, message: &str) {
+        self.above_bar(|| {
+            if self.color {
+                eprintln!("{}", format!("Warning: {}", message).yellow());
+            } else {
+                eprintln!("Warning: {}", message);
+            }
+        });
+    }
+
+    /// Prints a remark about the scan, kept off stdout so listings stay parseable
+    fn notice(&self, message: &str) {
+        self.above_bar(|| eprintln!("{}", message));
+    }
+
+    /// Prints with the progress bar, if any, moved out of the way
+    fn above_bar(&self, print: impl FnOnce()) {
+        match &self.bar {
+            Some(bar) => bar.suspend(print),
+            None => print(),
+        }
+    }
+
+    /// Prints a line of output to stdout, e.g. a project listed while the scan goes on
+    pub fn print(&self, line: &str) {
+        self.above_bar(|| println!("{}", line));
+    }
+
+    /// Prints a target that was cleaned; failures are printed with the result
+    fn print_cleaned(&self, event: &CleanEvent) {
+        if event.error.is_none() {
+            self.above_bar(|| println!("{}", event.describe()));
+        }
+    }
+
+    fn print_cleanup(&self, result: &CleanupResult) {
+        let verb = if self.dry_run { "Would free" } else { "Freed" };
+        eprintln!(
+            "{} {} from {} target directories",
+            verb,
+            paint_size(result.total_freed, self.color),
+            result.projects_cleaned
+        );
+        if let Some(reclaimed) = result.space_reclaimed {
+            eprintln!(
+                "Free space grew by {} on the affected filesystems",
+                paint_size(reclaimed, self.color)
+            );
+        }
+        if result.snapshots > 0 {
+            self.warn(&format!(
+                "{} local Time Machine snapshots still hold the removed files; the space is \
+                 released as macOS thins them (or run `tmutil thinlocalsnapshots /`)",
+                result.snapshots
+            ));
+        }
+        for error in &result.errors {
+            if self.color {
+                eprintln!("{}", format!("Error: {}", error).red());
+            } else {
+                eprintln!("Error: {}", error);
+            }
+        }
+        if !result.errors.is_empty() {
+            let failed = format!("{} target directories failed", result.errors.len());
+            if self.color {
+                eprintln!("{}", failed.red());
+            } else {
+                eprintln!("{}", failed);
+            }
+        }
+    }
+}
+
+impl ProgressSink for CliUI {
+    fn advance(&mut self, stage: Stage, done: usize, total: usize, _item: Option<&Path>) {
+        self.bar(stage.label(), total).set_position(done as u64);
+    }
+}
+
+impl UI for CliUI {
+    /// Nothing to drive interactively; only clears a bar left behind
+    fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        self.finish_bar();
+        Ok(())
+    }
+
+    fn on_event(&mut self, event: EngineEvent) {
+        match event {
+            EngineEvent::Progress {
+                stage,
+                done,
+                total,
+                item,
+            } => self.advance(stage, done, total, item.as_deref()),
+            EngineEvent::ProjectFound(project) => self.projects.push(*project),
+            EngineEvent::ProjectSkipped(diagnostic) => self.skipped.push(diagnostic),
+            EngineEvent::ScanFinished { projects, limited } => {
+                self.finish_bar();
+                for diagnostic in std::mem::take(&mut self.skipped) {
+                    self.warn(&format!(
+                        "Skipped {}: {}",
+                        diagnostic.path.display(),
+                        diagnostic.reason
+                    ));
+                }
+                eprintln!("Found {} Rust projects with target directories", projects);
+                if let Some(reason) = &limited {
+                    self.warn(&format!("Scan limit reached: {}", reason));
+                }
+                self.scan_limited = limited;
+            }
+            EngineEvent::Notice(message) => self.notice(&message),
+            EngineEvent::Warning(message) => self.warn(&message),
+            EngineEvent::TargetCleaned(event) => self.print_cleaned(&event),
+            EngineEvent::CleanFinished(result) => {
+                self.finish_bar();
+                self.print_cleanup(&result);
+                self.cleanup = Some(result);
+            }
+        }
+    }
+}
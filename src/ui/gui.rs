@@ -0,0 +1,264 @@
+use std::error::Error;
+
+use eframe::egui;
+
+use crate::cleaner::targer_cleaner::{CleanMode, CleanOptions};
+use crate::config::Config;
+use crate::engine::{Engine, EngineCommand, EngineEvent};
+use crate::scanner::rust_project::RustProject;
+use crate::size::format_bytes;
+use crate::summary::{RunSummary, SessionCleanups};
+use crate::ui::{UI, age_label, size_label};
+use crate::vfs;
+
+/// Windowed UI for the Rust target cleaner, for users who prefer it over a terminal
+pub struct CleanerGUI {
+    /// List of Rust projects found
+    projects: Vec<RustProject>,
+    /// Application configuration
+    config: Config,
+    /// Performs cleanups on behalf of the UI
+    engine: Engine,
+    /// Which projects are selected for cleaning, indexed like `projects`
+    selected: Vec<bool>,
+    /// Whether the confirmation dialog for a live cleanup is open
+    confirming: bool,
+    /// Status message to display
+    status_message: String,
+    /// Results of the cleanups performed this session
+    cleanups: SessionCleanups,
+}
+
+impl UI for CleanerGUI {
+    fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        eframe::run_native(
+            "clear-target",
+            eframe::NativeOptions::default(),
+            Box::new(|_| Ok(Box::new(Window { gui: self }))),
+        )
+        .map_err(|e| e.to_string().into())
+    }
+
+    fn on_event(&mut self, event: EngineEvent) {
+        match event {
+            EngineEvent::ProjectFound(project) => {
+                self.projects.push(*project);
+                self.selected.push(false);
+            }
+            EngineEvent::TargetCleaned(event)
+                if event.error.is_none()
+                    && !self.config.dry_run
+                    && !vfs::current().exists(&event.target) =>
+            {
+                // A cleaned target is gone, so it can no longer be selected
+                if let Some(i) = self.projects.iter().position(|p| p.path == event.project) {
+                    self.projects[i].target_info = None;
+                    self.selected[i] = false;
+                }
+            }
+            EngineEvent::CleanFinished(result) => {
+                self.status_message = format!(
+                    "{} {} from {} target directories, {} errors",
+                    if self.config.dry_run {
+                        "Would free"
+                    } else {
+                        "Freed"
+                    },
+                    format_bytes(result.total_freed),
+                    result.projects_cleaned,
+                    result.errors.len()
+                );
+                self.cleanups.record(result, self.config.dry_run);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl CleanerGUI {
+    /// Creates a new graphical UI instance
+    pub fn new(projects: Vec<RustProject>, engine: Engine) -> Result<Self, Box<dyn Error>> {
+        let config = engine.config().clone();
+
+        Ok(Self {
+            selected: vec![false; projects.len()],
+            projects,
+            config,
+            engine,
+            confirming: false,
+            status_message: String::new(),
+            cleanups: SessionCleanups::default(),
+        })
+    }
+
+    /// Takes the summary of the cleanups performed, in the mode each one ran in
+    pub fn take_summary(&mut self) -> RunSummary {
+        std::mem::take(&mut self.cleanups).into_summary(self.config.dry_run)
+    }
+
+    fn is_selectable(project: &RustProject) -> bool {
+        project
+            .target_info
+            .as_ref()
+            .is_some_and(|t| t.availability.reason().is_none())
+    }
+
+    /// Total size of the selected targets
+    fn selected_size(&self) -> u64 {
+        self.projects
+            .iter()
+            .zip(&self.selected)
+            .filter(|(_, selected)| **selected)
+            .filter_map(|(project, _)| project.target_info.as_ref())
+            .map(|target_info| target_info.size_bytes)
+            .sum()
+    }
+
+    /// Cleans the selected targets through the engine
+    fn clean_selected(&mut self) {
+        let command = EngineCommand::CleanRequested {
+            projects: self.projects.clone(),
+            selected: self.selected.clone(),
+            options: CleanOptions::from_config(&self.config),
+        };
+        let engine = self.engine.clone();
+        if let Err(e) = engine.handle(command, &mut |event| self.on_event(event)) {
+            self.status_message = format!("Error during cleanup: {}", e);
+        }
+    }
+
+    fn draw(&mut self, ui: &mut egui::Ui) {
+        egui::Panel::top("header").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.heading("Rust target directories");
+                ui.checkbox(&mut self.config.dry_run, "Dry run");
+            });
+        });
+
+        egui::Panel::bottom("actions").show(ui, |ui| self.draw_actions(ui));
+
+        egui::CentralPanel::default_margins().show(ui, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| self.draw_project_list(ui));
+        });
+
+        if self.confirming {
+            self.draw_confirmation(ui);
+        }
+    }
+
+    fn draw_actions(&mut self, ui: &mut egui::Ui) {
+        let count = self.selected.iter().filter(|&&s| s).count();
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "Selected: {}/{} | Space to free: {}",
+                count,
+                self.projects.len(),
+                format_bytes(self.selected_size())
+            ));
+
+            if ui.button("Select stale").clicked() {
+                for (project, selected) in self.projects.iter().zip(&mut self.selected) {
+                    *selected = Self::is_selectable(project)
+                        && project.target_info.as_ref().is_some_and(|t| t.is_stale);
+                }
+            }
+            if ui
+                .add_enabled(count > 0, egui::Button::new("Clean selected"))
+                .clicked()
+            {
+                if self.config.dry_run {
+                    self.clean_selected();
+                } else {
+                    self.confirming = true;
+                }
+            }
+        });
+        if !self.status_message.is_empty() {
+            ui.label(&self.status_message);
+        }
+    }
+
+    fn draw_project_list(&mut self, ui: &mut egui::Ui) {
+        for (project, selected) in self.projects.iter().zip(&mut self.selected) {
+            let Some(target_info) = &project.target_info else {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(&project.name).strong());
+                    ui.label(egui::RichText::new("cleaned").weak());
+                });
+                ui.separator();
+                continue;
+            };
+
+            ui.horizontal(|ui| {
+                ui.add_enabled(
+                    Self::is_selectable(project),
+                    egui::Checkbox::without_text(selected),
+                );
+                ui.label(egui::RichText::new(&project.name).strong());
+                if !project.tags.is_empty() {
+                    ui.label(
+                        egui::RichText::new(format!("[{}]", project.tags.join(", ")))
+                            .color(egui::Color32::from_rgb(200, 100, 200)),
+                    );
+                }
+                let size = ui.label(size_label(target_info));
+                if let Some(note) = target_info.breakdown.estimate_note() {
+                    size.on_hover_text(note);
+                }
+                let age = age_label(target_info.last_accessed);
+                if target_info.is_stale {
+                    ui.label(egui::RichText::new(age).color(egui::Color32::LIGHT_RED));
+                } else {
+                    ui.label(egui::RichText::new(age).color(egui::Color32::LIGHT_GREEN));
+                }
+            });
+            ui.label(egui::RichText::new(project.display_path()).small().weak());
+            if let Some(reason) = target_info.availability.reason() {
+                ui.label(
+                    egui::RichText::new(format!("Unavailable: {}", reason))
+                        .small()
+                        .color(egui::Color32::GRAY),
+                );
+            }
+            ui.separator();
+        }
+    }
+
+    fn draw_confirmation(&mut self, ui: &mut egui::Ui) {
+        let count = self.selected.iter().filter(|&&s| s).count();
+        let verb = match CleanMode::from_config(&self.config) {
+            CleanMode::Trash => "Move to the trash",
+            _ => "Permanently delete",
+        };
+        let prompt = format!(
+            "{} {} target directories ({})?",
+            verb,
+            count,
+            format_bytes(self.selected_size())
+        );
+
+        egui::Modal::new(egui::Id::new("confirm-clean")).show(ui.ctx(), |ui| {
+            ui.label(prompt);
+            ui.horizontal(|ui| {
+                if ui.button("Clean").clicked() {
+                    self.confirming = false;
+                    self.clean_selected();
+                }
+                if ui.button("Cancel").clicked() {
+                    self.confirming = false;
+                }
+            });
+        });
+    }
+}
+
+/// eframe application borrowing the UI for the lifetime of the window
+struct Window<'a> {
+    gui: &'a mut CleanerGUI,
+}
+
+impl eframe::App for Window<'_> {
+    fn ui(&mut self, ui: &mut egui::Ui, _frame: &mut eframe::Frame) {
+        self.gui.draw(ui);
+    }
+}
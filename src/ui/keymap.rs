@@ -0,0 +1,294 @@
+use crossterm::event::KeyCode;
+
+use crate::cleaner::scope::CleanScope;
+use crate::config::Config;
+use crate::size::format_bytes_compact;
+use crate::ui::tui::UIMode;
+
+/// A key binding as shown in the hint line
+pub struct KeyHint {
+    /// Keys triggering the action
+    pub keys: &'static str,
+    /// What the keys do
+    pub action: &'static str,
+}
+
+const fn hint(keys: &'static str, action: &'static str) -> KeyHint {
+    KeyHint { keys, action }
+}
+
+/// What a key does while browsing the project list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrowseAction {
+    /// Moves the cursor; Shift extends a range selection
+    Move,
+    /// Starts or finishes a visual range selection
+    VisualRange,
+    /// Undoes the range selection in progress
+    UndoRange,
+    Select,
+    /// Cleans the selected projects, limited to a part of their targets if given
+    Clean(Option<CleanScope>),
+    SavePlan,
+    CleanNow,
+    Expand,
+    ExactSize,
+    EditTags,
+    Filter,
+    BuildMark,
+    Keep,
+    ShowEmpty,
+    Sort,
+    Compact,
+    Trash,
+    DryRun,
+    Log,
+    ScrollLog,
+    ScanErrors,
+    Quit,
+}
+
+/// A key binding while browsing
+struct Binding {
+    /// Keys triggering the action
+    codes: &'static [KeyCode],
+    /// How the binding is listed in the hint line, if it is
+    hint: Option<KeyHint>,
+    action: BrowseAction,
+}
+
+const fn bind(
+    codes: &'static [KeyCode],
+    keys: &'static str,
+    action_label: &'static str,
+    action: BrowseAction,
+) -> Binding {
+    Binding {
+        codes,
+        hint: Some(hint(keys, action_label)),
+        action,
+    }
+}
+
+const fn unlisted(codes: &'static [KeyCode], action: BrowseAction) -> Binding {
+    Binding {
+        codes,
+        hint: None,
+        action,
+    }
+}
+
+/// Position of the quick clean key among the browse hints, next to the other cleanups
+const QUICK_CLEAN_HINT_AT: usize = 6;
+
+/// Browse bindings in hint line order; both the hints and the key handling come from here
+const BROWSE: &[Binding] = &[
+    bind(
+        &[KeyCode::Up, KeyCode::Down],
+        "↑/↓",
+        "move",
+        BrowseAction::Move,
+    ),
+    bind(
+        &[KeyCode::Char(' ')],
+        "Space",
+        "select",
+        BrowseAction::Select,
+    ),
+    bind(
+        &[KeyCode::Char('v')],
+        "Shift+↑/↓ v",
+        "range",
+        BrowseAction::VisualRange,
+    ),
+    bind(
+        &[KeyCode::Enter],
+        "Enter",
+        "clean selected",
+        BrowseAction::Clean(None),
+    ),
+    bind(
+        &[KeyCode::Char('w')],
+        "w",
+        "save plan",
+        BrowseAction::SavePlan,
+    ),
+    bind(
+        &[KeyCode::Char('D')],
+        "D",
+        "clean now",
+        BrowseAction::CleanNow,
+    ),
+    bind(
+        &[KeyCode::Char('i')],
+        "i",
+        "clean incremental",
+        BrowseAction::Clean(Some(CleanScope::Incremental)),
+    ),
+    bind(
+        &[KeyCode::Char('o')],
+        "o",
+        "clean docs",
+        BrowseAction::Clean(Some(CleanScope::Doc)),
+    ),
+    bind(
+        &[KeyCode::Char('a')],
+        "a",
+        "clean rust-analyzer",
+        BrowseAction::Clean(Some(CleanScope::RustAnalyzer)),
+    ),
+    bind(
+        &[KeyCode::Char('p')],
+        "p",
+        "clean dist/pkg",
+        BrowseAction::Clean(Some(CleanScope::Web)),
+    ),
+    bind(
+        &[KeyCode::Char('m')],
+        "m",
+        "clean .embuild",
+        BrowseAction::Clean(Some(CleanScope::Embedded)),
+    ),
+    bind(
+        &[KeyCode::Char('B')],
+        "B",
+        "clean benchmarks",
+        BrowseAction::Clean(Some(CleanScope::Benchmarks)),
+    ),
+    bind(
+        &[KeyCode::Right, KeyCode::Left, KeyCode::Char('e')],
+        "→/←",
+        "expand",
+        BrowseAction::Expand,
+    ),
+    bind(
+        &[KeyCode::Char('x')],
+        "x",
+        "exact size",
+        BrowseAction::ExactSize,
+    ),
+    bind(&[KeyCode::Char('#')], "#", "tags", BrowseAction::EditTags),
+    bind(&[KeyCode::Char('f')], "f", "filter", BrowseAction::Filter),
+    bind(
+        &[KeyCode::Char('b')],
+        "b",
+        "build mark",
+        BrowseAction::BuildMark,
+    ),
+    bind(&[KeyCode::Char('k')], "k", "keep", BrowseAction::Keep),
+    bind(
+        &[KeyCode::Char('E')],
+        "E",
+        "show empty",
+        BrowseAction::ShowEmpty,
+    ),
+    bind(&[KeyCode::Char('s')], "s", "sort", BrowseAction::Sort),
+    bind(&[KeyCode::Char('z')], "z", "compact", BrowseAction::Compact),
+    bind(&[KeyCode::Char('t')], "t", "trash", BrowseAction::Trash),
+    bind(&[KeyCode::Char('d')], "d", "dry run", BrowseAction::DryRun),
+    bind(&[KeyCode::Char('l')], "l", "log", BrowseAction::Log),
+    bind(
+        &[KeyCode::Char('!')],
+        "!",
+        "scan errors",
+        BrowseAction::ScanErrors,
+    ),
+    bind(&[KeyCode::Char('q')], "q", "quit", BrowseAction::Quit),
+    unlisted(&[KeyCode::Esc], BrowseAction::UndoRange),
+    unlisted(
+        &[KeyCode::PageUp, KeyCode::PageDown],
+        BrowseAction::ScrollLog,
+    ),
+];
+
+/// Action bound to a key while browsing, apart from Ctrl+C and the quick clean key
+pub fn browse_action(code: KeyCode) -> Option<BrowseAction> {
+    BROWSE
+        .iter()
+        .find(|binding| binding.codes.contains(&code))
+        .map(|binding| binding.action)
+}
+
+/// Whether a character has an action of its own while browsing, so the quick clean key cannot take it
+pub fn is_browse_key(c: char) -> bool {
+    browse_action(KeyCode::Char(c)).is_some()
+}
+
+const CONFIRM: &[KeyHint] = &[
+    hint("y", "confirm"),
+    hint("d", "dry run"),
+    hint("n/Esc", "cancel"),
+];
+
+const TYPED_CONFIRM: &[KeyHint] = &[hint("Enter", "confirm"), hint("Esc/Ctrl+C", "cancel")];
+
+const COUNTDOWN: &[KeyHint] = &[hint("Esc/n", "abort")];
+
+const COMPLETE: &[KeyHint] = &[
+    hint("r", "back to list"),
+    hint("d", "dry run"),
+    hint("Enter/q", "exit"),
+];
+
+const RESTORE: &[KeyHint] = &[
+    hint("↑/↓", "move"),
+    hint("Enter/r", "restore"),
+    hint("Esc", "back"),
+    hint("q", "quit"),
+];
+
+const EDIT_TAGS: &[KeyHint] = &[hint("Enter", "save"), hint("Esc", "cancel")];
+
+const YES_NO: &[KeyHint] = &[hint("y", "yes"), hint("n/Esc", "no")];
+
+/// Key bindings available in a UI mode
+pub fn hints(mode: &UIMode) -> Vec<&'static KeyHint> {
+    let hints: &[KeyHint] = match mode {
+        UIMode::Browse => {
+            return BROWSE
+                .iter()
+                .filter_map(|binding| binding.hint.as_ref())
+                .collect();
+        }
+        UIMode::Confirm => CONFIRM,
+        UIMode::Countdown => COUNTDOWN,
+        UIMode::Complete => COMPLETE,
+        UIMode::Restore => RESTORE,
+        UIMode::EditTags => EDIT_TAGS,
+        UIMode::ConfirmQuit => YES_NO,
+        UIMode::Cleaning | UIMode::Quit => &[],
+    };
+    hints.iter().collect()
+}
+
+/// Renders the key bindings of a mode as a single hint line
+///
+/// Browsing also lists the configured quick clean key, after the cleanup keys.
+pub fn hint_line(mode: &UIMode, config: &Config) -> String {
+    let mut hints = render(hints(mode));
+    if *mode == UIMode::Browse {
+        hints.insert(
+            QUICK_CLEAN_HINT_AT.min(hints.len()),
+            format!(
+                "{} clean stale ≥ {}",
+                config.quick_clean_key,
+                format_bytes_compact(config.quick_clean_above)
+            ),
+        );
+    }
+    hints.join(" · ")
+}
+
+/// Renders the key bindings of a confirmation that must be typed
+///
+/// Every plain character is part of the answer, so only Enter and cancelling are listed.
+pub fn typed_confirm_hint_line() -> String {
+    render(TYPED_CONFIRM).join(" · ")
+}
+
+fn render<'a>(hints: impl IntoIterator<Item = &'a KeyHint>) -> Vec<String> {
+    hints
+        .into_iter()
+        .map(|hint| format!("{} {}", hint.keys, hint.action))
+        .collect()
+}
@@ -0,0 +1,43 @@
+use chrono::{DateTime, Local};
+
+/// A single action recorded during the session
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    /// When the action happened
+    pub time: DateTime<Local>,
+    /// What happened
+    pub message: String,
+    /// Whether the action failed
+    pub is_error: bool,
+}
+
+/// Timestamped history of the actions taken this session
+#[derive(Debug, Default)]
+pub struct ActionLog {
+    entries: Vec<LogEntry>,
+}
+
+impl ActionLog {
+    /// Records a successful action
+    pub fn info(&mut self, message: impl Into<String>) {
+        self.push(message.into(), false);
+    }
+
+    /// Records a failure
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.push(message.into(), true);
+    }
+
+    /// All recorded entries, oldest first
+    pub fn entries(&self) -> &[LogEntry] {
+        &self.entries
+    }
+
+    fn push(&mut self, message: String, is_error: bool) {
+        self.entries.push(LogEntry {
+            time: Local::now(),
+            message,
+            is_error,
+        });
+    }
+}
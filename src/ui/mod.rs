@@ -1,11 +1,75 @@
 use std::error::Error;
+use std::time::{Duration, SystemTime};
 
+use crate::config::StalenessMetric;
+use crate::engine::EngineEvent;
+use crate::scanner::target_finder::TargetInfo;
+use crate::size::format_bytes;
+
+pub mod cli;
+#[cfg(feature = "gui")]
+mod gui;
+mod keymap;
+mod log;
+mod pick;
+mod session;
+mod theme;
+pub mod tty;
 mod tui;
 
+pub use cli::CliUI;
+#[cfg(feature = "gui")]
+pub use gui::CleanerGUI;
+pub use keymap::is_browse_key;
+pub use pick::FuzzyPicker;
 pub use tui::CleanerTUI;
 
 /// Common UI trait for different UI implementations
 pub trait UI {
     /// Runs the UI and returns the result
     fn run(&mut self) -> Result<(), Box<dyn Error>>;
+
+    /// Reacts to an event reported by the engine while it handles a command
+    fn on_event(&mut self, event: EngineEvent);
+}
+
+/// Which timestamps the last use was taken from, when access times were asked for
+pub fn metric_note(wanted: StalenessMetric, used: StalenessMetric) -> &'static str {
+    match (wanted, used) {
+        (StalenessMetric::Atime, StalenessMetric::Atime) => " (by atime)",
+        (StalenessMetric::Atime, StalenessMetric::Mtime) => " (by mtime: noatime filesystem)",
+        (StalenessMetric::Mtime, _) => "",
+    }
+}
+
+/// Size of a target for display, marked with `~` when it was estimated from a sample
+pub fn size_label(target_info: &TargetInfo) -> String {
+    let approximate = if target_info.breakdown.is_estimated() {
+        "~"
+    } else {
+        ""
+    };
+    format!("{}{}", approximate, format_bytes(target_info.size_bytes))
+}
+
+/// Describes how long ago a target was last used, e.g. "3 days ago"
+pub fn age_label(last_accessed: SystemTime) -> String {
+    let duration_since = SystemTime::now()
+        .duration_since(last_accessed)
+        .unwrap_or_else(|_| Duration::from_secs(30 * 24 * 60 * 60));
+
+    if duration_since.as_secs() < 86400 {
+        "Today".to_string()
+    } else if duration_since.as_secs() < 2 * 86400 {
+        "Yesterday".to_string()
+    } else {
+        let days = duration_since.as_secs() / 86400;
+        if days < 30 {
+            format!("{} days ago", days)
+        } else if days < 365 {
+            format!("{} months ago", days / 30)
+        } else {
+            format!("{} years ago", days / 365)
+        }
+    }
 }
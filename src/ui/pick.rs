@@ -0,0 +1,338 @@
+use std::error::Error;
+use std::io::{self, Stdout};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{
+    EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+};
+use ratatui::{
+    Frame, Terminal,
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+};
+
+use crate::scanner::rust_project::RustProject;
+use crate::size::format_bytes;
+use crate::ui::theme;
+
+/// Bonus for a matched character directly following the previous match
+const CONSECUTIVE_BONUS: i64 = 8;
+/// Bonus for a match at the start of a word (after `/`, `-`, `_`, `.` or a space)
+const BOUNDARY_BONUS: i64 = 6;
+/// Penalty per skipped character between two matches
+const GAP_PENALTY: i64 = 1;
+
+/// Scores how well a query matches a candidate as a subsequence, fzf-style
+///
+/// Returns `None` when not every query character occurs in order, otherwise the
+/// score and the char positions matched. Matching ignores case unless the query
+/// contains an uppercase letter.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<(i64, Vec<usize>)> {
+    let smart_case = query.chars().any(char::is_uppercase);
+    let normalize = |c: char| {
+        if smart_case {
+            c
+        } else {
+            c.to_ascii_lowercase()
+        }
+    };
+
+    let chars: Vec<char> = candidate.chars().collect();
+    let mut positions = Vec::new();
+    let mut score = 0;
+    let mut next = 0;
+    for wanted in query.chars().filter(|c| !c.is_whitespace()).map(normalize) {
+        let found = (next..chars.len()).find(|&i| normalize(chars[i]) == wanted)?;
+        score += 1;
+        if positions.last().is_some_and(|&last| last + 1 == found) {
+            score += CONSECUTIVE_BONUS;
+        } else if let Some(&last) = positions.last() {
+            score -= GAP_PENALTY * (found - last - 1) as i64;
+        }
+        if found == 0 || matches!(chars[found - 1], '/' | '-' | '_' | '.' | ' ') {
+            score += BOUNDARY_BONUS;
+        }
+        positions.push(found);
+        next = found + 1;
+    }
+    Some((score, positions))
+}
+
+/// Minimal fuzzy finder over project names and paths for `clear-target pick`
+///
+/// Typing narrows the list, Tab marks projects, Enter accepts the marked ones
+/// (or the highlighted one when none are marked) and Esc cancels.
+pub struct FuzzyPicker {
+    state: PickerState,
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+}
+
+/// What the finder shows and which projects are marked
+struct PickerState {
+    /// Text searched for, as displayed to the user
+    candidates: Vec<String>,
+    /// Target size of each candidate
+    sizes: Vec<u64>,
+    /// Which candidates are marked
+    picked: Vec<bool>,
+    /// Current query
+    query: String,
+    /// Candidates matching the query, best first, with the matched char positions
+    matches: Vec<(usize, Vec<usize>)>,
+    /// Highlighted row of `matches`
+    list_state: ListState,
+    /// Describes what accepting will do, e.g. "dry run"
+    mode_label: String,
+}
+
+impl FuzzyPicker {
+    /// Opens the finder over the given projects, all of which must have a target
+    pub fn new(projects: &[RustProject], mode_label: &str) -> Result<Self, Box<dyn Error>> {
+        enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        execute!(stdout, EnterAlternateScreen)?;
+        let terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+        let mut state = PickerState {
+            candidates: projects
+                .iter()
+                .map(|project| format!("{}  {}", project.name, project.display_path()))
+                .collect(),
+            sizes: projects
+                .iter()
+                .map(|project| project.target_info.as_ref().map_or(0, |t| t.size_bytes))
+                .collect(),
+            picked: vec![false; projects.len()],
+            query: String::new(),
+            matches: Vec::new(),
+            list_state: ListState::default(),
+            mode_label: mode_label.to_string(),
+        };
+        state.update_matches();
+        Ok(Self { state, terminal })
+    }
+
+    /// Runs the finder; returns the indices of the accepted projects, or `None` if cancelled
+    pub fn run(&mut self) -> Result<Option<Vec<usize>>, Box<dyn Error>> {
+        let result = self.run_internal();
+        disable_raw_mode()?;
+        execute!(self.terminal.backend_mut(), LeaveAlternateScreen)?;
+        self.terminal.show_cursor()?;
+        result
+    }
+
+    fn run_internal(&mut self) -> Result<Option<Vec<usize>>, Box<dyn Error>> {
+        loop {
+            let state = &self.state;
+            self.terminal.draw(|f| {
+                Self::draw(f, state);
+                theme::adapt(f.buffer_mut());
+            })?;
+            let state = &mut self.state;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            match key {
+                KeyEvent {
+                    code: KeyCode::Esc, ..
+                }
+                | KeyEvent {
+                    code: KeyCode::Char('c' | 'g'),
+                    modifiers: KeyModifiers::CONTROL,
+                    ..
+                } => return Ok(None),
+                KeyEvent {
+                    code: KeyCode::Enter,
+                    ..
+                } => return Ok(Some(state.accepted())),
+                KeyEvent {
+                    code: KeyCode::Tab, ..
+                } => {
+                    state.toggle_highlighted();
+                    state.move_highlight(1);
+                }
+                KeyEvent {
+                    code: KeyCode::BackTab,
+                    ..
+                } => {
+                    state.toggle_highlighted();
+                    state.move_highlight(-1);
+                }
+                KeyEvent {
+                    code: KeyCode::Up, ..
+                }
+                | KeyEvent {
+                    code: KeyCode::Char('p' | 'k'),
+                    modifiers: KeyModifiers::CONTROL,
+                    ..
+                } => state.move_highlight(-1),
+                KeyEvent {
+                    code: KeyCode::Down,
+                    ..
+                }
+                | KeyEvent {
+                    code: KeyCode::Char('n' | 'j'),
+                    modifiers: KeyModifiers::CONTROL,
+                    ..
+                } => state.move_highlight(1),
+                KeyEvent {
+                    code: KeyCode::Backspace,
+                    ..
+                } => {
+                    state.query.pop();
+                    state.update_matches();
+                }
+                KeyEvent {
+                    code: KeyCode::Char('u'),
+                    modifiers: KeyModifiers::CONTROL,
+                    ..
+                } => {
+                    state.query.clear();
+                    state.update_matches();
+                }
+                KeyEvent {
+                    code: KeyCode::Char(c),
+                    modifiers,
+                    ..
+                } if !modifiers.contains(KeyModifiers::CONTROL) => {
+                    state.query.push(c);
+                    state.update_matches();
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl PickerState {
+    /// Marked candidates, or the highlighted one when nothing is marked
+    fn accepted(&self) -> Vec<usize> {
+        let picked: Vec<usize> = (0..self.picked.len()).filter(|&i| self.picked[i]).collect();
+        if !picked.is_empty() {
+            return picked;
+        }
+        self.highlighted().into_iter().collect()
+    }
+
+    fn highlighted(&self) -> Option<usize> {
+        self.list_state
+            .selected()
+            .and_then(|row| self.matches.get(row))
+            .map(|(index, _)| *index)
+    }
+
+    fn toggle_highlighted(&mut self) {
+        if let Some(index) = self.highlighted() {
+            self.picked[index] = !self.picked[index];
+        }
+    }
+
+    fn move_highlight(&mut self, delta: isize) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let row = self.list_state.selected().unwrap_or(0);
+        let row = row.saturating_add_signed(delta).min(self.matches.len() - 1);
+        self.list_state.select(Some(row));
+    }
+
+    /// Re-ranks the candidates against the query, keeping scan order for equal scores
+    fn update_matches(&mut self) {
+        let mut scored: Vec<(i64, usize, Vec<usize>)> = self
+            .candidates
+            .iter()
+            .enumerate()
+            .filter_map(|(index, candidate)| {
+                let (score, positions) = fuzzy_match(&self.query, candidate)?;
+                Some((score, index, positions))
+            })
+            .collect();
+        scored.sort_by_key(|(score, index, _)| (std::cmp::Reverse(*score), *index));
+        self.matches = scored
+            .into_iter()
+            .map(|(_, index, positions)| (index, positions))
+            .collect();
+        self.list_state
+            .select((!self.matches.is_empty()).then_some(0));
+    }
+}
+
+impl FuzzyPicker {
+    fn draw(f: &mut Frame, view: &PickerState) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Min(0),
+                Constraint::Length(1),
+            ])
+            .split(f.area());
+
+        let prompt = Paragraph::new(Line::from(vec![
+            Span::styled("> ", Style::default().fg(Color::Cyan)),
+            Span::raw(view.query.as_str()),
+        ]))
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            "Pick targets to clean ({}) {}/{}",
+            view.mode_label,
+            view.matches.len(),
+            view.candidates.len()
+        )));
+        f.render_widget(prompt, chunks[0]);
+        f.set_cursor_position((
+            chunks[0].x + 3 + view.query.chars().count() as u16,
+            chunks[0].y + 1,
+        ));
+
+        let items: Vec<ListItem> = view
+            .matches
+            .iter()
+            .map(|(index, positions)| {
+                let marker = if view.picked[*index] { "● " } else { "  " };
+                let mut spans = vec![
+                    Span::styled(marker, Style::default().fg(Color::Green)),
+                    Span::styled(
+                        format!("{:>10}  ", format_bytes(view.sizes[*index])),
+                        Style::default().fg(Color::Magenta),
+                    ),
+                ];
+                spans.extend(view.candidates[*index].chars().enumerate().map(|(i, c)| {
+                    if positions.contains(&i) {
+                        Span::styled(
+                            c.to_string(),
+                            Style::default()
+                                .fg(Color::Yellow)
+                                .add_modifier(Modifier::BOLD),
+                        )
+                    } else {
+                        Span::raw(c.to_string())
+                    }
+                }));
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+        let list = List::new(items)
+            .highlight_style(Style::default().bg(Color::DarkGray))
+            .highlight_symbol("▶");
+        f.render_stateful_widget(list, chunks[1], &mut view.list_state.clone());
+
+        let picked = view.picked.iter().filter(|p| **p).count();
+        let picked_size: u64 = (0..view.picked.len())
+            .filter(|&i| view.picked[i])
+            .map(|i| view.sizes[i])
+            .sum();
+        let help = format!(
+            " {} picked ({})  Tab: mark  Enter: clean  Esc: cancel",
+            picked,
+            format_bytes(picked_size)
+        );
+        f.render_widget(
+            Paragraph::new(help).style(Style::default().fg(Color::DarkGray)),
+            chunks[2],
+        );
+    }
+}
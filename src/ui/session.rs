@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::cache::cache_dir;
+use crate::ui::tui::SortMode;
+use crate::vfs;
+
+/// Name of the file the UI state is stored in, inside the cache directory
+const SESSION_FILE: &str = "session.toml";
+
+/// Browsing state saved on exit so the next launch reopens where the user left off
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Session {
+    /// Order in which projects were listed
+    #[serde(default)]
+    pub sort_mode: SortMode,
+    /// Tag the list was filtered by
+    pub tag_filter: Option<String>,
+    /// Display path of the highlighted project
+    pub highlighted: Option<String>,
+    /// Index of the first row shown in the project list
+    #[serde(default)]
+    pub scroll_offset: usize,
+}
+
+impl Session {
+    /// Loads the saved session, starting fresh if none was saved yet
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        let path = Self::file()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(toml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Writes the session to the cache directory, unless running a simulation
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        if vfs::current().is_virtual() {
+            return Ok(());
+        }
+        fs::write(Self::file()?, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    fn file() -> Result<PathBuf, Box<dyn Error>> {
+        Ok(cache_dir()?.join(SESSION_FILE))
+    }
+}
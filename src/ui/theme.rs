@@ -0,0 +1,201 @@
+use std::sync::OnceLock;
+
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier};
+
+use crate::ui::cli;
+
+/// How many colors the terminal can show, from none to 24-bit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorDepth {
+    /// No colors, only attributes such as bold and reverse video
+    Mono,
+    /// The 16 standard ANSI colors
+    Ansi16,
+    /// The xterm 256-color palette
+    Ansi256,
+    /// 24-bit RGB
+    TrueColor,
+}
+
+/// Color depth of the terminal, detected once
+static DEPTH: OnceLock<ColorDepth> = OnceLock::new();
+
+/// The 16 ANSI colors with their usual xterm values, for picking the nearest one
+const ANSI16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// Color depth of the terminal the UI is drawn on
+///
+/// Colors disabled by `--no-color` or `NO_COLOR` count as a monochrome terminal.
+pub fn depth() -> ColorDepth {
+    *DEPTH.get_or_init(|| {
+        let var = |name| std::env::var(name).ok();
+        detect(
+            cli::use_color(true),
+            var("TERM").as_deref(),
+            var("COLORTERM").as_deref(),
+            var("WT_SESSION").is_some(),
+        )
+    })
+}
+
+/// Infers the color depth from `TERM` and `COLORTERM`
+///
+/// Windows Terminal (`WT_SESSION`) sets neither but supports 24-bit colors; other
+/// terminals that say nothing are assumed to know the 16 ANSI colors.
+fn detect(
+    color: bool,
+    term: Option<&str>,
+    colorterm: Option<&str>,
+    windows_terminal: bool,
+) -> ColorDepth {
+    let term = term.unwrap_or_default();
+    if !color || term == "dumb" {
+        ColorDepth::Mono
+    } else if matches!(colorterm, Some("truecolor" | "24bit"))
+        || term.ends_with("-direct")
+        || windows_terminal
+    {
+        ColorDepth::TrueColor
+    } else if term.contains("256color") {
+        ColorDepth::Ansi256
+    } else {
+        ColorDepth::Ansi16
+    }
+}
+
+/// Rewrites the colors of a drawn frame into ones the terminal can show
+///
+/// Styles are written for a color terminal; this is applied after drawing so each
+/// widget does not have to care. Without colors, a background marks a highlight
+/// (the selected row, the status bar) and is turned into reverse video.
+pub fn adapt(buffer: &mut Buffer) {
+    let depth = depth();
+    if depth == ColorDepth::TrueColor {
+        return;
+    }
+    for cell in &mut buffer.content {
+        if depth == ColorDepth::Mono {
+            if !matches!(cell.bg, Color::Reset | Color::Black) {
+                cell.modifier.insert(Modifier::REVERSED);
+            }
+            cell.fg = Color::Reset;
+            cell.bg = Color::Reset;
+        } else {
+            cell.fg = downgrade(cell.fg, depth);
+            cell.bg = downgrade(cell.bg, depth);
+        }
+    }
+}
+
+/// Nearest color available at `depth`
+fn downgrade(color: Color, depth: ColorDepth) -> Color {
+    match (color, depth) {
+        (Color::Rgb(r, g, b), ColorDepth::Ansi256) => Color::Indexed(cube_index(r, g, b)),
+        (Color::Rgb(r, g, b), ColorDepth::Ansi16) => nearest_ansi16((r, g, b)),
+        (Color::Indexed(index), ColorDepth::Ansi16) if index < 16 => ANSI16[index as usize].0,
+        (Color::Indexed(index), ColorDepth::Ansi16) => nearest_ansi16(indexed_rgb(index)),
+        _ => color,
+    }
+}
+
+/// Index of the closest color in the 6x6x6 cube of the 256-color palette
+fn cube_index(r: u8, g: u8, b: u8) -> u8 {
+    let level = |c: u8| {
+        if c < 48 {
+            0
+        } else if c < 115 {
+            1
+        } else {
+            (c - 35) / 40
+        }
+    };
+    16 + 36 * level(r) + 6 * level(g) + level(b)
+}
+
+/// RGB value of an entry of the 256-color palette beyond the first 16
+fn indexed_rgb(index: u8) -> (u8, u8, u8) {
+    if index >= 232 {
+        let gray = 8 + 10 * (index - 232);
+        return (gray, gray, gray);
+    }
+    let level = |l: u8| if l == 0 { 0 } else { 55 + 40 * l };
+    let cube = index - 16;
+    (level(cube / 36), level(cube / 6 % 6), level(cube % 6))
+}
+
+fn nearest_ansi16((r, g, b): (u8, u8, u8)) -> Color {
+    let distance = |(cr, cg, cb): (u8, u8, u8)| {
+        [(r, cr), (g, cg), (b, cb)]
+            .iter()
+            .map(|&(a, c)| (i32::from(a) - i32::from(c)).pow(2))
+            .sum::<i32>()
+    };
+    ANSI16
+        .iter()
+        .min_by_key(|(_, rgb)| distance(*rgb))
+        .map_or(Color::Reset, |(color, _)| *color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_the_color_depth() {
+        assert_eq!(
+            detect(false, Some("xterm-256color"), None, false),
+            ColorDepth::Mono
+        );
+        assert_eq!(detect(true, Some("dumb"), None, false), ColorDepth::Mono);
+        assert_eq!(
+            detect(true, Some("xterm-256color"), Some("truecolor"), false),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(detect(true, None, None, true), ColorDepth::TrueColor);
+        assert_eq!(
+            detect(true, Some("screen-256color"), None, false),
+            ColorDepth::Ansi256
+        );
+        assert_eq!(detect(true, Some("linux"), None, false), ColorDepth::Ansi16);
+        assert_eq!(detect(true, None, None, false), ColorDepth::Ansi16);
+    }
+
+    #[test]
+    fn downgrades_colors_to_the_palette() {
+        assert_eq!(
+            downgrade(Color::Rgb(200, 100, 200), ColorDepth::Ansi16),
+            Color::Magenta
+        );
+        assert_eq!(
+            downgrade(Color::Rgb(255, 0, 0), ColorDepth::Ansi256),
+            Color::Indexed(196)
+        );
+        assert_eq!(
+            downgrade(Color::Indexed(9), ColorDepth::Ansi16),
+            Color::LightRed
+        );
+        assert_eq!(
+            downgrade(Color::Indexed(232), ColorDepth::Ansi16),
+            Color::Black
+        );
+        assert_eq!(downgrade(Color::Yellow, ColorDepth::Ansi16), Color::Yellow);
+    }
+}
@@ -0,0 +1,70 @@
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, IsTerminal, Write};
+
+/// Device of the terminal a process was started from, whatever its streams are
+#[cfg(unix)]
+const TTY_INPUT: &str = "/dev/tty";
+#[cfg(unix)]
+const TTY_OUTPUT: &str = "/dev/tty";
+#[cfg(windows)]
+const TTY_INPUT: &str = "CONIN$";
+#[cfg(windows)]
+const TTY_OUTPUT: &str = "CONOUT$";
+
+/// How the process is connected to a terminal, deciding how the default command runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TtyMode {
+    /// stdout is a terminal: the full-screen UI can run
+    Interactive,
+    /// stdout is redirected (e.g. `clear-target | tee log`) but a terminal is attached:
+    /// plain output, with questions asked on the terminal
+    Prompt,
+    /// No terminal at all: plain output, nothing is asked
+    Batch,
+}
+
+/// Works out how to interact from where stdout goes and whether a terminal is attached
+///
+/// The terminal UI draws on stdout, so it only runs when stdout is a terminal; keys are
+/// read from the terminal even when stdin is not one.
+pub fn detect() -> TtyMode {
+    if io::stdout().is_terminal() {
+        TtyMode::Interactive
+    } else if io::stdin().is_terminal() && open().is_ok() {
+        TtyMode::Prompt
+    } else {
+        TtyMode::Batch
+    }
+}
+
+/// Refuses to start a full-screen command whose screen would end up in a redirected stdout
+pub fn require_interactive(command: &str) -> Result<(), Box<dyn Error>> {
+    if detect() == TtyMode::Interactive {
+        return Ok(());
+    }
+    Err(format!(
+        "`{}` needs a terminal on stdout; use `clear-target list` or `clear-target clean` in pipes and scripts",
+        command
+    )
+    .into())
+}
+
+/// Asks a question on the terminal, bypassing redirected streams, and returns the answer
+///
+/// An empty answer is returned when the terminal is closed before a line is entered.
+pub fn ask(question: &str) -> io::Result<String> {
+    let (input, mut output) = open()?;
+    write!(output, "{}", question)?;
+    output.flush()?;
+    let mut answer = String::new();
+    BufReader::new(input).read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
+
+/// Opens the terminal for reading answers and writing questions
+fn open() -> io::Result<(File, File)> {
+    let input = OpenOptions::new().read(true).write(true).open(TTY_INPUT)?;
+    let output = OpenOptions::new().write(true).open(TTY_OUTPUT)?;
+    Ok((input, output))
+}
@@ -1,6 +1,8 @@
+use std::cell::Cell;
 use std::error::Error;
 use std::io::{self, Stdout, Write};
-use std::time::{Duration, SystemTime};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 
 use crossterm::event::{KeyEvent, KeyModifiers};
 use crossterm::{
@@ -16,12 +18,131 @@ use ratatui::{
     text::{Line, Span},
     widgets::{Block, Borders, Gauge, List, ListItem, ListState, Paragraph, Wrap},
 };
+use serde::{Deserialize, Serialize};
+
+use crate::cache::audit::CleanupHistory;
+use crate::cache::tags::TagStore;
+use crate::cleaner::risk::{self, Risk, RiskLevel};
+use crate::cleaner::scope::CleanScope;
+use crate::cleaner::targer_cleaner::{CleanMode, CleanOptions, CleanupResult};
+use crate::cleaner::trash::{Trash, TrashEntry};
+use crate::cleaner::verify::{self, SizeChange};
+use crate::config::{Column, Config, StalenessMetric};
+use crate::engine::{Engine, EngineCommand, EngineEvent};
+use crate::progress::{ProgressSink, Stage};
+use crate::query::Query;
+use crate::scanner::rust_project::{BuildHealth, KEEP_MARKERS, RustProject};
+use crate::scanner::rust_project_scaner::ScanDiagnostic;
+use crate::scanner::target_finder::{TargetEntry, TargetFinder, device_label};
+use crate::size::format_bytes;
+use crate::summary::{RunSummary, SessionCleanups};
+use crate::timing::{self, Phase};
+use crate::ui::keymap::{self, BrowseAction};
+use crate::ui::log::ActionLog;
+use crate::ui::session::Session;
+use crate::ui::theme;
+use crate::ui::{UI, age_label, metric_note, size_label};
+use crate::vfs;
+
+/// File the plan is saved to with 'w', read by `clear-target apply` by default
+const PLAN_FILE: &str = "clear-target-plan.txt";
+/// Project names longer than this are shortened in the compact layout
+const COMPACT_NAME_WIDTH: usize = 32;
+
+/// Text of a compact layout column for a project
+fn column_text(project: &RustProject, column: Column) -> String {
+    let target_info = project.target_info.as_ref();
+    match column {
+        Column::Path => project.display_path(),
+        Column::Size => target_info.map_or_else(|| "No target".to_string(), size_label),
+        Column::Age => {
+            target_info.map_or_else(|| "N/A".to_string(), |t| age_label(t.last_accessed))
+        }
+        Column::Stale => match target_info {
+            Some(t) if t.is_stale => "stale".to_string(),
+            Some(_) => "fresh".to_string(),
+            None => String::new(),
+        },
+        Column::Triples => target_info
+            .map(|t| {
+                let triples: Vec<String> = t
+                    .triples
+                    .iter()
+                    .map(|triple| {
+                        match t.cross_builds.iter().find(|build| &build.triple == triple) {
+                            Some(build) => format!("{} ({})", triple, build.tool.label()),
+                            None => triple.clone(),
+                        }
+                    })
+                    .collect();
+                triples.join(", ")
+            })
+            .unwrap_or_default(),
+        Column::Files => target_info
+            .map(|t| match t.breakdown.files {
+                1 => "1 file".to_string(),
+                files => format!("{} files", files),
+            })
+            .unwrap_or_default(),
+        Column::Remote => project.remote.clone().unwrap_or_default(),
+    }
+}
+
+/// Details line listing directories or files kept next to a target, e.g. "Web outputs: dist/ 2 MB"
+fn companion_line(
+    label: &str,
+    paths: &[PathBuf],
+    size: u64,
+    suffix: &str,
+) -> Option<Line<'static>> {
+    if paths.is_empty() {
+        return None;
+    }
+    let names: Vec<String> = paths
+        .iter()
+        .map(|path| {
+            format!(
+                "{}{}",
+                path.file_name().unwrap_or_default().display(),
+                suffix
+            )
+        })
+        .collect();
+    Some(Line::from(Span::styled(
+        format!("  {}: {} {}", label, names.join(" "), format_bytes(size)),
+        Style::default().add_modifier(Modifier::DIM),
+    )))
+}
+
+/// Describes earlier cleanups of a project, e.g. "Last cleaned: 2 months ago, freed 4.10 GB"
+fn history_label(history: &CleanupHistory) -> String {
+    let mut label = format!(
+        "Last cleaned: {}, freed {}",
+        age_label(SystemTime::from(history.last_cleaned)).to_lowercase(),
+        format_bytes(history.last_freed)
+    );
+    if history.times_cleaned > 1 {
+        label.push_str(&format!(" ({} cleanups so far)", history.times_cleaned));
+    }
+    label
+}
 
-use crate::cleaner::targer_cleaner::TargetCleaner;
-use crate::config::Config;
-use crate::scanner::rust_project::RustProject;
-use crate::scanner::target_finder::TargetFinder;
-use crate::ui::UI;
+/// Color of a risk score in the confirm list
+fn risk_color(level: RiskLevel) -> Color {
+    match level {
+        RiskLevel::Low => Color::Green,
+        RiskLevel::Medium => Color::Yellow,
+        RiskLevel::High => Color::Red,
+    }
+}
+
+/// Describes what a cleanup of `count` selected projects removes, e.g. "3 target directories"
+fn cleanup_subject(scope: Option<CleanScope>, count: usize) -> String {
+    match scope {
+        None => format!("{} target directories", count),
+        Some(scope) => format!("the {} of {} targets", scope.label(), count),
+    }
+}
 
 /// Terminal UI for the Rust target cleaner
 pub struct CleanerTUI {
@@ -29,6 +150,8 @@ pub struct CleanerTUI {
     projects: Vec<RustProject>,
     /// Application configuration
     config: Config,
+    /// Performs cleanups on behalf of the UI
+    engine: Engine,
     /// Terminal interface
     terminal: Terminal<CrosstermBackend<Stdout>>,
     /// Current state of the application
@@ -38,12 +161,34 @@ pub struct CleanerTUI {
 /// Application state
 #[derive(Debug)]
 pub struct AppState {
-    /// Currently selected item in the list
+    /// Currently highlighted row, indexing into `visible`
     selected: usize,
+    /// Indices into `projects` of the rows shown, after filtering and sorting
+    visible: Vec<usize>,
+    /// Only show projects carrying this tag
+    tag_filter: Option<String>,
+    /// Order in which projects are listed
+    sort_mode: SortMode,
+    /// Tags assigned to projects, saved whenever they are edited
+    tag_store: TagStore,
+    /// Text typed so far while editing the tags of a project
+    tag_input: String,
+    /// Range selection being extended with Shift+arrows or in visual mode
+    range: Option<RangeSelection>,
+    /// History of the actions taken this session
+    log: ActionLog,
+    /// Whether the log pane is shown below the main view
+    show_log: bool,
+    /// How many entries the log pane is scrolled up from the newest
+    log_scroll: usize,
     /// List state for ratatui
     list_state: ListState,
+    /// First row shown in the project list, kept between frames so scrolling is stable
+    list_offset: Cell<usize>,
     /// Which projects are selected for cleaning
     selected_projects: Vec<bool>,
+    /// Selection set aside while a single project is cleaned with 'D'
+    saved_selection: Option<Vec<bool>>,
     /// Current UI mode
     mode: UIMode,
     /// Status message to display
@@ -52,6 +197,51 @@ pub struct AppState {
     total_freed_space: u64,
     /// Progress for cleanup operation
     cleanup_progress: f32,
+    /// Result of the most recent cleanup operation
+    cleanups: SessionCleanups,
+    /// Result of the cleanup that just finished, for the code that requested it
+    finished_cleanup: Option<CleanupResult>,
+    /// When the pending live cleanup starts, while counting down
+    countdown_deadline: Option<Instant>,
+    /// Text typed so far on a confirmation that requires typing
+    confirm_input: String,
+    /// Whether the pending confirmation must be typed
+    typed_confirm: bool,
+    /// Part of each target the pending cleanup is limited to, if any
+    scope: Option<CleanScope>,
+    /// Top-level target entries of expanded projects, indexed like `projects`
+    expanded_entries: Vec<Option<Vec<TargetEntry>>>,
+    /// Why the scan left projects out, shown as a banner above the list
+    scan_limited: Option<String>,
+    /// Whether projects without a target directory are listed
+    show_empty: bool,
+    /// Whether each project is listed on a single line
+    compact: bool,
+    /// Selected targets whose size changed since the scan, found when confirming
+    size_changes: Vec<SizeChange>,
+    /// Risk of cleaning each selected target, assessed when a cleanup is requested
+    risks: Vec<Risk>,
+    /// Projects the scan could not read, with why
+    diagnostics: Vec<ScanDiagnostic>,
+    /// Whether the scan diagnostics pane is shown below the main view, instead of the log
+    show_diagnostics: bool,
+    /// Entries shown in the restore view
+    trash_entries: Vec<TrashEntry>,
+    /// List state for the restore view
+    trash_list_state: ListState,
+}
+
+/// A range of rows being selected or deselected at once
+#[derive(Debug)]
+struct RangeSelection {
+    /// Row the range started at
+    anchor: usize,
+    /// Whether projects in the range get selected or deselected
+    value: bool,
+    /// Selection before the range started, restored for rows that leave the range
+    base: Vec<bool>,
+    /// Started with `v`, so plain arrow keys keep extending it
+    visual: bool,
 }
 
 /// UI modes
@@ -61,21 +251,190 @@ pub enum UIMode {
     Browse,
     /// Selection confirmation mode
     Confirm,
+    /// Grace period before a live cleanup starts
+    Countdown,
     /// Cleaning in progress
     Cleaning,
     /// Cleanup complete
     Complete,
+    /// Browsing trashed target directories to restore
+    Restore,
+    /// Editing the tags of the highlighted project
+    EditTags,
+    /// Asking whether to quit without cleaning
+    ConfirmQuit,
+    /// Exiting the application
+    Quit,
+}
+
+/// Order in which projects are listed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortMode {
+    /// Order in which the scanner found them
+    #[default]
+    Scan,
+    /// Grouped by first tag, untagged projects last
+    Tag,
+    /// Grouped by git remote host and owner, projects without a remote last
+    Remote,
+    /// Largest and longest unused targets first
+    ReclaimValue,
+}
+
+impl SortMode {
+    /// The sort mode selected after this one
+    fn next(self) -> Self {
+        match self {
+            SortMode::Scan => SortMode::Tag,
+            SortMode::Tag => SortMode::Remote,
+            SortMode::Remote => SortMode::ReclaimValue,
+            SortMode::ReclaimValue => SortMode::Scan,
+        }
+    }
+
+    /// Short name shown in the list title
+    fn label(self) -> &'static str {
+        match self {
+            SortMode::Scan => "scan order",
+            SortMode::Tag => "tag",
+            SortMode::Remote => "remote",
+            SortMode::ReclaimValue => "reclaim value",
+        }
+    }
+}
+
+/// Ranks how much deleting a project's target is worth: its size weighted by days unused
+///
+/// A day-old 10 GB target and a 100-day-old 100 MB target score about the same,
+/// so neither a huge active project nor a tiny abandoned one dominates the top.
+fn reclaim_value(project: &RustProject) -> u128 {
+    project.target_info.as_ref().map_or(0, |target_info| {
+        let unused_days = SystemTime::now()
+            .duration_since(target_info.last_accessed)
+            .unwrap_or_default()
+            .as_secs()
+            / 86400;
+        u128::from(target_info.size_bytes) * u128::from(unused_days + 1)
+    })
+}
+
+impl ProgressSink for CleanerTUI {
+    fn advance(&mut self, stage: Stage, done: usize, total: usize, item: Option<&Path>) {
+        match stage {
+            Stage::Sizing => {
+                self.state.status_message = format!("{} {}/{}", stage.label(), done, total);
+            }
+            Stage::Cleaning => {
+                self.state.status_message = match item {
+                    Some(item) => format!("Cleaned {}/{}: {}", done, total, item.display()),
+                    None => format!("Cleaned {}/{}", done, total),
+                };
+                self.state.cleanup_progress = done as f32 / total.max(1) as f32;
+                // A failed redraw only costs a progress update
+                self.redraw().ok();
+            }
+        }
+    }
 }
 
 impl UI for CleanerTUI {
     fn run(&mut self) -> Result<(), Box<dyn Error>> {
         self.run_internal()
     }
+
+    fn on_event(&mut self, event: EngineEvent) {
+        match event {
+            EngineEvent::Progress {
+                stage,
+                done,
+                total,
+                item,
+            } => self.advance(stage, done, total, item.as_deref()),
+            EngineEvent::ProjectFound(project) => {
+                self.projects.push(*project);
+                self.state.selected_projects.push(false);
+                self.state.expanded_entries.push(None);
+                self.rebuild_view();
+            }
+            EngineEvent::ProjectSkipped(diagnostic) => {
+                self.add_scan_diagnostics(vec![diagnostic]);
+            }
+            EngineEvent::ScanFinished { projects, limited } => {
+                self.state
+                    .log
+                    .info(format!("Scan found {} projects", projects));
+                if let Some(reason) = limited {
+                    self.show_scan_limit(&reason);
+                }
+            }
+            EngineEvent::Notice(message) => self.state.log.info(message),
+            EngineEvent::Warning(message) => self.state.log.error(format!("Warning: {}", message)),
+            EngineEvent::TargetCleaned(event) => {
+                // Failures are logged with the result, together with those found later
+                if event.error.is_none() {
+                    self.state.log.info(event.describe());
+                    // After a cancelled cleanup, what is still selected was left untouched
+                    if let Some(i) = self.projects.iter().position(|p| p.path == event.project) {
+                        self.state.selected_projects[i] = false;
+                        // A removed target leaves the list, or moves when sorted by size
+                        if !self.config.dry_run && !vfs::current().exists(&event.target) {
+                            self.projects[i].target_info = None;
+                            self.state.expanded_entries[i] = None;
+                            self.rebuild_view();
+                        } else if !self.config.dry_run
+                            && let Some(target_info) = &mut self.projects[i].target_info
+                        {
+                            match self.state.scope {
+                                Some(CleanScope::Web) => {
+                                    target_info.web_outputs.clear();
+                                    target_info.web_size = 0;
+                                }
+                                Some(CleanScope::Embedded) => {
+                                    target_info.embedded_dirs.clear();
+                                    target_info.embedded_size = 0;
+                                }
+                                Some(CleanScope::Benchmarks) => {
+                                    target_info.profiling_files.clear();
+                                    target_info.profiling_size = 0;
+                                }
+                                _ => {}
+                            }
+                            // Part of the target is left, so its size and breakdown changed
+                            if let Err(e) = TargetFinder::remeasure(target_info) {
+                                self.state.log.error(format!(
+                                    "Could not measure {}: {}",
+                                    target_info.path.display(),
+                                    e
+                                ));
+                            }
+                            self.state.expanded_entries[i] = None;
+                            self.rebuild_view();
+                        }
+                    }
+                }
+            }
+            EngineEvent::CleanFinished(result) => {
+                for error in &result.errors {
+                    self.state.log.error(error.clone());
+                }
+                self.record_cleanup(result.clone());
+                self.state.finished_cleanup = Some(result);
+            }
+        }
+    }
 }
 
 impl CleanerTUI {
     /// Creates a new terminal UI instance
-    pub fn new(projects: Vec<RustProject>, config: Config) -> Result<Self, Box<dyn Error>> {
+    pub fn new(projects: Vec<RustProject>, engine: Engine) -> Result<Self, Box<dyn Error>> {
+        let config = engine.config().clone();
+        // Loaded before the screen is taken over; a failure is reported in the action log
+        let (tag_store, tag_error) = match TagStore::load() {
+            Ok(store) => (store, None),
+            Err(e) => (TagStore::default(), Some(e.to_string())),
+        };
+
         // Clear terminal if configured
         if config.clear_terminal {
             print!("\x1B[2J\x1B[H");
@@ -89,75 +448,210 @@ impl CleanerTUI {
         let backend = CrosstermBackend::new(stdout);
         let terminal = Terminal::new(backend)?;
 
-        // Update target info with stale status
-        let mut updated_projects = Vec::new();
-        for project in projects {
-            if let Some(target_info) = &project.target_info {
-                let mut target_info_clone = target_info.clone();
-                TargetFinder::update_stale_status(&mut target_info_clone, config.stale_threshold)?;
-                let project_with_updated_target =
-                    project.clone().with_target_info(target_info_clone);
-                updated_projects.push(project_with_updated_target);
-            } else {
-                updated_projects.push(project.clone());
-            }
-        }
-
         // Initialize application state
-        let selected_projects = vec![false; updated_projects.len()];
+        let selected_projects = vec![false; projects.len()];
         let mut list_state = ListState::default();
         list_state.select(Some(0));
 
         let state = AppState {
             selected: 0,
+            visible: (0..projects.len()).collect(),
+            tag_filter: None,
+            sort_mode: SortMode::Scan,
+            tag_store,
+            tag_input: String::new(),
+            range: None,
+            log: ActionLog::default(),
+            show_log: false,
+            log_scroll: 0,
             list_state,
+            list_offset: Cell::new(0),
             selected_projects,
+            saved_selection: None,
             mode: UIMode::Browse,
-            status_message:
-                "Use arrow keys to navigate, Space to select, Enter to confirm, 'q' to quit"
-                    .to_string(),
+            status_message: String::new(),
             total_freed_space: 0,
             cleanup_progress: 0.0,
+            cleanups: SessionCleanups::default(),
+            finished_cleanup: None,
+            countdown_deadline: None,
+            confirm_input: String::new(),
+            typed_confirm: false,
+            scope: None,
+            expanded_entries: vec![None; projects.len()],
+            scan_limited: None,
+            show_empty: config.show_empty,
+            compact: config.compact,
+            size_changes: Vec::new(),
+            risks: Vec::new(),
+            diagnostics: Vec::new(),
+            show_diagnostics: false,
+            trash_entries: Vec::new(),
+            trash_list_state: ListState::default(),
         };
 
-        Ok(Self {
-            projects: updated_projects,
+        let mut tui = Self {
+            projects,
             config,
+            engine,
             terminal,
             state,
-        })
+        };
+        let stale = tui
+            .projects
+            .iter()
+            .filter(|p| p.target_info.as_ref().is_some_and(|t| t.is_stale))
+            .count();
+        tui.state.log.info(format!(
+            "Scan found {} projects ({} stale)",
+            tui.projects.len(),
+            stale
+        ));
+        if let Some(error) = tag_error {
+            tui.state
+                .log
+                .error(format!("Warning: Failed to load project tags: {}", error));
+        }
+        tui.rebuild_view();
+        tui.restore_session();
+        Ok(tui)
+    }
+
+    /// Reapplies the sort order, filter, highlight and scroll position of the previous run
+    fn restore_session(&mut self) {
+        let Ok(session) = Session::load() else {
+            return;
+        };
+
+        self.state.sort_mode = session.sort_mode;
+        // A filter on a tag nobody carries anymore would hide every project
+        self.state.tag_filter = session
+            .tag_filter
+            .filter(|tag| self.projects.iter().any(|p| p.tags.contains(tag)));
+        self.rebuild_view();
+
+        if let Some(position) = session.highlighted.and_then(|path| {
+            self.state
+                .visible
+                .iter()
+                .position(|&i| self.projects[i].display_path() == path)
+        }) {
+            self.state.selected = position;
+            self.state.list_state.select(Some(position));
+        }
+        self.state.list_offset.set(session.scroll_offset);
+    }
+
+    /// Saves the browsing state so the next launch reopens where this one left off
+    fn save_session(&self) -> Result<(), Box<dyn Error>> {
+        // Runs without projects (e.g. the restore view) have nothing worth keeping
+        if self.projects.is_empty() {
+            return Ok(());
+        }
+
+        Session {
+            sort_mode: self.state.sort_mode,
+            tag_filter: self.state.tag_filter.clone(),
+            highlighted: self
+                .current_project()
+                .map(|i| self.projects[i].display_path()),
+            scroll_offset: self.state.list_offset.get(),
+        }
+        .save()
+    }
+
+    /// Shows a warning banner saying the scan hit a limit and left projects out
+    pub fn show_scan_limit(&mut self, reason: &str) {
+        self.state.scan_limited = Some(reason.to_string());
+        self.state
+            .log
+            .error(format!("Scan limit reached: {}", reason));
+    }
+
+    /// Records projects the scan could not read, so the user can see why they are missing
+    pub fn add_scan_diagnostics(&mut self, diagnostics: Vec<ScanDiagnostic>) {
+        if diagnostics.is_empty() {
+            return;
+        }
+        for diagnostic in &diagnostics {
+            self.state.log.error(format!(
+                "Skipped {}: {}",
+                diagnostic.path.display(),
+                diagnostic.reason
+            ));
+        }
+        self.state.diagnostics.extend(diagnostics);
+        self.state.status_message = format!(
+            "{} projects could not be read; press '!' to see why",
+            self.state.diagnostics.len()
+        );
+    }
+
+    /// Selects the cleanable projects matching a selection expression
+    pub fn preselect(&mut self, selection: &Query) {
+        for (i, project) in self.projects.iter().enumerate() {
+            self.state.selected_projects[i] = project.target_info.is_some()
+                && self.unavailable_reason(i).is_none()
+                && selection.matches(project);
+        }
+        self.update_total_freed_space();
+        self.state.status_message = format!(
+            "Selected {} projects matching --select",
+            self.selected_count()
+        );
+        self.state.log.info(self.state.status_message.clone());
+    }
+
+    /// Switches to the view listing trashed target directories
+    pub fn open_restore_view(&mut self) -> Result<(), Box<dyn Error>> {
+        self.state.trash_entries = Trash::open()?.list()?;
+        self.state
+            .trash_list_state
+            .select((!self.state.trash_entries.is_empty()).then_some(0));
+        self.state.mode = UIMode::Restore;
+        self.state.status_message = if self.state.trash_entries.is_empty() {
+            "Trash is empty".to_string()
+        } else {
+            format!("{} entries in the trash", self.state.trash_entries.len())
+        };
+        Ok(())
+    }
+
+    /// Takes the summary of the cleanups performed, in the mode each one ran in
+    pub fn take_summary(&mut self) -> RunSummary {
+        std::mem::take(&mut self.state.cleanups).into_summary(self.config.dry_run)
     }
 
     /// Runs the terminal UI
     fn run_internal(&mut self) -> Result<(), Box<dyn Error>> {
         loop {
             // Draw the UI
+            self.redraw()?;
+
+            // Start the cleanup once the grace period has run out
+            if let Some(deadline) = self.state.countdown_deadline
+                && Instant::now() >= deadline
             {
-                let state = &self.state;
-                let projects = &self.projects;
-                let config = &self.config;
-                let total_freed_space = self.state.total_freed_space;
-                let status_message = &self.state.status_message;
-
-                self.terminal.draw(|f| {
-                    Self::draw_ui_static(
-                        f,
-                        state,
-                        projects,
-                        config,
-                        total_freed_space,
-                        status_message,
-                    );
-                })?;
+                self.state.countdown_deadline = None;
+                self.begin_cleaning()?;
+                continue;
             }
 
-            // Handle events
+            // Handle events, waking up regularly so countdowns keep ticking
+            if !event::poll(Duration::from_millis(100))? {
+                continue;
+            }
             if let Event::Key(key) = event::read()? {
                 match self.state.mode {
                     UIMode::Browse => self.handle_browse_mode(key)?,
                     UIMode::Confirm => self.handle_confirm_mode(key)?,
+                    UIMode::Countdown => self.handle_countdown_mode(key)?,
                     UIMode::Cleaning => self.handle_cleaning_mode(key)?,
                     UIMode::Complete => self.handle_complete_mode(key)?,
+                    UIMode::Restore => self.handle_restore_mode(key)?,
+                    UIMode::EditTags => self.handle_edit_tags_mode(key)?,
+                    UIMode::ConfirmQuit => self.handle_confirm_quit_mode(key)?,
+                    UIMode::Quit => {}
                 }
             }
 
@@ -169,89 +663,868 @@ impl CleanerTUI {
 
         // Restore terminal
         self.restore_terminal()?;
+        if let Err(e) = self.save_session() {
+            eprintln!("Warning: Failed to save session: {}", e);
+        }
+        Ok(())
+    }
+
+    /// Draws the current state to the terminal
+    fn redraw(&mut self) -> Result<(), Box<dyn Error>> {
+        let state = &self.state;
+        let projects = &self.projects;
+        let config = &self.config;
+        let total_freed_space = self.state.total_freed_space;
+        let status_message = &self.state.status_message;
+
+        timing::measure(Phase::Rendering, || {
+            self.terminal.draw(|f| {
+                Self::draw_ui_static(
+                    f,
+                    state,
+                    projects,
+                    config,
+                    total_freed_space,
+                    status_message,
+                );
+                theme::adapt(f.buffer_mut());
+            })
+        })?;
         Ok(())
     }
 
     /// Handles key events in browse mode
     fn handle_browse_mode(&mut self, key: event::KeyEvent) -> Result<(), Box<dyn Error>> {
-        match key {
-            KeyEvent {
-                code: KeyCode::Up, ..
-            } => {
-                if self.state.selected > 0 {
+        let action = if key.code == KeyCode::Char('c') && key.modifiers == KeyModifiers::CONTROL {
+            BrowseAction::Quit
+        } else if let Some(action) = keymap::browse_action(key.code) {
+            action
+        } else {
+            // Never one of the bound keys, which the configuration refuses
+            if let KeyCode::Char(c) = key.code
+                && c == self.config.quick_clean_key
+                && !key.modifiers.contains(KeyModifiers::CONTROL)
+            {
+                self.quick_clean();
+            }
+            return Ok(());
+        };
+
+        match action {
+            BrowseAction::Move => {
+                // Shift+arrows extend a range; plain arrows end one unless in visual mode
+                let extend = key.modifiers.contains(KeyModifiers::SHIFT);
+                if extend && self.state.range.is_none() {
+                    self.start_range(false);
+                } else if !extend && self.state.range.as_ref().is_some_and(|r| !r.visual) {
+                    self.state.range = None;
+                }
+
+                if key.code == KeyCode::Up && self.state.selected > 0 {
                     self.state.selected -= 1;
-                    self.state.list_state.select(Some(self.state.selected));
+                } else if key.code == KeyCode::Down
+                    && self.state.selected < self.state.visible.len().saturating_sub(1)
+                {
+                    self.state.selected += 1;
                 }
+                self.state.list_state.select(Some(self.state.selected));
+                self.apply_range();
             }
-            KeyEvent {
-                code: KeyCode::Down,
-                ..
-            } => {
-                if self.state.selected < self.projects.len().saturating_sub(1) {
-                    self.state.selected += 1;
-                    self.state.list_state.select(Some(self.state.selected));
+            BrowseAction::VisualRange => {
+                if self.state.range.take().is_some() {
+                    self.state.status_message = "Range selection finished".to_string();
+                    self.state
+                        .log
+                        .info(format!("{} projects selected", self.selected_count()));
+                } else {
+                    self.start_range(true);
+                    self.state.status_message =
+                        "Visual selection: move to extend the range, 'v' to finish, Esc to undo"
+                            .to_string();
                 }
             }
-            KeyEvent {
-                code: KeyCode::Char(' '),
-                ..
-            } => {
-                if !self.projects.is_empty() {
-                    self.state.selected_projects[self.state.selected] =
-                        !self.state.selected_projects[self.state.selected];
+            BrowseAction::UndoRange => {
+                if let Some(range) = self.state.range.take() {
+                    self.state.selected_projects = range.base;
                     self.update_total_freed_space();
+                    self.state.status_message = "Range selection undone".to_string();
+                }
+            }
+            BrowseAction::Select => {
+                if let Some(index) = self.current_project() {
+                    if let Some(reason) = self.unavailable_reason(index) {
+                        self.state.status_message =
+                            format!("Cannot select this project: target is {}", reason);
+                    } else {
+                        self.state.selected_projects[index] = !self.state.selected_projects[index];
+                        self.update_total_freed_space();
+                        let verb = if self.state.selected_projects[index] {
+                            "Selected"
+                        } else {
+                            "Deselected"
+                        };
+                        self.state.log.info(format!(
+                            "{} {}",
+                            verb,
+                            self.projects[index].display_path()
+                        ));
+                    }
+                }
+            }
+            BrowseAction::Clean(scope) => self.request_cleanup(scope),
+            BrowseAction::Expand => {
+                if let Some(index) = self.current_project() {
+                    let expand = match key.code {
+                        KeyCode::Right => true,
+                        KeyCode::Left => false,
+                        _ => self.state.expanded_entries[index].is_none(),
+                    };
+                    self.set_expanded(index, expand);
+                }
+            }
+            BrowseAction::ExactSize => {
+                if let Some(index) = self.current_project() {
+                    self.measure_exact(index);
+                }
+            }
+            BrowseAction::EditTags => {
+                if let Some(index) = self.current_project() {
+                    self.state.tag_input = self.projects[index].tags.join(", ");
+                    self.state.mode = UIMode::EditTags;
+                    self.state.status_message = self.tag_prompt();
+                }
+            }
+            BrowseAction::CleanNow => {
+                if let Some(index) = self.current_project() {
+                    self.prompt_clean_one(index);
+                }
+            }
+            BrowseAction::Filter => {
+                self.cycle_tag_filter();
+            }
+            BrowseAction::BuildMark => {
+                if let Some(index) = self.current_project() {
+                    self.cycle_build_health(index);
+                }
+            }
+            BrowseAction::Keep => {
+                if let Some(index) = self.current_project() {
+                    self.toggle_keep_marker(index);
+                }
+            }
+            BrowseAction::SavePlan => {
+                self.save_plan();
+            }
+            BrowseAction::ShowEmpty => {
+                self.state.show_empty = !self.state.show_empty;
+                self.rebuild_view();
+                self.state.status_message = if self.state.show_empty {
+                    "Showing projects without a target directory".to_string()
+                } else {
+                    "Hiding projects without a target directory".to_string()
+                };
+            }
+            BrowseAction::Compact => {
+                self.state.compact = !self.state.compact;
+                self.state.status_message = if self.state.compact {
+                    "Compact layout: one line per project".to_string()
+                } else {
+                    "Detailed layout".to_string()
+                };
+            }
+            BrowseAction::Sort => {
+                self.state.sort_mode = self.state.sort_mode.next();
+                self.rebuild_view();
+                self.state.status_message = format!("Sorted by {}", self.state.sort_mode.label());
+            }
+            BrowseAction::Trash => {
+                self.open_restore_view()?;
+            }
+            BrowseAction::Log => {
+                self.state.show_log = !self.state.show_log;
+                self.state.show_diagnostics = false;
+                self.state.log_scroll = 0;
+            }
+            BrowseAction::ScanErrors => {
+                self.state.show_diagnostics = !self.state.show_diagnostics;
+                self.state.show_log = false;
+                if self.state.diagnostics.is_empty() {
+                    self.state.status_message = "Every project found could be read".to_string();
                 }
             }
+            BrowseAction::ScrollLog if self.state.show_log => {
+                let max = self.state.log.entries().len().saturating_sub(1);
+                self.state.log_scroll = if key.code == KeyCode::PageUp {
+                    (self.state.log_scroll + 5).min(max)
+                } else {
+                    self.state.log_scroll.saturating_sub(5)
+                };
+            }
+            BrowseAction::ScrollLog => {}
+            BrowseAction::DryRun => self.toggle_dry_run(),
+            BrowseAction::Quit => {
+                self.state.mode = UIMode::ConfirmQuit;
+                self.state.status_message = "Quit without cleaning? (y/N)".to_string();
+            }
+        }
+        Ok(())
+    }
+
+    /// Asks for confirmation to clean the selected projects, optionally only part of each target
+    fn request_cleanup(&mut self, scope: Option<CleanScope>) {
+        if self.selected_count() > 0 {
+            self.state.size_changes.clear();
+            if self.config.verify_sizes && scope.is_none() {
+                self.state.size_changes =
+                    verify::verify_sizes(&mut self.projects, &self.state.selected_projects);
+                self.update_total_freed_space();
+                self.rebuild_view();
+            }
+            self.state.risks = risk::assess(
+                &self.projects,
+                &self.state.selected_projects,
+                self.config.rebuild_budget,
+            );
+            self.state.scope = scope;
+            self.state.mode = UIMode::Confirm;
+            self.state.confirm_input.clear();
+            self.show_confirm_prompt();
+        } else {
+            self.state.status_message =
+                "No projects selected. Use Space to select projects.".to_string();
+        }
+    }
+
+    /// Handles key events in confirmation mode
+    fn handle_confirm_mode(&mut self, key: event::KeyEvent) -> Result<(), Box<dyn Error>> {
+        if self.typed_confirmation_reason().is_some() {
+            return self.handle_typed_confirm(key);
+        }
+
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                self.start_cleanup()?;
+            }
+            KeyCode::Char('d') => {
+                self.toggle_dry_run();
+                self.state.confirm_input.clear();
+                self.show_confirm_prompt();
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.back_to_browse("Operation cancelled");
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Toggles between dry run and live mode
+    fn toggle_dry_run(&mut self) {
+        self.config.dry_run = !self.config.dry_run;
+        let mode = if self.config.dry_run {
+            "dry run"
+        } else {
+            "live"
+        };
+        self.state.status_message = format!("Switched to {} mode", mode);
+        self.state.log.info(format!("Switched to {} mode", mode));
+    }
+
+    /// Builds the confirmation prompt, spelling out whether files will really be removed
+    fn confirm_prompt(&self) -> String {
+        let selected_count = self.selected_count();
+        let subject = cleanup_subject(self.state.scope, selected_count);
+        let action = match CleanMode::from_config(&self.config) {
+            CleanMode::DryRun => {
+                return format!(
+                    "DRY RUN: simulate deleting {}? Nothing will be removed. (y/N, 'd' for live)",
+                    subject
+                );
+            }
+            _ if vfs::current().is_virtual() => {
+                return format!(
+                    "SIMULATED: delete {} in memory only? Nothing on disk changes. (y/N, 'd' for dry run)",
+                    subject
+                );
+            }
+            CleanMode::Delete => "permanently delete",
+            CleanMode::Trash => "move to the trash",
+        };
+
+        if let Some(reason) = self.typed_confirmation_reason() {
+            format!(
+                "LIVE: {} {} ({})? Type 'yes' or {} and press Enter, Esc to cancel: {}",
+                action, subject, reason, selected_count, self.state.confirm_input
+            )
+        } else {
+            format!("LIVE: {} {}? (y/N, 'd' for dry run)", action, subject)
+        }
+    }
+
+    /// Index into `projects` of the highlighted row, if any row is shown
+    fn current_project(&self) -> Option<usize> {
+        self.state.visible.get(self.state.selected).copied()
+    }
+
+    /// Starts a range at the highlighted row, flipping its selection state
+    fn start_range(&mut self, visual: bool) {
+        let Some(index) = self.current_project() else {
+            return;
+        };
+        self.state.range = Some(RangeSelection {
+            anchor: self.state.selected,
+            value: !self.state.selected_projects[index],
+            base: self.state.selected_projects.clone(),
+            visual,
+        });
+        self.apply_range();
+    }
+
+    /// Applies the range between its anchor and the highlighted row to the selection
+    fn apply_range(&mut self) {
+        let Some(range) = &self.state.range else {
+            return;
+        };
+
+        let (from, to) = if range.anchor <= self.state.selected {
+            (range.anchor, self.state.selected)
+        } else {
+            (self.state.selected, range.anchor)
+        };
+        let mut selected = range.base.clone();
+        for &index in &self.state.visible[from..=to] {
+            if self.unavailable_reason(index).is_none() {
+                selected[index] = range.value;
+            }
+        }
+
+        self.state.selected_projects = selected;
+        self.update_total_freed_space();
+    }
+
+    /// Recomputes which projects are shown and in what order, keeping the highlight in place
+    ///
+    /// Called whenever sizes, staleness or targets change, so the list stays sorted and
+    /// filtered. The highlight follows the project it was on, identified by its path
+    /// rather than its row, and stays on the same line of the screen where possible.
+    fn rebuild_view(&mut self) {
+        let highlighted = self
+            .current_project()
+            .map(|index| self.projects[index].path.clone());
+        let screen_row = self
+            .state
+            .selected
+            .saturating_sub(self.state.list_offset.get());
+        // Ranges are anchored to rows, which move when the view changes
+        self.state.range = None;
+
+        let mut visible: Vec<usize> = (0..self.projects.len())
+            .filter(|&i| self.state.show_empty || self.projects[i].target_info.is_some())
+            .filter(|&i| match &self.state.tag_filter {
+                Some(tag) => self.projects[i].tags.contains(tag),
+                None => true,
+            })
+            .collect();
+        match self.state.sort_mode {
+            SortMode::Scan => {}
+            SortMode::Tag => visible.sort_by_key(|&i| {
+                let tags = &self.projects[i].tags;
+                (tags.is_empty(), tags.first().cloned())
+            }),
+            SortMode::Remote => visible.sort_by_key(|&i| {
+                let remote = &self.projects[i].remote;
+                (remote.is_none(), remote.clone())
+            }),
+            SortMode::ReclaimValue => {
+                visible.sort_by_key(|&i| std::cmp::Reverse(reclaim_value(&self.projects[i])))
+            }
+        }
+
+        let position = highlighted
+            .and_then(|path| visible.iter().position(|&i| self.projects[i].path == path));
+        if let Some(position) = position {
+            self.state
+                .list_offset
+                .set(position.saturating_sub(screen_row));
+        }
+        self.state.selected = position
+            .unwrap_or(self.state.selected)
+            .min(visible.len().saturating_sub(1));
+        self.state
+            .list_state
+            .select((!visible.is_empty()).then_some(self.state.selected));
+        self.state.visible = visible;
+    }
+
+    /// Switches the tag filter to the next tag in use, then back to showing everything
+    fn cycle_tag_filter(&mut self) {
+        let tags: std::collections::BTreeSet<&String> =
+            self.projects.iter().flat_map(|p| &p.tags).collect();
+
+        let next = match &self.state.tag_filter {
+            None => tags.first().copied(),
+            Some(current) => tags
+                .range::<&String, _>((
+                    std::ops::Bound::Excluded(current),
+                    std::ops::Bound::Unbounded,
+                ))
+                .next()
+                .copied(),
+        }
+        .cloned();
+
+        self.state.status_message = match &next {
+            Some(tag) => format!("Showing projects tagged '{}' ('f' for next tag)", tag),
+            None if tags.is_empty() => {
+                "No tags assigned yet. Press '#' to tag a project.".to_string()
+            }
+            None => "Showing all projects".to_string(),
+        };
+        self.state.tag_filter = next;
+        self.rebuild_view();
+    }
+
+    /// Builds the prompt shown while editing tags
+    fn tag_prompt(&self) -> String {
+        let name = self
+            .current_project()
+            .map(|i| self.projects[i].name.as_str())
+            .unwrap_or_default();
+        format!(
+            "Tags for {} (comma-separated), Enter to save, Esc to cancel: {}",
+            name, self.state.tag_input
+        )
+    }
+
+    /// Handles key events while editing the tags of the highlighted project
+    fn handle_edit_tags_mode(&mut self, key: event::KeyEvent) -> Result<(), Box<dyn Error>> {
+        match key.code {
+            KeyCode::Char(c) => self.state.tag_input.push(c),
+            KeyCode::Backspace => {
+                self.state.tag_input.pop();
+            }
+            KeyCode::Enter => {
+                self.state.mode = UIMode::Browse;
+                self.save_tags()?;
+                return Ok(());
+            }
+            KeyCode::Esc => {
+                self.state.mode = UIMode::Browse;
+                self.state.status_message = "Tags unchanged".to_string();
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        self.state.status_message = self.tag_prompt();
+        Ok(())
+    }
+
+    /// Stores the typed tags on the highlighted project and persists them
+    fn save_tags(&mut self) -> Result<(), Box<dyn Error>> {
+        let Some(index) = self.current_project() else {
+            return Ok(());
+        };
+        let tags = TagStore::parse(&self.state.tag_input);
+        self.reload_tag_store();
+        let project = &mut self.projects[index];
+        project.tags = tags.clone();
+        self.state.tag_store.set(project, tags);
+
+        // Policies may give the new tags a different stale threshold
+        let threshold = self.config.stale_threshold_for(project);
+        if let Some(target_info) = project.target_info.as_mut() {
+            TargetFinder::update_stale_status(target_info, threshold)?;
+        }
+
+        match self.state.tag_store.save() {
+            Ok(()) => {
+                self.state.status_message = if project.tags.is_empty() {
+                    format!("Removed tags from {}", project.name)
+                } else {
+                    format!("Tagged {}: {}", project.name, project.tags.join(", "))
+                };
+                self.state.log.info(self.state.status_message.clone());
+            }
+            Err(e) => {
+                self.state.status_message = format!("Could not save tags: {}", e);
+                self.state.log.error(self.state.status_message.clone());
+            }
+        }
+        self.rebuild_view();
+        Ok(())
+    }
+
+    /// Cycles the build mark of a project through broken, passing and unmarked
+    fn cycle_build_health(&mut self, index: usize) {
+        let project = &mut self.projects[index];
+        project.build_health = match project.build_health {
+            None => Some(BuildHealth::Broken),
+            Some(BuildHealth::Broken) => Some(BuildHealth::Passing),
+            Some(BuildHealth::Passing) => None,
+        };
+        let health = project.build_health;
+        let name = project.name.clone();
+        self.reload_tag_store();
+        self.state
+            .tag_store
+            .set_health(&self.projects[index], health);
+
+        match self.state.tag_store.save() {
+            Ok(()) => {
+                self.state.status_message = match health {
+                    Some(health) => format!("Marked {} as {}", name, health.label()),
+                    None => format!("Cleared the build mark of {}", name),
+                };
+                self.state.log.info(self.state.status_message.clone());
+            }
+            Err(e) => {
+                self.state.status_message = format!("Could not save the build mark: {}", e);
+                self.state.log.error(self.state.status_message.clone());
+            }
+        }
+    }
+
+    /// Creates a `.keep-target` file in a project, or removes its keep markers
+    fn toggle_keep_marker(&mut self, index: usize) {
+        let project = &mut self.projects[index];
+        if project.host.is_some() {
+            self.state.status_message =
+                "Keep markers can only be set on local projects".to_string();
+            return;
+        }
+        let fs = vfs::current();
+        if fs.is_virtual() {
+            self.state.status_message = "Keep markers are not changed in a simulation".to_string();
+            return;
+        }
+
+        let outcome = match project.keep_marker() {
+            Some(_) => KEEP_MARKERS
+                .iter()
+                .map(|marker| project.path.join(marker))
+                .filter(|path| fs.exists(path))
+                .try_for_each(|path| fs.remove_file(&path))
+                .map(|_| format!("{} can be cleaned again", project.name)),
+            None => std::fs::write(project.path.join(KEEP_MARKERS[0]), "")
+                .map(|_| format!("Created {} in {}", KEEP_MARKERS[0], project.name)),
+        };
+        if let Err(e) = project.update_status(&self.config) {
+            self.state.log.error(format!(
+                "Could not update {}: {}",
+                project.display_path(),
+                e
+            ));
+        }
+        if self.unavailable_reason(index).is_some() {
+            self.state.selected_projects[index] = false;
+        }
+        self.update_total_freed_space();
+
+        match outcome {
+            Ok(message) => {
+                self.state.status_message = message;
+                self.state.log.info(self.state.status_message.clone());
+            }
+            Err(e) => {
+                self.state.status_message = format!("Could not change the keep marker: {}", e);
+                self.state.log.error(self.state.status_message.clone());
+            }
+        }
+    }
+
+    /// Writes the selected projects to a plan file for `clear-target apply`
+    fn save_plan(&mut self) {
+        if vfs::current().is_virtual() {
+            self.state.status_message = "Plans are not saved in a simulation".to_string();
+            return;
+        }
+        let Some(summary) = Self::plan_summary(&self.state, &self.projects) else {
+            self.state.status_message = "Select projects to save a plan".to_string();
+            return;
+        };
+        let mut plan = format!(
+            "# clear-target plan, {}: {}\n# Apply with `clear-target apply {}`\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M"),
+            summary,
+            PLAN_FILE
+        );
+        let mut remote = 0;
+        for (project, _) in self
+            .projects
+            .iter()
+            .zip(&self.state.selected_projects)
+            .filter(|(_, selected)| **selected)
+        {
+            if project.host.is_some() {
+                remote += 1;
+            } else {
+                plan.push_str(&format!("{}\n", project.path.display()));
+            }
+        }
+
+        self.state.status_message = match std::fs::write(PLAN_FILE, plan) {
+            Ok(()) if remote > 0 => format!(
+                "Saved the plan to {}, without {} remote projects",
+                PLAN_FILE, remote
+            ),
+            Ok(()) => format!(
+                "Saved the plan to {}; run `clear-target apply` to clean it",
+                PLAN_FILE
+            ),
+            Err(e) => format!("Could not save the plan: {}", e),
+        };
+        self.state.log.info(self.state.status_message.clone());
+    }
+
+    /// One-line summary of the selected projects, e.g. `3 projects, 1.2 GB, largest: foo (800 MB)`
+    fn plan_summary(state: &AppState, projects: &[RustProject]) -> Option<String> {
+        let selected: Vec<(&RustProject, u64)> = projects
+            .iter()
+            .zip(&state.selected_projects)
+            .filter(|(_, selected)| **selected)
+            .filter_map(|(project, _)| Some((project, project.target_info.as_ref()?.size_bytes)))
+            .collect();
+        let (largest, largest_size) = selected.iter().max_by_key(|(_, size)| *size)?;
+        Some(format!(
+            "{} projects, {}, largest: {} ({})",
+            selected.len(),
+            format_bytes(selected.iter().map(|(_, size)| size).sum()),
+            largest.name,
+            format_bytes(*largest_size)
+        ))
+    }
+
+    /// Picks up marks recorded by `clear-target mark` since the UI started
+    fn reload_tag_store(&mut self) {
+        if let Ok(store) = TagStore::load() {
+            self.state.tag_store = store;
+        }
+    }
+
+    /// Why a project's target cannot be cleaned right now, if it cannot
+    fn unavailable_reason(&self, index: usize) -> Option<&'static str> {
+        self.projects[index]
+            .target_info
+            .as_ref()
+            .and_then(|t| t.availability.reason())
+    }
+
+    /// Selects every listed stale project of at least `quick_clean_above` and asks to clean them
+    ///
+    /// Replaces the current selection; projects hidden by the tag filter are left out.
+    fn quick_clean(&mut self) {
+        let threshold = self.config.quick_clean_above;
+        self.state.selected_projects.fill(false);
+        for &i in &self.state.visible {
+            let quick = self.projects[i].target_info.as_ref().is_some_and(|t| {
+                t.is_stale && t.size_bytes >= threshold && t.availability.reason().is_none()
+            });
+            self.state.selected_projects[i] = quick;
+        }
+        self.update_total_freed_space();
+
+        let count = self.selected_count();
+        self.state.log.info(format!(
+            "Quick clean selected {} stale projects of at least {}",
+            count,
+            format_bytes(threshold)
+        ));
+        self.request_cleanup(None);
+        if count == 0 {
+            self.state.status_message = format!(
+                "No stale projects of at least {} to clean",
+                format_bytes(threshold)
+            );
+        }
+    }
+
+    /// Number of projects currently selected for cleaning
+    fn selected_count(&self) -> usize {
+        self.state.selected_projects.iter().filter(|&x| *x).count()
+    }
+
+    /// Shows the confirmation prompt, noting whether it must be typed
+    fn show_confirm_prompt(&mut self) {
+        self.state.typed_confirm = self.typed_confirmation_reason().is_some();
+        self.state.status_message = self.confirm_prompt();
+    }
+
+    /// Explains why a live cleanup must be confirmed by typing, if it must
+    fn typed_confirmation_reason(&self) -> Option<String> {
+        // Partial cleanups only remove caches that cargo regenerates
+        if self.config.dry_run || self.state.scope.is_some() {
+            return None;
+        }
+
+        if self.state.total_freed_space > self.config.confirm_typed_above {
+            return Some(format!(
+                "{}, more than {}",
+                format_bytes(self.state.total_freed_space),
+                format_bytes(self.config.confirm_typed_above)
+            ));
+        }
+
+        let orphans = self
+            .projects
+            .iter()
+            .zip(&self.state.selected_projects)
+            .filter(|(project, selected)| **selected && project.orphan)
+            .count();
+        if orphans > 0 {
+            return Some(format!(
+                "includes {} orphaned targets whose projects are gone",
+                orphans
+            ));
+        }
+
+        let includes_fresh = self.projects.iter().enumerate().any(|(i, project)| {
+            self.state.selected_projects[i]
+                && project.target_info.as_ref().is_some_and(|t| !t.is_stale)
+        });
+        includes_fresh.then(|| "includes recently used projects".to_string())
+    }
+
+    /// Handles key events while a typed confirmation is required
+    ///
+    /// Every plain character is part of the answer, so no letter switches modes here.
+    fn handle_typed_confirm(&mut self, key: event::KeyEvent) -> Result<(), Box<dyn Error>> {
+        let modified = key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.back_to_browse("Operation cancelled");
+                return Ok(());
+            }
+            KeyCode::Char(c) if !modified => self.state.confirm_input.push(c),
+            KeyCode::Backspace => {
+                self.state.confirm_input.pop();
+            }
+            KeyCode::Enter => {
+                let input = self.state.confirm_input.trim();
+                if input.eq_ignore_ascii_case("yes") || input == self.selected_count().to_string() {
+                    return self.start_cleanup();
+                }
+                self.state.confirm_input.clear();
+            }
+            KeyCode::Esc => {
+                self.back_to_browse("Operation cancelled");
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        self.show_confirm_prompt();
+        Ok(())
+    }
+
+    /// Asks to clean just the highlighted project, if it can be cleaned
+    ///
+    /// Goes through the same confirmation, checks and countdown as a cleanup of the
+    /// selection; the selection is set aside meanwhile and put back afterwards.
+    fn prompt_clean_one(&mut self, index: usize) {
+        if self.projects[index].target_info.is_none() {
+            self.state.status_message = "This project has no target directory".to_string();
+            return;
+        }
+        if let Some(reason) = self.unavailable_reason(index) {
+            self.state.status_message = format!("Cannot clean this project: target is {}", reason);
+            return;
+        }
+
+        let mut only = vec![false; self.projects.len()];
+        only[index] = true;
+        let selection = std::mem::replace(&mut self.state.selected_projects, only);
+        self.state.saved_selection.get_or_insert(selection);
+        self.update_total_freed_space();
+        self.request_cleanup(None);
+    }
+
+    /// Puts back the selection set aside while a single project was being cleaned
+    fn restore_selection(&mut self) {
+        let Some(saved) = self.state.saved_selection.take() else {
+            return;
+        };
+        // A target cleaned meanwhile can no longer be selected
+        self.state.selected_projects = saved
+            .into_iter()
+            .zip(&self.projects)
+            .map(|(selected, project)| selected && project.target_info.is_some())
+            .collect();
+        self.update_total_freed_space();
+    }
+
+    /// Returns to the project list from a confirmation or a finished cleanup
+    fn back_to_browse(&mut self, message: &str) {
+        self.state.mode = UIMode::Browse;
+        self.state.status_message = message.to_string();
+        self.restore_selection();
+    }
+
+    /// Adds a cleanup result to the totals reported when the UI exits
+    fn record_cleanup(&mut self, result: CleanupResult) {
+        self.state.cleanups.record(result, self.config.dry_run);
+    }
+
+    /// Handles key events while asking whether to quit
+    fn handle_confirm_quit_mode(&mut self, key: event::KeyEvent) -> Result<(), Box<dyn Error>> {
+        match key {
             KeyEvent {
-                code: KeyCode::Enter,
+                code: KeyCode::Char('y') | KeyCode::Char('Y'),
                 ..
-            } => {
-                let selected_count = self.state.selected_projects.iter().filter(|&x| *x).count();
-                if selected_count > 0 {
-                    self.state.mode = UIMode::Confirm;
-                    self.state.status_message = format!(
-                        "Confirm deletion of {} target directories? (y/N)",
-                        selected_count
-                    );
-                } else {
-                    self.state.status_message =
-                        "No projects selected. Use Space to select projects.".to_string();
-                }
             }
-
-            KeyEvent {
+            | KeyEvent {
                 code: KeyCode::Char('c'),
                 modifiers: KeyModifiers::CONTROL,
                 ..
             } => {
-                self.state.mode = UIMode::Complete;
-                return Ok(());
+                self.state.mode = UIMode::Quit;
             }
             KeyEvent {
-                code: KeyCode::Char('q'),
+                code: KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc,
                 ..
             } => {
-                self.state.mode = UIMode::Complete;
-                return Ok(());
+                self.state.mode = UIMode::Browse;
+                self.state.status_message.clear();
             }
             _ => {}
         }
         Ok(())
     }
 
-    /// Handles key events in confirmation mode
-    fn handle_confirm_mode(&mut self, key: event::KeyEvent) -> Result<(), Box<dyn Error>> {
-        match key.code {
-            KeyCode::Char('y') | KeyCode::Char('Y') => {
-                self.state.mode = UIMode::Cleaning;
-                self.state.status_message = "Cleaning target directories...".to_string();
-                self.perform_cleanup()?;
-            }
-            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
-                self.state.mode = UIMode::Browse;
-                self.state.status_message = "Operation cancelled. Use arrow keys to navigate, Space to select, Enter to confirm, 'q' to quit".to_string();
-            }
-            _ => {}
+    /// Starts a confirmed cleanup, giving live runs a grace period to abort first
+    fn start_cleanup(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.config.dry_run || self.config.countdown_secs == 0 || vfs::current().is_virtual() {
+            return self.begin_cleaning();
+        }
+
+        self.state.mode = UIMode::Countdown;
+        self.state.countdown_deadline =
+            Some(Instant::now() + Duration::from_secs(self.config.countdown_secs));
+        self.state.status_message.clear();
+        Ok(())
+    }
+
+    /// Switches to cleaning mode and performs the cleanup
+    fn begin_cleaning(&mut self) -> Result<(), Box<dyn Error>> {
+        self.state.mode = UIMode::Cleaning;
+        self.state.status_message = "Cleaning target directories...".to_string();
+        self.perform_cleanup()
+    }
+
+    /// Handles key events during the countdown before a live cleanup
+    fn handle_countdown_mode(&mut self, key: event::KeyEvent) -> Result<(), Box<dyn Error>> {
+        let abort = matches!(
+            key.code,
+            KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N')
+        ) || (key.code == KeyCode::Char('c')
+            && key.modifiers.contains(KeyModifiers::CONTROL));
+
+        if abort {
+            self.state.countdown_deadline = None;
+            self.back_to_browse("Cleanup aborted. Nothing was deleted.");
+            self.state.log.info("Cleanup aborted during countdown");
         }
         Ok(())
     }
@@ -266,94 +1539,148 @@ impl CleanerTUI {
     fn handle_complete_mode(&mut self, key: event::KeyEvent) -> Result<(), Box<dyn Error>> {
         match key.code {
             KeyCode::Char('d') => {
-                // Toggle between dry run and live mode
-                self.config.dry_run = !self.config.dry_run;
-                let mode = if self.config.dry_run {
-                    "dry run"
-                } else {
-                    "live"
-                };
-                self.state.status_message = format!("Switched to {} mode", mode);
+                self.toggle_dry_run();
             }
             KeyCode::Char('r') => {
                 // Reset to selection mode to choose different projects
-                self.state.mode = UIMode::Browse;
-                self.state.status_message =
-                    "Back to selection mode. Use arrow keys to navigate, Space to select, Enter to confirm, 'q' to quit"
-                    .to_string();
+                self.back_to_browse("Back to selection mode");
             }
             KeyCode::Enter | KeyCode::Char('q') => {
-                return Ok(());
+                self.state.mode = UIMode::Quit;
             }
             _ => {}
         }
         Ok(())
     }
 
-    /// Performs the cleanup operation
-    fn perform_cleanup(&mut self) -> Result<(), Box<dyn Error>> {
-        let total_to_clean = self.state.selected_projects.iter().filter(|&x| *x).count();
-        let mut cleaned = 0;
+    /// Expands or collapses the target breakdown of a project
+    fn set_expanded(&mut self, index: usize, expand: bool) {
+        if !expand {
+            self.state.expanded_entries[index] = None;
+            return;
+        }
 
-        for (i, project) in self.projects.iter().enumerate() {
-            if self.state.selected_projects[i] {
-                if project.target_info.is_some() {
-                    // Simulate cleanup progress
-                    cleaned += 1;
-                    self.state.cleanup_progress = cleaned as f32 / total_to_clean as f32;
+        let Some(target_info) = &self.projects[index].target_info else {
+            self.state.status_message = "This project has no target directory".to_string();
+            return;
+        };
 
-                    // Redraw to update progress
-                    {
-                        let state = &self.state;
-                        let projects = &self.projects;
-                        let config = &self.config;
-                        let total_freed_space = self.state.total_freed_space;
-                        let status_message = &self.state.status_message;
-
-                        self.terminal.draw(|f| {
-                            Self::draw_ui_static(
-                                f,
-                                state,
-                                projects,
-                                config,
-                                total_freed_space,
-                                status_message,
-                            );
-                        })?;
-                    }
+        match TargetFinder::list_target_entries(&target_info.path) {
+            Ok(entries) => self.state.expanded_entries[index] = Some(entries),
+            Err(e) => {
+                self.state.status_message =
+                    format!("Could not read {}: {}", target_info.path.display(), e)
+            }
+        }
+    }
 
-                    // Use our TargetCleaner to perform the cleanup
-                    match TargetCleaner::clean_selected_projects(
-                        &self.projects,
-                        &self.state.selected_projects,
-                        self.config.dry_run,
-                    ) {
-                        Ok(result) => {
-                            if self.config.dry_run {
-                                self.state.status_message = format!(
-                                    "Dry run complete. Would have freed {} of space.",
-                                    format_bytes(result.total_freed)
-                                );
-                            } else {
-                                self.state.status_message = format!(
-                                    "Cleanup complete. Freed {} of space. {} errors occurred.",
-                                    format_bytes(result.total_freed),
-                                    result.errors.len()
-                                );
-
-                                // Show errors if any occurred
-                                for error in &result.errors {
-                                    eprintln!("Error: {}", error);
-                                }
-                            }
-                            self.state.total_freed_space = result.total_freed;
+    /// Measures every file of a project's target, replacing a size estimated from a sample
+    fn measure_exact(&mut self, index: usize) {
+        let project = &mut self.projects[index];
+        if project.host.is_some() {
+            self.state.status_message = "Remote targets cannot be measured from here".to_string();
+            return;
+        }
+        let Some(target_info) = project.target_info.as_mut() else {
+            self.state.status_message = "This project has no target directory".to_string();
+            return;
+        };
+        let estimated = size_label(target_info);
+        match TargetFinder::measure_exact(target_info) {
+            Ok(()) => {
+                let message = format!(
+                    "Measured {} exactly: {} (was {})",
+                    project.name,
+                    size_label(target_info),
+                    estimated
+                );
+                self.state.log.info(message.clone());
+                self.state.status_message = message;
+            }
+            Err(e) => {
+                self.state.status_message =
+                    format!("Could not measure {}: {}", target_info.path.display(), e)
+            }
+        }
+        self.update_total_freed_space();
+        self.rebuild_view();
+    }
+
+    /// Handles key events in the restore view
+    fn handle_restore_mode(&mut self, key: event::KeyEvent) -> Result<(), Box<dyn Error>> {
+        let selected = self.state.trash_list_state.selected();
+        match key.code {
+            KeyCode::Up => {
+                if let Some(i) = selected
+                    && i > 0
+                {
+                    self.state.trash_list_state.select(Some(i - 1));
+                }
+            }
+            KeyCode::Down => {
+                if let Some(i) = selected
+                    && i + 1 < self.state.trash_entries.len()
+                {
+                    self.state.trash_list_state.select(Some(i + 1));
+                }
+            }
+            KeyCode::Enter | KeyCode::Char('r') => {
+                if let Some(i) = selected {
+                    let entry = self.state.trash_entries[i].clone();
+                    match Trash::open().and_then(|trash| trash.restore(&entry)) {
+                        Ok(()) => {
+                            self.state.trash_entries.remove(i);
+                            let len = self.state.trash_entries.len();
+                            self.state
+                                .trash_list_state
+                                .select((len > 0).then(|| i.min(len - 1)));
+                            self.state.status_message =
+                                format!("Restored {}", entry.original_path.display());
+                            self.state.log.info(self.state.status_message.clone());
                         }
                         Err(e) => {
-                            self.state.status_message = format!("Error during cleanup: {}", e);
+                            self.state.status_message = format!("Restore failed: {}", e);
+                            self.state.log.error(self.state.status_message.clone());
                         }
                     }
                 }
             }
+            KeyCode::Esc => {
+                self.state.mode = UIMode::Browse;
+                self.state.status_message.clear();
+            }
+            KeyCode::Char('q') => {
+                self.state.mode = UIMode::Quit;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Performs the cleanup operation through the engine, redrawing as targets are cleaned
+    fn perform_cleanup(&mut self) -> Result<(), Box<dyn Error>> {
+        let command = EngineCommand::CleanRequested {
+            projects: self.projects.clone(),
+            selected: self.state.selected_projects.clone(),
+            options: CleanOptions::from_config(&self.config).with_scope(self.state.scope),
+        };
+        self.state.cleanup_progress = 0.0;
+
+        let engine = self.engine.clone();
+        let mut reclaimed = None;
+        let mut snapshots = 0;
+        match engine.handle(command, &mut |event| self.on_event(event)) {
+            Ok(()) => {
+                if let Some(result) = self.state.finished_cleanup.take() {
+                    self.state.total_freed_space = result.total_freed;
+                    reclaimed = result.space_reclaimed;
+                    snapshots = result.snapshots;
+                }
+            }
+            Err(e) => {
+                self.state.status_message = format!("Error during cleanup: {}", e);
+                self.state.log.error(self.state.status_message.clone());
+            }
         }
 
         // Transition to complete mode
@@ -361,17 +1688,31 @@ impl CleanerTUI {
 
         if self.config.dry_run {
             self.state.status_message = format!(
-                "Dry run complete. Would have freed {} of space. Press Enter or q to exit.",
+                "Dry run complete. Would have freed {} of space.",
                 format_bytes(self.state.total_freed_space)
             );
         } else {
             self.state.status_message = format!(
-                "Cleanup complete. Freed {} of space. Press Enter or q to exit.",
+                "Cleanup complete. Freed {} of space.",
                 format_bytes(self.state.total_freed_space)
             );
+            if let Some(reclaimed) = reclaimed {
+                self.state.status_message.push_str(&format!(
+                    " Free space grew by {} on the affected filesystems.",
+                    format_bytes(reclaimed)
+                ));
+            }
+            if snapshots > 0 {
+                self.state.status_message.push_str(&format!(
+                    " {} local Time Machine snapshots still hold the removed files; \
+                     the space is released as macOS thins them.",
+                    snapshots
+                ));
+            }
         }
 
         self.state.cleanup_progress = 1.0;
+        self.state.log.info(self.state.status_message.clone());
 
         Ok(())
     }
@@ -380,10 +1721,10 @@ impl CleanerTUI {
     fn update_total_freed_space(&mut self) {
         self.state.total_freed_space = 0;
         for (i, project) in self.projects.iter().enumerate() {
-            if self.state.selected_projects[i] {
-                if let Some(ref target_info) = project.target_info {
-                    self.state.total_freed_space += target_info.size_bytes;
-                }
+            if self.state.selected_projects[i]
+                && let Some(ref target_info) = project.target_info
+            {
+                self.state.total_freed_space += target_info.size_bytes;
             }
         }
     }
@@ -410,29 +1751,75 @@ impl CleanerTUI {
         total_freed_space: u64,
         status_message: &str,
     ) {
+        let log_height = if state.show_log || state.show_diagnostics {
+            10
+        } else {
+            0
+        };
+        let banner_height = u16::from(state.scan_limited.is_some());
+        let outer = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(banner_height), Constraint::Min(0)])
+            .split(f.area());
+        if let Some(reason) = &state.scan_limited {
+            let banner = Paragraph::new(format!("⚠ Scan limit reached: {}", reason)).style(
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            );
+            f.render_widget(banner, outer[0]);
+        }
+
+        let plan = matches!(
+            state.mode,
+            UIMode::Browse | UIMode::EditTags | UIMode::ConfirmQuit
+        )
+        .then(|| Self::plan_summary(state, projects))
+        .flatten();
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Min(10),   // Main content
-                Constraint::Length(3), // Status bar
+                Constraint::Min(10),                                    // Main content
+                Constraint::Length(if plan.is_some() { 3 } else { 0 }), // Plan
+                Constraint::Length(log_height),                         // Log pane
+                Constraint::Length(3),                                  // Status bar
             ])
-            .split(f.area());
+            .split(outer[1]);
 
         // Draw main content
         match state.mode {
-            UIMode::Browse | UIMode::Confirm => {
-                Self::draw_project_list_static(f, chunks[0], state, projects)
+            UIMode::Confirm => Self::draw_confirm_static(f, chunks[0], state, projects, config),
+            UIMode::Browse | UIMode::EditTags | UIMode::ConfirmQuit | UIMode::Quit => {
+                Self::draw_project_list_static(f, chunks[0], state, projects, config)
             }
+            UIMode::Countdown => Self::draw_countdown_static(f, chunks[0], state),
             UIMode::Cleaning => Self::draw_progress_static(f, chunks[0], state, status_message),
             UIMode::Complete => {
                 Self::draw_complete_static(f, chunks[0], config, total_freed_space, status_message)
             }
+            UIMode::Restore => Self::draw_restore_static(f, chunks[0], state),
+        }
+
+        if let Some(plan) = plan {
+            let panel = Paragraph::new(plan).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Plan (w to save)"),
+            );
+            f.render_widget(panel, chunks[1]);
+        }
+
+        if state.show_log {
+            Self::draw_log_static(f, chunks[2], state);
+        } else if state.show_diagnostics {
+            Self::draw_diagnostics_static(f, chunks[2], state);
         }
 
         // Draw status bar
         Self::draw_status_bar_static(
             f,
-            chunks[1],
+            chunks[3],
             state,
             projects.len(),
             config,
@@ -444,7 +1831,7 @@ impl CleanerTUI {
     /// Draws the project list
     #[allow(dead_code)]
     fn draw_project_list(&mut self, f: &mut Frame, area: Rect) {
-        Self::draw_project_list_static(f, area, &self.state, &self.projects);
+        Self::draw_project_list_static(f, area, &self.state, &self.projects, &self.config);
     }
 
     /// Static method to draw the project list without borrowing issues
@@ -453,45 +1840,83 @@ impl CleanerTUI {
         area: Rect,
         state: &AppState,
         projects: &[RustProject],
+        config: &Config,
     ) {
+        let name_width = state
+            .visible
+            .iter()
+            .map(|&i| projects[i].name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .min(COMPACT_NAME_WIDTH);
+        let column_widths: Vec<usize> = config
+            .columns
+            .iter()
+            .map(|&column| {
+                state
+                    .visible
+                    .iter()
+                    .map(|&i| column_text(&projects[i], column).chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
         // Create list items from projects
-        let items: Vec<ListItem> = projects
+        let items: Vec<ListItem> = state
+            .visible
             .iter()
-            .enumerate()
-            .map(|(i, project)| {
+            .map(|&i| {
+                let project = &projects[i];
+                if state.compact {
+                    return Self::compact_item(
+                        project,
+                        i,
+                        state,
+                        config,
+                        name_width,
+                        &column_widths,
+                    );
+                }
                 let (name, path, size, age) = if let Some(ref target_info) = project.target_info {
                     let is_stale = target_info.is_stale;
-                    let duration_since = SystemTime::now()
-                        .duration_since(target_info.last_accessed)
-                        .unwrap_or_else(|_| Duration::from_secs(30 * 24 * 60 * 60));
-
-                    let age_display = if duration_since.as_secs() < 86400 {
-                        "Today".to_string()
-                    } else if duration_since.as_secs() < 2 * 86400 {
-                        "Yesterday".to_string()
+                    let age_display = format!(
+                        "{}{}",
+                        age_label(target_info.last_accessed),
+                        metric_note(config.staleness_metric, target_info.metric)
+                    );
+
+                    let status_indicator = if target_info.availability.reason().is_some() {
+                        "⚪"
+                    } else if is_stale {
+                        "🔴"
                     } else {
-                        let days = duration_since.as_secs() / 86400;
-                        if days < 30 {
-                            format!("{} days ago", days)
-                        } else if days < 365 {
-                            format!("{} months ago", days / 30)
-                        } else {
-                            format!("{} years ago", days / 365)
-                        }
+                        "🟢"
                     };
+                    let container_flag =
+                        if project.container_root.is_some() || target_info.foreign_owner.is_some() {
+                            " 🐳"
+                        } else {
+                            ""
+                        };
 
-                    let status_indicator = if is_stale { "🔴" } else { "🟢" };
+                    let breakdown = target_info.breakdown.compact();
+                    let size = if breakdown.is_empty() {
+                        size_label(target_info)
+                    } else {
+                        format!("{} ({})", size_label(target_info), breakdown)
+                    };
 
                     (
-                        format!("{} {}", status_indicator, project.name),
-                        format!("{}", project.path.display()),
-                        format!("{}", format_bytes(target_info.size_bytes)),
+                        format!("{} {}{}", status_indicator, project.name, container_flag),
+                        project.display_path(),
+                        size,
                         age_display,
                     )
                 } else {
                     (
                         format!("🔴 {}", project.name),
-                        format!("{}", project.path.display()),
+                        project.display_path(),
                         "No target".to_string(),
                         "N/A".to_string(),
                     )
@@ -505,29 +1930,222 @@ impl CleanerTUI {
                 };
                 let line_style = Style::default().fg(line_color);
 
-                let content = vec![
-                    Line::from(Span::styled(name, line_style.add_modifier(Modifier::BOLD))),
-                    Line::from(Span::styled(path, line_style)),
+                let mut name_line = vec![Span::styled(
+                    name,
+                    line_style.add_modifier(Modifier::BOLD),
+                )];
+                if !project.tags.is_empty() {
+                    name_line.push(Span::styled(
+                        format!("  [{}]", project.tags.join(", ")),
+                        Style::default().fg(Color::Magenta),
+                    ));
+                }
+                match project.build_health {
+                    Some(BuildHealth::Broken) => name_line.push(Span::styled(
+                        "  ✗ build broken",
+                        Style::default().fg(Color::Red),
+                    )),
+                    Some(BuildHealth::Passing) => name_line.push(Span::styled(
+                        "  ✓ builds",
+                        Style::default().fg(Color::Green),
+                    )),
+                    None => {}
+                }
+
+                let age_caption = match project.target_info.as_ref().map(|t| t.metric) {
+                    Some(StalenessMetric::Atime) => "Last used: ",
+                    _ => "Last built: ",
+                };
+                let mut content = vec![
+                    Line::from(name_line),
+                    Line::from(match &project.remote {
+                        Some(remote) => vec![
+                            Span::styled(path, line_style),
+                            Span::styled(
+                                format!("  {}", remote),
+                                Style::default().fg(Color::DarkGray),
+                            ),
+                        ],
+                        None => vec![Span::styled(path, line_style)],
+                    }),
                     Line::from(vec![
                         Span::styled("Size: ", Style::default()),
                         Span::styled(size, line_style.add_modifier(Modifier::DIM)),
                         Span::raw("  "),
-                        Span::styled("Last accessed: ", Style::default()),
+                        Span::styled(age_caption, Style::default()),
                         Span::styled(age, line_style.add_modifier(Modifier::DIM)),
                     ]),
                 ];
+                if let Some(oldest) = project
+                    .target_info
+                    .as_ref()
+                    .and_then(|t| t.oldest_artifact)
+                {
+                    content.push(Line::from(Span::styled(
+                        format!("Oldest artifact: {}", age_label(oldest)),
+                        Style::default().fg(Color::DarkGray),
+                    )));
+                }
+                if let Some(note) = project
+                    .target_info
+                    .as_ref()
+                    .and_then(|t| t.breakdown.estimate_note())
+                {
+                    content.push(Line::from(Span::styled(
+                        format!("Size {}; 'x' measures every file", note),
+                        Style::default().fg(Color::DarkGray),
+                    )));
+                }
+                if let Some(history) = &project.history {
+                    content.push(Line::from(Span::styled(
+                        history_label(history),
+                        Style::default().fg(Color::DarkGray),
+                    )));
+                }
+
+                if project.orphan {
+                    content.push(Line::from(Span::styled(
+                        "Orphaned target: no Cargo.toml next to it, the project was deleted or moved",
+                        Style::default().fg(Color::Red),
+                    )));
+                }
+                if let Some(reason) = project
+                    .target_info
+                    .as_ref()
+                    .and_then(|t| t.availability.reason())
+                {
+                    content.push(Line::from(Span::styled(
+                        format!("Unavailable: {}", reason),
+                        Style::default().fg(Color::DarkGray),
+                    )));
+                } else if let Some(uid) =
+                    project.target_info.as_ref().and_then(|t| t.foreign_owner)
+                {
+                    content.push(Line::from(Span::styled(
+                        format!(
+                            "Contains files owned by uid {} (container build); deleting needs elevate_command",
+                            uid
+                        ),
+                        Style::default().fg(Color::Red),
+                    )));
+                } else if let Some(root) = &project.container_root {
+                    content.push(Line::from(Span::styled(
+                        format!("Container context: {}", root.display()),
+                        Style::default().fg(Color::Cyan),
+                    )));
+                }
+
+                if let Some(target_info) = &project.target_info {
+                    let device = target_info
+                        .device
+                        .map(|d| format!(" (device {})", device_label(d)))
+                        .unwrap_or_default();
+                    let location = match &target_info.link_target {
+                        Some(link) => Some(format!("Symlink to {}{}", link.display(), device)),
+                        None if target_info.device.is_some() => Some(format!(
+                            "Separate mount{}; only its contents are cleaned",
+                            device
+                        )),
+                        None => None,
+                    };
+                    if let Some(location) = location {
+                        content.push(Line::from(Span::styled(
+                            location,
+                            Style::default().fg(Color::Cyan),
+                        )));
+                    }
+                }
+
+                // Expanded projects list what removing their target would delete
+                if let Some(Some(entries)) = state.expanded_entries.get(i) {
+                    let heading = if config.dry_run {
+                        "Would delete:"
+                    } else {
+                        "Will delete:"
+                    };
+                    content.push(Line::from(Span::styled(
+                        format!("  {}", heading),
+                        Style::default().fg(Color::Cyan),
+                    )));
+                    if entries.is_empty() {
+                        content.push(Line::from("    (empty)"));
+                    }
+                    for entry in entries {
+                        content.push(Line::from(vec![
+                            Span::styled(
+                                format!("    {:>10}  ", format_bytes(entry.size_bytes)),
+                                Style::default().add_modifier(Modifier::DIM),
+                            ),
+                            Span::raw(entry.name.clone()),
+                        ]));
+                    }
+                    if !project.members.is_empty() {
+                        content.push(Line::from(Span::styled(
+                            format!("  Shared by members: {}", project.members.join(", ")),
+                            Style::default().add_modifier(Modifier::DIM),
+                        )));
+                    }
+                    let artifacts = project
+                        .target_info
+                        .as_ref()
+                        .map(|t| t.breakdown.artifacts())
+                        .unwrap_or_default();
+                    if !artifacts.is_empty() {
+                        content.push(Line::from(Span::styled(
+                            format!("  Inside profiles: {}", artifacts),
+                            Style::default().add_modifier(Modifier::DIM),
+                        )));
+                    }
+                    if let Some(target_info) = &project.target_info
+                        && !target_info.cross_builds.is_empty()
+                    {
+                        let builds: Vec<String> = target_info
+                            .cross_builds
+                            .iter()
+                            .map(|build| format!("{} ({})", build.triple, build.tool.label()))
+                            .collect();
+                        content.push(Line::from(Span::styled(
+                            format!("  Cross-compiled: {}", builds.join(", ")),
+                            Style::default().add_modifier(Modifier::DIM),
+                        )));
+                    }
+                    if let Some(target_info) = &project.target_info {
+                        content.extend(companion_line(
+                            "Web outputs",
+                            &target_info.web_outputs,
+                            target_info.web_size,
+                            "/",
+                        ));
+                        content.extend(companion_line(
+                            "Embedded toolchain",
+                            &target_info.embedded_dirs,
+                            target_info.embedded_size,
+                            "/",
+                        ));
+                        content.extend(companion_line(
+                            "Profiling data",
+                            &target_info.profiling_files,
+                            target_info.profiling_size,
+                            "",
+                        ));
+                    }
+                }
 
                 ListItem::new(content)
             })
             .collect();
 
+        let mut title = "Rust Projects".to_string();
+        if let Some(tag) = &state.tag_filter {
+            title.push_str(&format!(" [tag: {}]", tag));
+        }
+        if state.sort_mode != SortMode::Scan {
+            title.push_str(&format!(" [sorted by {}]", state.sort_mode.label()));
+        }
+
         // Create the list widget
         let list = List::new(items)
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title("Rust Projects"),
-            )
+            .block(Block::default().borders(Borders::ALL).title(title))
             .highlight_style(
                 Style::default()
                     .bg(Color::DarkGray)
@@ -536,7 +2154,94 @@ impl CleanerTUI {
 
         // Render the list
         let mut list_state = state.list_state.clone();
+        *list_state.offset_mut() = state.list_offset.get();
         f.render_stateful_widget(list, area, &mut list_state);
+        state.list_offset.set(list_state.offset());
+    }
+
+    /// One-line list item for the compact layout: selection, name and the configured columns
+    fn compact_item(
+        project: &RustProject,
+        i: usize,
+        state: &AppState,
+        config: &Config,
+        name_width: usize,
+        column_widths: &[usize],
+    ) -> ListItem<'static> {
+        let is_selected = state.selected_projects.get(i).copied().unwrap_or(false);
+        let checkbox = if is_selected { "[x]" } else { "[ ]" };
+        let mut name: String = project.name.chars().take(name_width).collect();
+        if project.name.chars().count() > name_width {
+            name.pop();
+            name.push('…');
+        }
+        let staleness_color = match &project.target_info {
+            Some(target_info) if target_info.availability.reason().is_none() => {
+                if target_info.is_stale {
+                    Color::Red
+                } else {
+                    Color::Green
+                }
+            }
+            _ => Color::DarkGray,
+        };
+        let line_style = Style::default().fg(if is_selected {
+            Color::Yellow
+        } else {
+            Color::White
+        });
+
+        let mut row = vec![
+            Span::styled(format!("{} ", checkbox), line_style),
+            Span::styled(
+                format!("{:<width$}", name, width = name_width),
+                line_style.add_modifier(Modifier::BOLD),
+            ),
+        ];
+        for (&column, &width) in config.columns.iter().zip(column_widths) {
+            let text = column_text(project, column);
+            let (text, style) = match column {
+                Column::Size | Column::Files => {
+                    (format!("  {:>width$}", text, width = width), line_style)
+                }
+                Column::Age | Column::Stale => (
+                    format!("  {:<width$}", text, width = width),
+                    Style::default().fg(staleness_color),
+                ),
+                Column::Triples | Column::Remote => (
+                    format!("  {:<width$}", text, width = width),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Column::Path => (format!("  {:<width$}", text, width = width), line_style),
+            };
+            row.push(Span::styled(text, style));
+        }
+
+        let mut content = vec![Line::from(row)];
+        if let Some(Some(entries)) = state.expanded_entries.get(i) {
+            let heading = if config.dry_run {
+                "Would delete:"
+            } else {
+                "Will delete:"
+            };
+            content.push(Line::from(Span::styled(
+                format!("  {}", heading),
+                Style::default().fg(Color::Cyan),
+            )));
+            if entries.is_empty() {
+                content.push(Line::from("    (empty)"));
+            }
+            for entry in entries {
+                content.push(Line::from(vec![
+                    Span::styled(
+                        format!("    {:>10}  ", format_bytes(entry.size_bytes)),
+                        Style::default().add_modifier(Modifier::DIM),
+                    ),
+                    Span::raw(entry.name.clone()),
+                ]));
+            }
+        }
+        ListItem::new(content)
     }
 
     /// Draws the progress view during cleanup
@@ -580,6 +2285,158 @@ impl CleanerTUI {
         f.render_widget(status, chunks[2]);
     }
 
+    /// Static method to draw the list of exactly what a confirmed cleanup will delete
+    fn draw_confirm_static(
+        f: &mut Frame,
+        area: Rect,
+        state: &AppState,
+        projects: &[RustProject],
+        config: &Config,
+    ) {
+        let (mode, color) = match CleanMode::from_config(config) {
+            CleanMode::DryRun => ("DRY RUN: nothing will be removed", Color::Yellow),
+            _ if vfs::current().is_virtual() => (
+                "SIMULATED: removed from an in-memory view only",
+                Color::Yellow,
+            ),
+            CleanMode::Delete => ("LIVE: permanently deleted", Color::Red),
+            CleanMode::Trash => ("LIVE: moved to the trash", Color::Red),
+        };
+        let selected: Vec<&RustProject> = projects
+            .iter()
+            .zip(&state.selected_projects)
+            .filter(|(project, selected)| **selected && project.target_info.is_some())
+            .map(|(project, _)| project)
+            .collect();
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Min(1),
+                Constraint::Length(1),
+            ])
+            .split(Block::default().borders(Borders::ALL).inner(area));
+        f.render_widget(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(color))
+                .title("Confirm cleanup"),
+            area,
+        );
+        f.render_widget(
+            Paragraph::new(mode).style(Style::default().fg(color).add_modifier(Modifier::BOLD)),
+            chunks[0],
+        );
+
+        let items: Vec<ListItem> = selected
+            .iter()
+            .filter_map(|project| {
+                let target_info = project.target_info.as_ref()?;
+                let amount = match state.scope {
+                    None => size_label(target_info),
+                    Some(scope) => scope.label().to_string(),
+                };
+                let mut line = vec![
+                    Span::styled(
+                        format!("{:>12}  ", amount),
+                        Style::default().fg(Color::Magenta),
+                    ),
+                    Span::styled(
+                        format!("{:<24} ", project.name),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(target_info.path.display().to_string()),
+                ];
+                let display_path = project.display_path();
+                if let Some(risk) = state
+                    .risks
+                    .iter()
+                    .find(|risk| risk.project == display_path && risk.score() > 0)
+                {
+                    line.push(Span::styled(
+                        format!("  risk {}: {}", risk.score(), risk.describe()),
+                        Style::default().fg(risk_color(risk.level())),
+                    ));
+                }
+                if let Some(change) = state
+                    .size_changes
+                    .iter()
+                    .find(|change| change.project == display_path)
+                {
+                    line.push(Span::styled(
+                        format!("  ⚠ was {} at scan", format_bytes(change.scanned)),
+                        Style::default().fg(Color::Yellow),
+                    ));
+                }
+                Some(ListItem::new(Line::from(line)))
+            })
+            .collect();
+        f.render_widget(List::new(items), chunks[1]);
+
+        let total = match state.scope {
+            None => format!(
+                "Total: {} in {} target directories",
+                format_bytes(state.total_freed_space),
+                selected.len()
+            ),
+            scope => format!("Total: {}", cleanup_subject(scope, selected.len())),
+        };
+        let total = match state.size_changes.len() {
+            0 => total,
+            changed => format!("{}  ({} changed since the scan)", total, changed),
+        };
+        let total = match state
+            .risks
+            .iter()
+            .filter(|risk| risk.level() == RiskLevel::High)
+            .count()
+        {
+            0 => total,
+            risky => format!(
+                "{}  ⚠ {} high-risk, consider deselecting them",
+                total, risky
+            ),
+        };
+        f.render_widget(
+            Paragraph::new(total).style(Style::default().add_modifier(Modifier::BOLD)),
+            chunks[2],
+        );
+    }
+
+    /// Static method to draw the countdown shown before a live cleanup starts
+    fn draw_countdown_static(f: &mut Frame, area: Rect, state: &AppState) {
+        let remaining = state
+            .countdown_deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+            .unwrap_or_default();
+        let selected_count = state.selected_projects.iter().filter(|&x| *x).count();
+
+        let subject = match state.scope {
+            None => format!(
+                "{} target directories ({})",
+                selected_count,
+                format_bytes(state.total_freed_space)
+            ),
+            scope => cleanup_subject(scope, selected_count),
+        };
+        let text = format!(
+            "Deleting {} in {}s...\n\nPress Esc to abort.",
+            subject,
+            remaining.as_secs() + 1
+        );
+
+        let paragraph = Paragraph::new(text)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("About to delete"),
+            )
+            .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+            .wrap(Wrap { trim: true });
+        f.render_widget(paragraph, area);
+    }
+
     /// Draws the completion view
     #[allow(dead_code)]
     fn draw_complete(&mut self, f: &mut Frame, area: Rect) {
@@ -625,6 +2482,100 @@ impl CleanerTUI {
         f.render_widget(paragraph, area);
     }
 
+    /// Static method to draw the restore view listing trashed target directories
+    fn draw_restore_static(f: &mut Frame, area: Rect, state: &AppState) {
+        let items: Vec<ListItem> = state
+            .trash_entries
+            .iter()
+            .map(|entry| {
+                let removed = chrono::DateTime::<chrono::Local>::from(entry.removed_time())
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string();
+
+                ListItem::new(vec![
+                    Line::from(Span::styled(
+                        entry.original_path.display().to_string(),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    )),
+                    Line::from(vec![
+                        Span::styled("Size: ", Style::default()),
+                        Span::styled(
+                            format_bytes(entry.size_bytes),
+                            Style::default().add_modifier(Modifier::DIM),
+                        ),
+                        Span::raw("  "),
+                        Span::styled("Removed: ", Style::default()),
+                        Span::styled(removed, Style::default().add_modifier(Modifier::DIM)),
+                    ]),
+                ])
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title("Trash"))
+            .highlight_style(
+                Style::default()
+                    .bg(Color::DarkGray)
+                    .add_modifier(Modifier::BOLD),
+            );
+
+        let mut list_state = state.trash_list_state.clone();
+        f.render_stateful_widget(list, area, &mut list_state);
+    }
+
+    /// Static method to draw the pane listing this session's actions
+    fn draw_log_static(f: &mut Frame, area: Rect, state: &AppState) {
+        let entries = state.log.entries();
+        let rows = area.height.saturating_sub(2) as usize;
+        let end = entries.len().saturating_sub(state.log_scroll);
+        let start = end.saturating_sub(rows);
+
+        let lines: Vec<Line> = entries[start..end]
+            .iter()
+            .map(|entry| {
+                let style = if entry.is_error {
+                    Style::default().fg(Color::Red)
+                } else {
+                    Style::default()
+                };
+                Line::from(vec![
+                    Span::styled(
+                        entry.time.format("%H:%M:%S ").to_string(),
+                        Style::default().add_modifier(Modifier::DIM),
+                    ),
+                    Span::styled(entry.message.clone(), style),
+                ])
+            })
+            .collect();
+
+        let title = if state.log_scroll > 0 {
+            format!("Log ({} newer)", state.log_scroll)
+        } else {
+            "Log".to_string()
+        };
+        let paragraph =
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(paragraph, area);
+    }
+
+    /// Lists the projects the scan could not read, with why
+    fn draw_diagnostics_static(f: &mut Frame, area: Rect, state: &AppState) {
+        let lines: Vec<Line> = state
+            .diagnostics
+            .iter()
+            .map(|diagnostic| {
+                Line::from(vec![
+                    Span::raw(format!("{}  ", diagnostic.path.display())),
+                    Span::styled(diagnostic.reason.clone(), Style::default().fg(Color::Red)),
+                ])
+            })
+            .collect();
+        let title = format!("Scan errors ({})", state.diagnostics.len());
+        let paragraph =
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(paragraph, area);
+    }
+
     /// Draws the status bar
     #[allow(dead_code)]
     fn draw_status_bar(&mut self, f: &mut Frame, area: Rect) {
@@ -651,26 +2602,36 @@ impl CleanerTUI {
     ) {
         let selected_count = state.selected_projects.iter().filter(|&x| *x).count();
         let status_text = format!(
-            "{} | Selected: {}/{} | Space to free: {} | {}",
+            "{} | Selected: {}/{} | Space to free: {}",
             if config.dry_run {
-                "Dry Run (press 'd' to toggle live mode)"
+                "Dry Run"
             } else {
-                "Live Mode (press 'd' to toggle dry run)"
+                "Live Mode"
             },
             selected_count,
             project_count,
             format_bytes(total_freed_space),
-            status_message
         );
 
-        let status_bar =
-            Paragraph::new(status_text).style(Style::default().bg(Color::Blue).fg(Color::White));
+        let status_bar = Paragraph::new(vec![
+            Line::from(status_text),
+            Line::from(status_message),
+            Line::from(Span::styled(
+                if state.mode == UIMode::Confirm && state.typed_confirm {
+                    keymap::typed_confirm_hint_line()
+                } else {
+                    keymap::hint_line(&state.mode, config)
+                },
+                Style::default().fg(Color::LightCyan),
+            )),
+        ])
+        .style(Style::default().bg(Color::Blue).fg(Color::White));
         f.render_widget(status_bar, area);
     }
 
     /// Checks if we should exit the application
     fn should_exit(&self) -> bool {
-        matches!(self.state.mode, UIMode::Complete)
+        matches!(self.state.mode, UIMode::Quit)
     }
 
     /// Clears the terminal if configured to do so
@@ -695,24 +2656,3 @@ impl CleanerTUI {
         Ok(())
     }
 }
-
-/// Formats bytes into a human-readable string
-fn format_bytes(bytes: u64) -> String {
-    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
-    const THRESHOLD: f64 = 1024.0;
-
-    if bytes == 0 {
-        return "0 B".to_string();
-    }
-
-    let bytes_f = bytes as f64;
-    let unit_index = (bytes_f.log10() / THRESHOLD.log10()).floor() as usize;
-    let unit_index = unit_index.min(UNITS.len() - 1);
-    let scaled = bytes_f / THRESHOLD.powi(unit_index as i32);
-
-    if unit_index == 0 {
-        format!("{} {}", bytes, UNITS[unit_index])
-    } else {
-        format!("{:.2} {}", scaled, UNITS[unit_index])
-    }
-}
@@ -0,0 +1,702 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Debug;
+use std::fs;
+use std::io;
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// Filesystem every scan, sizing and cleanup goes through, once chosen
+static CURRENT: OnceLock<&'static dyn FileSystem> = OnceLock::new();
+
+/// Filesystem in use: the real one unless another was installed first
+pub fn current() -> &'static dyn FileSystem {
+    *CURRENT.get_or_init(|| &RealFs)
+}
+
+/// Makes every later scan, sizing and cleanup use `fs`, e.g. for `--simulate`
+///
+/// Fails once anything has used the filesystem, so a run never mixes two of them.
+pub fn install(fs: &'static dyn FileSystem) -> Result<(), String> {
+    CURRENT
+        .set(fs)
+        .map_err(|_| "the filesystem is already in use".to_string())
+}
+
+/// What a path is, not following a symlink at its end
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Dir,
+    Symlink,
+}
+
+impl From<fs::FileType> for EntryKind {
+    fn from(file_type: fs::FileType) -> Self {
+        if file_type.is_symlink() {
+            EntryKind::Symlink
+        } else if file_type.is_dir() {
+            EntryKind::Dir
+        } else {
+            EntryKind::File
+        }
+    }
+}
+
+/// The parts of a path's metadata that scanning, sizing and cleaning look at
+#[derive(Debug, Clone, Copy)]
+pub struct Metadata {
+    pub kind: EntryKind,
+    /// Space a file takes on disk; 0 for directories and symlinks
+    pub size: u64,
+    /// Last modification time
+    pub modified: SystemTime,
+}
+
+impl Metadata {
+    pub fn is_dir(&self) -> bool {
+        self.kind == EntryKind::Dir
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.kind == EntryKind::File
+    }
+}
+
+/// One entry of a directory listing
+#[derive(Debug, Clone)]
+pub struct DirEntry {
+    pub path: PathBuf,
+    /// What the entry is, not following a symlink
+    pub kind: EntryKind,
+}
+
+impl DirEntry {
+    /// Last component of the path, e.g. `debug`
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+/// Filesystem operations used to find, size and remove target directories
+///
+/// [`RealFs`] passes them to the disk; [`MemoryFs`] keeps a tree in memory, so the
+/// whole pipeline can run without touching the disk.
+pub trait FileSystem: Debug + Send + Sync {
+    /// Metadata of a path, not following a symlink at its end
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata>;
+
+    /// Metadata of a path, following symlinks
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+
+    /// Entries of a directory, in no particular order
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>>;
+
+    /// Contents of a text file
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+
+    /// Absolute form of a path with every symlink resolved
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+
+    /// Removes a directory and everything in it
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    /// Removes a file or a symlink
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+
+    /// Whether removals only happen in memory
+    ///
+    /// Nothing outside the filesystem (audit log, caches, backups, other hosts) may be
+    /// changed then either, or it would disagree with the disk.
+    fn is_virtual(&self) -> bool {
+        false
+    }
+
+    /// Whether a path exists, following symlinks
+    fn exists(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok()
+    }
+
+    /// Whether a path is a directory, following symlinks
+    fn is_dir(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok_and(|metadata| metadata.is_dir())
+    }
+
+    /// Whether a path is a regular file, following symlinks
+    fn is_file(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok_and(|metadata| metadata.is_file())
+    }
+}
+
+/// The filesystem on disk
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RealFs;
+
+impl RealFs {
+    fn convert(path: &Path, metadata: fs::Metadata) -> Metadata {
+        let kind = EntryKind::from(metadata.file_type());
+        Metadata {
+            kind,
+            size: if kind == EntryKind::File {
+                size_on_disk(path, &metadata)
+            } else {
+                0
+            },
+            modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+        }
+    }
+}
+
+impl FileSystem for RealFs {
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+        Ok(Self::convert(path, fs::symlink_metadata(path)?))
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        Ok(Self::convert(path, fs::metadata(path)?))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>> {
+        Ok(fs::read_dir(path)?
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                Some(DirEntry {
+                    kind: entry.file_type().ok()?.into(),
+                    path: entry.path(),
+                })
+            })
+            .collect())
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::canonicalize(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir_all(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+}
+
+/// Space a file takes on disk
+///
+/// NTFS-compressed and sparse files use less than their length, so Windows asks
+/// the filesystem directly to match what Explorer and the free space reflect.
+#[cfg(windows)]
+fn size_on_disk(path: &Path, metadata: &fs::Metadata) -> u64 {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Foundation::{GetLastError, NO_ERROR};
+    use windows_sys::Win32::Storage::FileSystem::{GetCompressedFileSizeW, INVALID_FILE_SIZE};
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut high = 0u32;
+    // SAFETY: wide is NUL-terminated and high is a valid out-parameter
+    let low = unsafe { GetCompressedFileSizeW(wide.as_ptr(), &mut high) };
+    // INVALID_FILE_SIZE is also a legitimate low word, so only GetLastError tells them apart
+    if low == INVALID_FILE_SIZE && unsafe { GetLastError() } != NO_ERROR {
+        return metadata.len();
+    }
+    (u64::from(high) << 32) | u64::from(low)
+}
+
+/// Space a file takes on disk
+#[cfg(not(windows))]
+fn size_on_disk(_path: &Path, metadata: &fs::Metadata) -> u64 {
+    metadata.len()
+}
+
+/// A file or directory added to a [`MemoryFs`]
+#[derive(Debug, Clone)]
+struct Node {
+    metadata: Metadata,
+    /// Text of a file added with contents
+    content: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryState {
+    nodes: BTreeMap<PathBuf, Node>,
+    /// Paths of the underlying disk removed, along with everything below them
+    removed: BTreeSet<PathBuf>,
+}
+
+impl MemoryState {
+    fn is_removed(&self, path: &Path) -> bool {
+        path.ancestors()
+            .any(|ancestor| self.removed.contains(ancestor))
+    }
+
+    /// Direct children of a directory added in memory
+    fn children(&self, path: &Path) -> Vec<DirEntry> {
+        self.nodes
+            .range::<Path, _>((Bound::Excluded(path), Bound::Unbounded))
+            .take_while(|(child, _)| child.starts_with(path))
+            .filter(|(child, _)| child.parent() == Some(path))
+            .map(|(child, node)| DirEntry {
+                path: child.clone(),
+                kind: node.metadata.kind,
+            })
+            .collect()
+    }
+}
+
+/// Filesystem kept in memory, optionally laid over the disk
+///
+/// Created empty it holds only what is added to it, e.g. a few projects for a test.
+/// Laid over the disk ([`MemoryFs::overlay`]) it reads through to the real files, and
+/// removing hides paths instead of deleting them, so a cleanup can be simulated end to
+/// end on real data.
+#[derive(Debug, Default)]
+pub struct MemoryFs {
+    /// Filesystem read through for paths not added in memory
+    base: Option<RealFs>,
+    state: Mutex<MemoryState>,
+}
+
+impl MemoryFs {
+    /// Empty filesystem
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Filesystem showing the disk, where removals only happen in memory
+    pub fn overlay() -> Self {
+        Self {
+            base: Some(RealFs),
+            ..Self::default()
+        }
+    }
+
+    /// Adds a directory, along with any missing parent directories
+    #[allow(dead_code)]
+    pub fn add_dir(&self, path: impl AsRef<Path>, modified: SystemTime) {
+        self.add(path.as_ref(), EntryKind::Dir, 0, None, modified);
+    }
+
+    /// Adds a file taking `size` bytes, along with its parent directories
+    #[allow(dead_code)]
+    pub fn add_file(&self, path: impl AsRef<Path>, size: u64, modified: SystemTime) {
+        self.add(path.as_ref(), EntryKind::File, size, None, modified);
+    }
+
+    /// Adds a text file, along with its parent directories
+    #[allow(dead_code)]
+    pub fn add_text(&self, path: impl AsRef<Path>, content: &str, modified: SystemTime) {
+        let size = content.len() as u64;
+        self.add(
+            path.as_ref(),
+            EntryKind::File,
+            size,
+            Some(content.to_string()),
+            modified,
+        );
+    }
+
+    fn add(
+        &self,
+        path: &Path,
+        kind: EntryKind,
+        size: u64,
+        content: Option<String>,
+        modified: SystemTime,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        for parent in path.ancestors().skip(1) {
+            state
+                .nodes
+                .entry(parent.to_path_buf())
+                .or_insert_with(|| Node {
+                    metadata: Metadata {
+                        kind: EntryKind::Dir,
+                        size: 0,
+                        modified,
+                    },
+                    content: None,
+                });
+        }
+        state.removed.remove(path);
+        state.nodes.insert(
+            path.to_path_buf(),
+            Node {
+                metadata: Metadata {
+                    kind,
+                    size,
+                    modified,
+                },
+                content,
+            },
+        );
+    }
+
+    fn not_found(path: &Path) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} does not exist", path.display()),
+        )
+    }
+
+    /// Removes a path from memory, hiding it on the disk below
+    fn remove(&self, path: &Path, dir: bool) -> io::Result<()> {
+        let metadata = self.symlink_metadata(path)?;
+        if metadata.is_dir() != dir {
+            return Err(io::Error::other(if dir {
+                format!("{} is not a directory", path.display())
+            } else {
+                format!("{} is a directory", path.display())
+            }));
+        }
+        let mut state = self.state.lock().unwrap();
+        state.nodes.retain(|node, _| !node.starts_with(path));
+        if self.base.is_some() {
+            state.removed.insert(path.to_path_buf());
+        }
+        Ok(())
+    }
+}
+
+impl FileSystem for MemoryFs {
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let state = self.state.lock().unwrap();
+        if state.is_removed(path) {
+            return Err(Self::not_found(path));
+        }
+        if let Some(node) = state.nodes.get(path) {
+            return Ok(node.metadata);
+        }
+        drop(state);
+        match &self.base {
+            Some(base) => base.symlink_metadata(path),
+            None => Err(Self::not_found(path)),
+        }
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let metadata = self.symlink_metadata(path)?;
+        match &self.base {
+            // A link into a removed directory leads nowhere
+            Some(base) if metadata.kind == EntryKind::Symlink => {
+                self.canonicalize(path)?;
+                base.metadata(path)
+            }
+            _ => Ok(metadata),
+        }
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>> {
+        if !self.metadata(path)?.is_dir() {
+            return Err(io::Error::other(format!(
+                "{} is not a directory",
+                path.display()
+            )));
+        }
+        let state = self.state.lock().unwrap();
+        let mut entries = state.children(path);
+        if let Some(base) = &self.base
+            && !state.nodes.contains_key(path)
+        {
+            entries.extend(
+                base.read_dir(path)?
+                    .into_iter()
+                    .filter(|entry| !state.is_removed(&entry.path)),
+            );
+        }
+        Ok(entries)
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        let state = self.state.lock().unwrap();
+        if state.is_removed(path) {
+            return Err(Self::not_found(path));
+        }
+        match state.nodes.get(path) {
+            Some(Node {
+                content: Some(content),
+                ..
+            }) => Ok(content.clone()),
+            Some(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} has no text contents", path.display()),
+            )),
+            None => match &self.base {
+                Some(base) => base.read_to_string(path),
+                None => Err(Self::not_found(path)),
+            },
+        }
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        let state = self.state.lock().unwrap();
+        if state.is_removed(path) {
+            return Err(Self::not_found(path));
+        }
+        if state.nodes.contains_key(path) {
+            return Ok(path.to_path_buf());
+        }
+        let resolved = match &self.base {
+            Some(base) => base.canonicalize(path)?,
+            None => return Err(Self::not_found(path)),
+        };
+        if state.is_removed(&resolved) {
+            return Err(Self::not_found(path));
+        }
+        Ok(resolved)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.remove(path, true)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.remove(path, false)
+    }
+
+    fn is_virtual(&self) -> bool {
+        true
+    }
+}
+
+/// An entry met while walking a directory tree
+#[derive(Debug, Clone)]
+pub struct WalkEntry {
+    pub path: PathBuf,
+    /// Levels below the root of the walk, which is at depth 0
+    pub depth: usize,
+    /// What the entry is, after following a symlink when links are followed
+    pub kind: EntryKind,
+}
+
+/// Why part of a tree was left out of a walk
+#[derive(Debug)]
+pub enum WalkError {
+    /// A followed symlink leads back to a directory containing it
+    Loop { path: PathBuf, ancestor: PathBuf },
+    /// A directory could not be read
+    Io { path: PathBuf, error: io::Error },
+}
+
+/// Depth-first walk of a directory tree on a [`FileSystem`]
+///
+/// A directory's contents are visited before its next sibling, so the entries below a
+/// directory follow it without interruption.
+pub struct Walk<'a> {
+    fs: &'a dyn FileSystem,
+    follow_links: bool,
+    max_depth: usize,
+    /// Entries found but not visited yet, the next one last
+    stack: Vec<WalkEntry>,
+    /// Directory visited last, read before going on unless it was skipped
+    descend: Option<WalkEntry>,
+    /// Resolved paths of the directories leading to the current one, for loop detection
+    ancestors: Vec<(usize, PathBuf)>,
+    /// Why the root itself cannot be walked
+    root_error: Option<WalkError>,
+}
+
+impl<'a> Walk<'a> {
+    /// Walks everything below `root`, starting with the root itself
+    pub fn new(fs: &'a dyn FileSystem, root: &Path) -> Self {
+        let (stack, root_error) = match fs.metadata(root) {
+            Ok(metadata) => (
+                vec![WalkEntry {
+                    path: root.to_path_buf(),
+                    depth: 0,
+                    kind: metadata.kind,
+                }],
+                None,
+            ),
+            Err(error) => (
+                Vec::new(),
+                Some(WalkError::Io {
+                    path: root.to_path_buf(),
+                    error,
+                }),
+            ),
+        };
+        Self {
+            fs,
+            follow_links: false,
+            max_depth: usize::MAX,
+            stack,
+            descend: None,
+            ancestors: Vec::new(),
+            root_error,
+        }
+    }
+
+    /// Descends into symlinked directories, reporting links that lead back up as loops
+    pub fn follow_links(mut self, follow_links: bool) -> Self {
+        self.follow_links = follow_links;
+        self
+    }
+
+    /// Visits nothing deeper than `depth` levels below the root
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Leaves out the contents of the directory visited last
+    pub fn skip_current_dir(&mut self) {
+        self.descend = None;
+    }
+}
+
+impl Iterator for Walk<'_> {
+    type Item = Result<WalkEntry, WalkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(error) = self.root_error.take() {
+            return Some(Err(error));
+        }
+        if let Some(dir) = self.descend.take() {
+            match self.fs.read_dir(&dir.path) {
+                Ok(entries) => {
+                    for entry in entries.into_iter().rev() {
+                        let kind = match entry.kind {
+                            EntryKind::Symlink if self.follow_links => self
+                                .fs
+                                .metadata(&entry.path)
+                                .map_or(EntryKind::Symlink, |metadata| metadata.kind),
+                            kind => kind,
+                        };
+                        self.stack.push(WalkEntry {
+                            path: entry.path,
+                            depth: dir.depth + 1,
+                            kind,
+                        });
+                    }
+                }
+                Err(error) => {
+                    return Some(Err(WalkError::Io {
+                        path: dir.path,
+                        error,
+                    }));
+                }
+            }
+        }
+
+        let entry = self.stack.pop()?;
+        if entry.kind == EntryKind::Dir && entry.depth < self.max_depth {
+            if self.follow_links {
+                self.ancestors.retain(|(depth, _)| *depth < entry.depth);
+                let resolved = self
+                    .fs
+                    .canonicalize(&entry.path)
+                    .unwrap_or_else(|_| entry.path.clone());
+                if let Some((_, ancestor)) = self.ancestors.iter().find(|(_, a)| *a == resolved) {
+                    return Some(Err(WalkError::Loop {
+                        ancestor: ancestor.clone(),
+                        path: entry.path,
+                    }));
+                }
+                self.ancestors.push((entry.depth, resolved));
+            }
+            self.descend = Some(entry.clone());
+        }
+        Some(Ok(entry))
+    }
+}
+
+/// Memory filesystem shared by the tests that run the whole pipeline through [`current`]
+#[cfg(test)]
+pub fn test_fs() -> &'static MemoryFs {
+    static FS: OnceLock<&'static MemoryFs> = OnceLock::new();
+    FS.get_or_init(|| {
+        let fs: &'static MemoryFs = Box::leak(Box::new(MemoryFs::new()));
+        install(fs).expect("no test uses the real filesystem through vfs::current");
+        fs
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(entries: Vec<DirEntry>) -> Vec<String> {
+        let mut names: Vec<String> = entries.iter().map(DirEntry::name).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn adds_parent_directories() {
+        let fs = MemoryFs::new();
+        fs.add_file("/p/target/debug/app", 10, SystemTime::UNIX_EPOCH);
+        assert!(fs.is_dir(Path::new("/p/target/debug")));
+        assert!(fs.is_file(Path::new("/p/target/debug/app")));
+        assert_eq!(names(fs.read_dir(Path::new("/p")).unwrap()), ["target"]);
+        assert_eq!(
+            fs.metadata(Path::new("/p/target/debug/app")).unwrap().size,
+            10
+        );
+    }
+
+    #[test]
+    fn lists_direct_children_only() {
+        let fs = MemoryFs::new();
+        fs.add_file("/p/a/deep", 1, SystemTime::UNIX_EPOCH);
+        fs.add_file("/p/b", 1, SystemTime::UNIX_EPOCH);
+        fs.add_file("/pa/c", 1, SystemTime::UNIX_EPOCH);
+        assert_eq!(names(fs.read_dir(Path::new("/p")).unwrap()), ["a", "b"]);
+    }
+
+    #[test]
+    fn removes_directories_with_their_contents() {
+        let fs = MemoryFs::new();
+        fs.add_file("/p/target/debug/app", 10, SystemTime::UNIX_EPOCH);
+        fs.add_text("/p/Cargo.toml", "[package]", SystemTime::UNIX_EPOCH);
+        assert!(fs.remove_file(Path::new("/p/target")).is_err());
+        fs.remove_dir_all(Path::new("/p/target")).unwrap();
+        assert!(!fs.exists(Path::new("/p/target/debug/app")));
+        assert_eq!(names(fs.read_dir(Path::new("/p")).unwrap()), ["Cargo.toml"]);
+        assert_eq!(
+            fs.read_to_string(Path::new("/p/Cargo.toml")).unwrap(),
+            "[package]"
+        );
+    }
+
+    #[test]
+    fn walks_depth_first_and_skips_directories() {
+        let fs = MemoryFs::new();
+        fs.add_file("/r/a/x", 1, SystemTime::UNIX_EPOCH);
+        fs.add_file("/r/b/y", 1, SystemTime::UNIX_EPOCH);
+        let mut walk = Walk::new(&fs, Path::new("/r"));
+        let mut visited = Vec::new();
+        while let Some(entry) = walk.next() {
+            let entry = entry.unwrap();
+            if entry.path.ends_with("a") {
+                walk.skip_current_dir();
+            }
+            visited.push((entry.path, entry.depth));
+        }
+        assert_eq!(
+            visited,
+            [
+                (PathBuf::from("/r"), 0),
+                (PathBuf::from("/r/a"), 1),
+                (PathBuf::from("/r/b"), 1),
+                (PathBuf::from("/r/b/y"), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_a_missing_root() {
+        let fs = MemoryFs::new();
+        let mut walk = Walk::new(&fs, Path::new("/missing"));
+        assert!(matches!(walk.next(), Some(Err(WalkError::Io { .. }))));
+        assert!(walk.next().is_none());
+    }
+}